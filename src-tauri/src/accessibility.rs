@@ -0,0 +1,94 @@
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Emitter, State, Window};
+
+use crate::{api, settings, AppState, JobStatus};
+
+// Screen-reader announcements. Streamed text arrives in small pieces that a
+// live region reads out one by one, or not at all, so the window also gets a
+// few plain state changes to announce: the job started, how far it has got,
+// and that it finished, with a short summary. Sent on `accessibility-{label}`.
+
+// Progress is announced in steps of this many percent
+const PROGRESS_STEP: usize = 25;
+// Characters of the result read out with the completion
+const PREVIEW_CHARS: usize = 80;
+
+#[derive(Clone, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+enum Announcement {
+    #[serde(rename_all = "camelCase")]
+    Started { source_lang: String, target_lang: String },
+    Progress { percent: usize },
+    #[serde(rename_all = "camelCase")]
+    Completed {
+        status: JobStatus,
+        characters: usize,
+        // Start of the result, cut at a word boundary
+        preview: String,
+        // Move focus to the result, see the `focusResult` setting
+        focus_result: bool,
+    },
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AccessibilityEvent {
+    #[serde(flatten)]
+    announcement: Announcement,
+    // See api.rs
+    api_version: u32,
+}
+
+fn announce(window: &Window, announcement: Announcement) {
+    let event_name = format!("accessibility-{}", window.label());
+    window.emit(&event_name, AccessibilityEvent { announcement, api_version: api::API_VERSION }).unwrap_or(());
+}
+
+pub fn started(window: &Window, source_lang: &str, target_lang: &str) {
+    announce(window, Announcement::Started { source_lang: source_lang.to_string(), target_lang: target_lang.to_string() });
+}
+
+/// Announces progress whenever another step is reached, but not 0% or 100%
+/// (the start and completion announcements cover those).
+#[derive(Default)]
+pub struct ProgressAnnouncer {
+    last: usize,
+}
+
+impl ProgressAnnouncer {
+    pub fn update(&mut self, window: &Window, done: usize, total: usize) {
+        if total == 0 {
+            return;
+        }
+        let percent = done * 100 / total / PROGRESS_STEP * PROGRESS_STEP;
+        if percent > self.last && percent < 100 {
+            self.last = percent;
+            announce(window, Announcement::Progress { percent });
+        }
+    }
+}
+
+pub fn completed(window: &Window, state: &AppState, output: &str) {
+    let status = if state.is_cancelled.load(Ordering::Relaxed) { JobStatus::Cancelled } else { JobStatus::Completed };
+    let output = output.trim();
+    let preview = match output.char_indices().nth(PREVIEW_CHARS) {
+        Some((end, _)) => {
+            let cut = &output[..end];
+            let cut = cut.rfind(char::is_whitespace).map_or(cut, |space| &cut[..space]);
+            format!("{}…", cut.trim_end())
+        }
+        None => output.to_string(),
+    };
+    let focus_result = state.settings.lock().unwrap().focus_result;
+    announce(window, Announcement::Completed { status, characters: output.chars().count(), preview, focus_result });
+}
+
+/// Turns automatic focus of the result on or off and saves the setting.
+#[tauri::command]
+pub async fn set_focus_result(enabled: bool, app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let mut settings = state.settings.lock().unwrap();
+    settings.focus_result = enabled;
+    settings::save(&app, &settings)?;
+    app.emit("settings-changed", &*settings).unwrap_or(());
+    Ok(())
+}
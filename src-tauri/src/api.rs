@@ -0,0 +1,82 @@
+// Version of the command/event interface, for consumers outside the bundled
+// frontend (browser extension, scripts). API_VERSION goes up when a command or
+// event field is removed or changes meaning; additions are announced as
+// capabilities instead, so consumers can check for a feature rather than a
+// release. Streamed translation events carry API_VERSION as well.
+
+pub const API_VERSION: u32 = 1;
+
+// Optional features of this build, by the command or event that provides them
+const CAPABILITIES: &[&str] = &[
+    // `translation-chunk-error-{label}` during a translation
+    "chunkErrors",
+    // `translation-queued-{label}` while waiting for an earlier job
+    "jobQueue",
+    // `translation-security-warning-{label}`, see prompt_guard.rs
+    "securityWarnings",
+    // `get_job_report` with load/prompt/generation timings
+    "jobTimings",
+    // `estimate_job`
+    "estimates",
+    // Reviewable segments of project files
+    "segments",
+    // `markup` translate option
+    "markupStreaming",
+    // `inputCleanup` translate option
+    "inputCleanup",
+    // `normalizeUnicode` translate option
+    "normalizeUnicode",
+    // `status` and `output_length` on the final translation event
+    "jobStatus",
+    // `model-switch` while a job changes the loaded model
+    "modelSwitchEvents",
+    // `accessibility-{label}` announcements and `set_focus_result`
+    "accessibilityEvents",
+    // `get_os_preferences` and `os-preferences-changed`
+    "osPreferences",
+    // `soundCues` setting and `preview_sound_cue`
+    "soundCues",
+    // `next_target_language` and the `targetCycle` setting
+    "targetCycle",
+    // `get_recent_language_pairs`
+    "recentLanguagePairs",
+    // Tab-separated spreadsheet cells are translated cell by cell
+    "tableCells",
+    // `download_model` with `model-download-progress`
+    "modelDownloads",
+    // `environment` on history entries
+    "historyEnvironment",
+    // `add_custom_model` / `remove_custom_model`; `translate` accepts their names
+    "customModels",
+    // `diff_translations` between history entries
+    "translationDiff",
+    // `list_models` scanning the model search paths
+    "listModels",
+    // `get_model_registry` and `model-registry-reloaded` (models.json)
+    "modelRegistry",
+    // `completionHooks` setting and per-job `hooks` on documents and batches
+    "completionHooks",
+    // `publish` setting: MQTT topic / named pipe output
+    "publish",
+    // `sha256` in models.json, the `verifying` model switch phase and `model-check-failed`
+    "modelChecksums",
+    // `download_from_hf`, and resuming interrupted model downloads
+    "hfDownloads",
+    // `gpuLayers` setting and `get_gpu_info`
+    "gpuOffload",
+    // `gpuDevices`, `splitMode` and `mainGpu` in models.json
+    "multiGpu",
+];
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiVersion {
+    api_version: u32,
+    app_version: &'static str,
+    capabilities: &'static [&'static str],
+}
+
+#[tauri::command]
+pub async fn get_api_version() -> Result<ApiVersion, String> {
+    Ok(ApiVersion { api_version: API_VERSION, app_version: env!("CARGO_PKG_VERSION"), capabilities: CAPABILITIES })
+}
@@ -0,0 +1,291 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, State, Window};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::documents::{self, FileJob};
+use crate::export::{self, OutputTemplate};
+use crate::hooks::{self, CompletionHooks, FileJobKind, FinishedJob, JobOutcome};
+use crate::{priority, AppState};
+
+// Batch jobs: translate every supported file in a folder or ZIP archive as a
+// sub-job of one document job. The output mirrors the input's folder structure
+// and can optionally be packed back into a ZIP.
+
+// Text formats read_document can handle
+const SUPPORTED_EXTENSIONS: &[&str] = &["txt", "text", "md", "markdown", "rst", "adoc"];
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BatchStatus {
+    Queued,
+    Translating,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchEntry {
+    // Relative to the folder or archive root, always '/'-separated
+    path: String,
+    output: String,
+    status: BatchStatus,
+    error: Option<String>,
+}
+
+/// Overall state of a batch; sent as `batch-manifest` when the job starts and
+/// when it ends, and returned by `translate_batch`.
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchManifest {
+    source: String,
+    output_dir: String,
+    archive: Option<String>,
+    files: Vec<BatchEntry>,
+}
+
+// Sent as `batch-progress` whenever a file changes status
+#[derive(Clone, serde::Serialize)]
+struct BatchProgress<'a> {
+    index: usize,
+    total: usize,
+    entry: &'a BatchEntry,
+}
+
+pub fn is_supported(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+pub fn is_zip(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+// "docs/intro.md" on every platform
+fn archive_name(relative: &Path) -> String {
+    relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// Supported files below `root`, as paths relative to it, in a stable order
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("Failed to read {:?}: {}", dir, e))?;
+    let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|e| e.path())).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if is_supported(&path) {
+            if let Ok(relative) = path.strip_prefix(root) {
+                files.push(relative.to_path_buf());
+            }
+        }
+    }
+    Ok(())
+}
+
+// Extracts the supported files of an archive into `dir`. Entries that would
+// land outside of it ("../" paths) are ignored.
+fn extract_zip(archive_path: &Path, dir: &Path) -> Result<(), String> {
+    let file = File::open(archive_path).map_err(|e| format!("Failed to open {:?}: {}", archive_path, e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("Invalid ZIP {:?}: {}", archive_path, e))?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        let Some(relative) = entry.enclosed_name() else {
+            continue;
+        };
+        if entry.is_dir() || !is_supported(&relative) {
+            continue;
+        }
+        let target = dir.join(&relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+        }
+        let mut out = File::create(&target).map_err(|e| format!("Failed to create {:?}: {}", target, e))?;
+        std::io::copy(&mut entry, &mut out).map_err(|e| format!("Failed to extract {:?}: {}", relative, e))?;
+    }
+    Ok(())
+}
+
+// Packs the given files (relative to `root`) into a new archive
+fn write_zip(root: &Path, files: &[PathBuf], archive_path: &Path) -> Result<(), String> {
+    let file = File::create(archive_path).map_err(|e| format!("Failed to create {:?}: {}", archive_path, e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for relative in files {
+        let contents = std::fs::read(root.join(relative)).map_err(|e| format!("Failed to read {:?}: {}", relative, e))?;
+        zip.start_file(archive_name(relative), options).map_err(|e| e.to_string())?;
+        zip.write_all(&contents).map_err(|e| e.to_string())?;
+    }
+    zip.finish().map_err(|e| format!("Failed to write {:?}: {}", archive_path, e))?;
+    Ok(())
+}
+
+// Scratch folder for an extracted archive
+fn staging_dir() -> PathBuf {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    std::env::temp_dir().join(format!("spark-batch-{}-{}", std::process::id(), nanos))
+}
+
+// "books" / "books.zip" + "Japanese" -> "books.japanese" next to the input
+fn default_output_dir(input: &Path, target_lang: &str) -> PathBuf {
+    let stem = input.file_stem().and_then(|s| s.to_str()).unwrap_or("batch");
+    let suffix = target_lang.trim().to_lowercase().replace(' ', "-");
+    input.with_file_name(format!("{}.{}", stem, suffix))
+}
+
+fn update(window: &Window, manifest: &mut BatchManifest, index: usize, status: BatchStatus, error: Option<String>) {
+    let entry = &mut manifest.files[index];
+    entry.status = status;
+    entry.error = error;
+    let progress = BatchProgress { index, total: manifest.files.len(), entry: &manifest.files[index] };
+    window.emit("batch-progress", progress).unwrap_or(());
+}
+
+fn run_batch(
+    job: &FileJob,
+    root: &Path,
+    output_dir: &Path,
+    file_name: &str,
+    manifest: &mut BatchManifest,
+    state: &AppState,
+    window: &Window,
+) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_files(root, root, &mut files).unwrap_or_else(|e| eprintln!("{}", e));
+    let outputs: Vec<PathBuf> = files
+        .iter()
+        .map(|relative| export::output_path(file_name, relative, job.source_lang, job.target_lang, job.template))
+        .collect();
+    manifest.files = files
+        .iter()
+        .zip(&outputs)
+        .map(|(relative, output)| BatchEntry {
+            path: archive_name(relative),
+            output: output_dir.join(output).to_string_lossy().into_owned(),
+            status: BatchStatus::Queued,
+            error: None,
+        })
+        .collect();
+    window.emit("batch-manifest", &*manifest).unwrap_or(());
+
+    let mut written = Vec::new();
+    for (index, (relative, output)) in files.iter().zip(&outputs).enumerate() {
+        if state.is_cancelled.load(Ordering::Relaxed) {
+            update(window, manifest, index, BatchStatus::Cancelled, None);
+            continue;
+        }
+        update(window, manifest, index, BatchStatus::Translating, None);
+        match documents::translate_file(job, &root.join(relative), &output_dir.join(output), state, window) {
+            Ok(()) => {
+                update(window, manifest, index, BatchStatus::Done, None);
+                written.push(output.clone());
+            }
+            Err(_) if state.is_cancelled.load(Ordering::Relaxed) => {
+                update(window, manifest, index, BatchStatus::Cancelled, None);
+            }
+            Err(e) => update(window, manifest, index, BatchStatus::Failed, Some(e)),
+        }
+    }
+    written
+}
+
+/// Translates every supported text file in a folder or ZIP archive, mirroring
+/// the folder structure under `output_dir` (default: `{name}.{target}` next to
+/// the input). Each file is a sub-job reported through `batch-progress`; files
+/// that fail are recorded in the manifest and the batch carries on. With
+/// `repackage`, the translated files are also packed into `{output_dir}.zip`.
+/// `hooks` replace the configured completion hooks for this job (see hooks.rs).
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn translate_batch(
+    path: String,
+    output_dir: Option<String>,
+    source_lang: String,
+    target_lang: String,
+    model_id: String,
+    template: Option<OutputTemplate>,
+    file_name: Option<String>,
+    repackage: Option<bool>,
+    hooks: Option<CompletionHooks>,
+    state: State<'_, AppState>,
+    window: Window,
+) -> Result<BatchManifest, String> {
+    let input = PathBuf::from(&path);
+    if !input.is_dir() && !is_zip(&input) {
+        return Err(format!("{:?} is neither a folder nor a ZIP archive", input));
+    }
+    let job = FileJob {
+        source_lang: &source_lang,
+        target_lang: &target_lang,
+        model_id: &model_id,
+        include_summary: false,
+        input_cleanup: false,
+        template: template.unwrap_or_default(),
+        on_segment: None,
+        project_id: None,
+    };
+    let output_dir = output_dir.map(PathBuf::from).unwrap_or_else(|| default_output_dir(&input, &target_lang));
+    let file_name = file_name.as_deref().unwrap_or(export::DEFAULT_FILE_NAME);
+    let mut manifest = BatchManifest {
+        source: path.clone(),
+        output_dir: output_dir.to_string_lossy().into_owned(),
+        archive: None,
+        files: Vec::new(),
+    };
+
+    // Waits for earlier jobs and resets the cancellation flag
+    let _turn = state.jobs.start(&state, &window, priority::JobKind::Background).await?;
+    // No await points below, so the guard is dropped on this thread
+    let _priority = priority::PriorityGuard::new(&state, priority::JobKind::Background);
+
+    let written = if input.is_dir() {
+        Ok(run_batch(&job, &input, &output_dir, file_name, &mut manifest, &state, &window))
+    } else {
+        let staging = staging_dir();
+        let result = extract_zip(&input, &staging)
+            .map(|_| run_batch(&job, &staging, &output_dir, file_name, &mut manifest, &state, &window));
+        let _ = std::fs::remove_dir_all(&staging);
+        result
+    };
+    let result = written.and_then(|written| {
+        if repackage.unwrap_or(false) && !written.is_empty() {
+            let mut archive = output_dir.clone().into_os_string();
+            archive.push(".zip");
+            let archive = PathBuf::from(archive);
+            write_zip(&output_dir, &written, &archive)?;
+            manifest.archive = Some(archive.to_string_lossy().into_owned());
+        }
+        Ok(())
+    });
+
+    let finished = FinishedJob {
+        kind: FileJobKind::Batch,
+        status: JobOutcome::of(&state, &result),
+        input: path,
+        output: result.is_ok().then(|| manifest.archive.clone().unwrap_or_else(|| manifest.output_dir.clone())),
+        source_lang,
+        target_lang,
+        model_id,
+        error: result.as_ref().err().cloned(),
+        files: Some(manifest.files.len()),
+        failed_files: Some(manifest.files.iter().filter(|entry| entry.status == BatchStatus::Failed).count()),
+    };
+    hooks::job_finished(&state, hooks, finished);
+    result?;
+    window.emit("batch-manifest", &manifest).unwrap_or(());
+    Ok(manifest)
+}
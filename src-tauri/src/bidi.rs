@@ -0,0 +1,39 @@
+// Right-to-left output. Streamed events say which direction their text runs
+// in, so the windows can lay out Arabic or Hebrew correctly before the first
+// strong character arrives. Models sometimes emit explicit embedding, override
+// or isolate controls (often unbalanced), which reorder everything after them
+// on screen; those are removed from the output. Marks (LRM, RLM, ALM) only
+// affect neighbouring neutral characters and are kept.
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+// Languages written right to left, by the names the frontend passes
+const RTL_LANGUAGES: &[&str] = &["arabic", "hebrew", "persian", "farsi", "urdu", "pashto", "yiddish", "dhivehi"];
+
+/// Direction of text in `lang`.
+pub fn direction(lang: &str) -> Direction {
+    if RTL_LANGUAGES.contains(&lang.to_ascii_lowercase().as_str()) {
+        Direction::Rtl
+    } else {
+        Direction::Ltr
+    }
+}
+
+// LRE, RLE, PDF, LRO, RLO and LRI, RLI, FSI, PDI
+fn is_explicit_control(c: char) -> bool {
+    matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+/// `text` without explicit directional controls.
+pub fn normalize(text: &str) -> String {
+    if text.contains(is_explicit_control) {
+        text.chars().filter(|&c| !is_explicit_control(c)).collect()
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,126 @@
+// Casing of headings. Whether the model keeps "QUARTERLY REPORT" in capitals
+// or writes "Getting Started With Spark" in title case is a coin toss, so the
+// casing of short source lines is re-applied to the matching output lines:
+// ALL CAPS stays all caps in any cased script, and Title Case becomes title
+// case in English and a capitalized first word elsewhere (French, German,
+// Spanish, ... headings don't capitalize every word). Scripts without case,
+// like Japanese, are left alone.
+
+// Longer lines are prose, not headings
+const MAX_HEADING_WORDS: usize = 12;
+
+// Lowercase inside English titles unless first or last
+const SMALL_WORDS: &[&str] = &[
+    "a", "an", "the", "and", "but", "or", "nor", "for", "so", "yet", "as", "at", "by", "in", "of", "on", "to", "up",
+    "via", "with", "from", "into", "per", "vs",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Casing {
+    Upper,
+    Title,
+}
+
+fn is_cased(c: char) -> bool {
+    c.is_uppercase() || c.is_lowercase()
+}
+
+// The casing of a heading-like line, None for prose and mixed case
+fn classify(line: &str) -> Option<Casing> {
+    let line = line.trim();
+    let words: Vec<&str> = line.split_whitespace().filter(|word| word.chars().any(is_cased)).collect();
+    if words.is_empty() || words.len() > MAX_HEADING_WORDS || line.ends_with(['.', ',', ';', ':']) {
+        return None;
+    }
+    let letters: Vec<char> = line.chars().filter(|&c| is_cased(c)).collect();
+    // "I" or "OK" alone says nothing about the casing
+    if letters.len() >= 3 && letters.iter().all(|c| c.is_uppercase()) {
+        return Some(Casing::Upper);
+    }
+    let capitalized = |word: &str| word.chars().find(|&c| is_cased(c)).is_some_and(char::is_uppercase);
+    let significant: Vec<&str> =
+        words.iter().copied().filter(|word| !SMALL_WORDS.contains(&word.to_lowercase().as_str())).collect();
+    (words.len() >= 2 && significant.len() >= 2 && significant.iter().all(|word| capitalized(word))).then_some(Casing::Title)
+}
+
+/// Whether `text` has headings whose casing `apply` would restore.
+pub fn has_headings(text: &str) -> bool {
+    text.lines().any(|line| classify(line).is_some())
+}
+
+// Mostly cased letters, i.e. a script where casing means something
+fn uses_case(line: &str) -> bool {
+    let letters = line.chars().filter(|c| c.is_alphabetic()).count();
+    let cased = line.chars().filter(|&c| is_cased(c)).count();
+    letters > 0 && cased * 2 > letters
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// Capitalizes every word but inner small words; the rest of each word is kept,
+// so acronyms and names like "iPhone" survive
+fn english_title_case(line: &str) -> String {
+    let words: Vec<&str> = line.split(' ').collect();
+    let last = words.iter().rposition(|word| !word.is_empty()).unwrap_or(0);
+    let first = words.iter().position(|word| !word.is_empty()).unwrap_or(0);
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            if i != first && i != last && SMALL_WORDS.contains(&word.to_lowercase().as_str()) {
+                word.to_lowercase()
+            } else if word.chars().skip(1).any(char::is_uppercase) {
+                word.to_string()
+            } else {
+                capitalize(word)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn restore(line: &str, casing: Casing, target_lang: &str) -> String {
+    if !uses_case(line) {
+        return line.to_string();
+    }
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let content = line.trim_start();
+    let cased = match casing {
+        Casing::Upper => content.to_uppercase(),
+        Casing::Title if target_lang.eq_ignore_ascii_case("english") => english_title_case(content),
+        Casing::Title => capitalize(content),
+    };
+    format!("{}{}", indent, cased)
+}
+
+/// Re-applies the casing of the headings in `source` to the corresponding lines
+/// of `output`. Lines are matched by position among non-empty lines; when the
+/// model merged or split lines the output is returned unchanged.
+pub fn apply(source: &str, output: &str, target_lang: &str) -> String {
+    let headings: Vec<Option<Casing>> = source.lines().filter(|line| !line.trim().is_empty()).map(classify).collect();
+    let output_lines = output.lines().filter(|line| !line.trim().is_empty()).count();
+    if output_lines != headings.len() || headings.iter().all(Option::is_none) {
+        return output.to_string();
+    }
+    let mut headings = headings.into_iter();
+    output
+        .split_inclusive('\n')
+        .map(|line| {
+            if line.trim().is_empty() {
+                return line.to_string();
+            }
+            let content = line.trim_end_matches(['\r', '\n']);
+            let ending = &line[content.len()..];
+            match headings.next().flatten() {
+                Some(casing) => format!("{}{}", restore(content, casing, target_lang), ending),
+                None => line.to_string(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+// Bundled learner database (readings, meanings, strokes, components); its
+// sources are named in the file. It is a small sample of common characters,
+// not a full jōyō/hanzi set: anything else comes back with `found: false`.
+// Parsed lazily on first lookup so startup time is unaffected.
+const CHARACTER_DB_JSON: &str = include_str!("../data/characters.json");
+
+#[derive(Clone, serde::Deserialize)]
+struct CharacterEntry {
+    #[serde(default)]
+    on: Vec<String>,
+    #[serde(default)]
+    kun: Vec<String>,
+    #[serde(default)]
+    pinyin: Vec<String>,
+    #[serde(default)]
+    meanings: Vec<String>,
+    strokes: u32,
+    #[serde(default)]
+    components: Vec<String>,
+}
+
+// Besides the entries the file names its sources and coverage, which aren't needed here
+#[derive(serde::Deserialize)]
+struct CharacterFile {
+    characters: HashMap<String, CharacterEntry>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct CharacterInfo {
+    character: String,
+    codepoint: String,
+    // False when the character is CJK but not in the bundled database
+    found: bool,
+    on_readings: Vec<String>,
+    kun_readings: Vec<String>,
+    pinyin: Vec<String>,
+    meanings: Vec<String>,
+    stroke_count: Option<u32>,
+    components: Vec<String>,
+}
+
+fn database() -> &'static HashMap<char, CharacterEntry> {
+    static DB: OnceLock<HashMap<char, CharacterEntry>> = OnceLock::new();
+    DB.get_or_init(|| {
+        let raw = serde_json::from_str::<CharacterFile>(CHARACTER_DB_JSON)
+            .map(|file| file.characters)
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to parse bundled character database: {}", e);
+                HashMap::new()
+            });
+        raw.into_iter()
+            .filter_map(|(key, entry)| key.chars().next().map(|c| (c, entry)))
+            .collect()
+    })
+}
+
+pub fn is_cjk_ideograph(c: char) -> bool {
+    matches!(c as u32,
+        0x4E00..=0x9FFF     // CJK Unified Ideographs
+        | 0x3400..=0x4DBF   // Extension A
+        | 0x20000..=0x2A6DF // Extension B
+        | 0xF900..=0xFAFF   // Compatibility Ideographs
+    )
+}
+
+/// Looks up every distinct CJK ideograph in `text` (in order of first appearance).
+pub fn analyze(text: &str) -> Vec<CharacterInfo> {
+    let db = database();
+    let mut seen = std::collections::HashSet::new();
+
+    text.chars()
+        .filter(|c| is_cjk_ideograph(*c) && seen.insert(*c))
+        .map(|c| {
+            let codepoint = format!("U+{:04X}", c as u32);
+            match db.get(&c) {
+                Some(entry) => CharacterInfo {
+                    character: c.to_string(),
+                    codepoint,
+                    found: true,
+                    on_readings: entry.on.clone(),
+                    kun_readings: entry.kun.clone(),
+                    pinyin: entry.pinyin.clone(),
+                    meanings: entry.meanings.clone(),
+                    stroke_count: Some(entry.strokes),
+                    components: entry.components.clone(),
+                },
+                None => CharacterInfo {
+                    character: c.to_string(),
+                    codepoint,
+                    found: false,
+                    on_readings: Vec::new(),
+                    kun_readings: Vec::new(),
+                    pinyin: Vec::new(),
+                    meanings: Vec::new(),
+                    stroke_count: None,
+                    components: Vec::new(),
+                },
+            }
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn analyze_characters(text: String) -> Result<Vec<CharacterInfo>, String> {
+    Ok(analyze(&text))
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{lang_detect, AppState};
+
+// How much source text goes into one prompt. Sizes are estimated tokens rather
+// than bytes: a kanji is three UTF-8 bytes but roughly one token, an English
+// word of five letters is about one token, and an emoji sequence of several
+// code points is a single character that must never be cut. The limit can be
+// set per source language in the settings (`chunkTokens`).
+
+/// Chunk size used for languages without a configured limit; about 800
+/// characters of English or 200 of Japanese.
+pub const DEFAULT_CHUNK_TOKENS: usize = 200;
+
+// Estimated tokens are counted in quarters so Latin letters can weigh less than one
+const QUARTERS: usize = 4;
+
+pub fn is_cjk(c: char) -> bool {
+    matches!(c as u32, 0x3040..=0x30FF | 0x31F0..=0x31FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0xAC00..=0xD7AF | 0xFF66..=0xFF9F)
+}
+
+// Weight of one grapheme cluster in quarter tokens
+fn grapheme_weight(grapheme: &str) -> usize {
+    let mut chars = grapheme.chars();
+    let first = chars.next().unwrap_or(' ');
+    if chars.next().is_some() && !first.is_alphabetic() {
+        // Emoji sequences (ZWJ, skin tones, flags) take several tokens
+        return 3 * QUARTERS;
+    }
+    if is_cjk(first) {
+        QUARTERS
+    } else if first.is_ascii() {
+        1
+    } else {
+        // Other scripts (Cyrillic, Thai, accented Latin, ...) tokenize less densely
+        2
+    }
+}
+
+/// Estimated token count of `text`.
+pub fn estimate_tokens(text: &str) -> usize {
+    let quarters: usize = text.graphemes(true).map(grapheme_weight).sum();
+    quarters.div_ceil(QUARTERS)
+}
+
+/// Chunk size for `source_lang`; the language is detected from `text` when
+/// it has no configured limit (e.g. automatic source language).
+pub fn limit(state: &AppState, source_lang: &str, text: &str) -> usize {
+    let limits = state.settings.lock().unwrap().chunk_tokens.clone();
+    limit_for(&limits, source_lang)
+        .or_else(|| lang_detect::detect(text).and_then(|lang| limit_for(&limits, lang)))
+        .unwrap_or(DEFAULT_CHUNK_TOKENS)
+}
+
+fn limit_for(limits: &HashMap<String, usize>, lang: &str) -> Option<usize> {
+    limits
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(lang))
+        .map(|(_, &tokens)| tokens.max(1))
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+// Input cleanup for text copied from PDFs (and hard-wrapped mail): page
+// headers, footers and page numbers repeated on every page are dropped, words
+// hyphenated across a line break are joined, and lines wrapped mid-sentence are
+// unwrapped into paragraphs. Without this the model translates every line as a
+// sentence fragment. Enabled per call with the `inputCleanup` option.
+
+// Lines this short that repeat at least MIN_REPEATS times are headers/footers
+const HEADER_MAX_CHARS: usize = 80;
+const MIN_REPEATS: usize = 3;
+// A line shorter than this share of the longest line ended its paragraph (or is a heading)
+const SHORT_LINE_RATIO: f64 = 0.6;
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32, 0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0xAC00..=0xD7AF | 0xFF00..=0xFFEF)
+}
+
+fn ends_sentence(line: &str) -> bool {
+    line.trim_end()
+        .chars()
+        .last()
+        .is_some_and(|c| matches!(c, '.' | '!' | '?' | ':' | ';' | '。' | '！' | '？' | '：' | '」' | '』' | '"' | '”' | ')'))
+}
+
+// Bullets, numbered items and quoted mail lines start a new line of their own
+fn starts_block(line: &str) -> bool {
+    let line = line.trim_start();
+    let numbered = {
+        let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+        digits > 0 && matches!(line[digits..].chars().next(), Some('.' | ')'))
+    };
+    numbered || line.starts_with(['-', '*', '•', '・', '>', '#', '|'])
+}
+
+// "Page 3 of 12", "- 3 -" and "Annual Report 2023   7" all become the same
+// shape as on the other pages
+fn normalize(line: &str) -> String {
+    line.trim().chars().map(|c| if c.is_ascii_digit() { '#' } else { c }).collect()
+}
+
+fn is_page_number(line: &str) -> bool {
+    let line = line.trim().trim_matches(|c: char| c == '-' || c == '–' || c.is_whitespace());
+    !line.is_empty() && line.chars().all(|c| c.is_ascii_digit() || c == '/' || c.is_whitespace())
+}
+
+// Drops page numbers and short lines that repeat on most pages
+fn remove_headers(lines: Vec<&str>) -> Vec<&str> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for line in &lines {
+        if !line.trim().is_empty() && line.chars().count() <= HEADER_MAX_CHARS {
+            *counts.entry(normalize(line)).or_default() += 1;
+        }
+    }
+    lines
+        .into_iter()
+        .filter(|line| {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                return true;
+            }
+            if is_page_number(trimmed) {
+                return false;
+            }
+            // Separators and bullets repeat legitimately
+            let has_letters = trimmed.chars().any(char::is_alphabetic);
+            !(has_letters && counts.get(&normalize(trimmed)).copied().unwrap_or(0) >= MIN_REPEATS)
+        })
+        .collect()
+}
+
+// "Eu-" + "rope": a word broken across the line break
+fn hyphenated(paragraph: &str, next: &str) -> bool {
+    paragraph.ends_with('-')
+        && paragraph[..paragraph.len() - 1].chars().last().is_some_and(char::is_alphabetic)
+        && next.trim_start().chars().next().is_some_and(char::is_lowercase)
+}
+
+// Joins `next` onto `paragraph`, removing a line-break hyphen or adding a space
+fn join(paragraph: &mut String, next: &str) {
+    let next = next.trim_start();
+    if hyphenated(paragraph, next) {
+        paragraph.pop();
+    } else if !(paragraph.chars().last().is_some_and(is_cjk) || next.chars().next().is_some_and(is_cjk)) {
+        paragraph.push(' ');
+    }
+    paragraph.push_str(next);
+}
+
+/// Cleans up text copied from a PDF or hard-wrapped mail. Blank lines still
+/// separate paragraphs.
+pub fn clean(text: &str) -> String {
+    let lines = remove_headers(text.lines().collect());
+    let wrap_width = lines.iter().map(|line| line.trim_end().chars().count()).max().unwrap_or(0);
+    let min_wrapped = (wrap_width as f64 * SHORT_LINE_RATIO) as usize;
+
+    let mut out: Vec<String> = Vec::new();
+    let mut paragraph: Option<String> = None;
+    // Length of the paragraph's last physical line
+    let mut last_line_chars = 0;
+    for line in lines {
+        let trimmed = line.trim_end();
+        if trimmed.trim().is_empty() {
+            out.extend(paragraph.take());
+            // Collapse runs of blank lines left by removed headers
+            if out.last().is_some_and(|last| !last.is_empty()) {
+                out.push(String::new());
+            }
+            continue;
+        }
+        match paragraph.as_mut() {
+            Some(current) if hyphenated(current, trimmed) => join(current, trimmed),
+            Some(current) if last_line_chars >= min_wrapped && !ends_sentence(current) && !starts_block(trimmed) => {
+                join(current, trimmed)
+            }
+            _ => out.extend(paragraph.replace(trimmed.to_string())),
+        }
+        last_line_chars = trimmed.chars().count();
+    }
+    out.extend(paragraph);
+    while out.last().is_some_and(|last| last.is_empty()) {
+        out.pop();
+    }
+    out.join("\n")
+}
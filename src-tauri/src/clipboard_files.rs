@@ -0,0 +1,130 @@
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::os_window::WindowHandle;
+use crate::{batch, popup};
+
+// Files copied in Explorer put a file list on the clipboard instead of text.
+// The popup trigger then offers to translate those files (text documents
+// through translate_document, folders and ZIP archives through
+// translate_batch) rather than doing nothing.
+// Only implemented on Windows; elsewhere the clipboard never has a file list.
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileKind {
+    // A text file translate_document can read
+    Document,
+    Folder,
+    Archive,
+    // Anything else; listed but not translated
+    Unsupported,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardFile {
+    path: String,
+    name: String,
+    kind: FileKind,
+}
+
+impl ClipboardFile {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+fn kind(path: &Path) -> FileKind {
+    if path.is_dir() {
+        FileKind::Folder
+    } else if batch::is_zip(path) {
+        FileKind::Archive
+    } else if batch::is_supported(path) {
+        FileKind::Document
+    } else {
+        FileKind::Unsupported
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+    use std::path::PathBuf;
+    use std::thread;
+    use std::time::Duration;
+    use windows_sys::Win32::System::DataExchange::{CloseClipboard, GetClipboardData, IsClipboardFormatAvailable, OpenClipboard};
+    use windows_sys::Win32::UI::Shell::DragQueryFileW;
+
+    // Standard clipboard format, defined in the Ole feature of windows-sys
+    const CF_HDROP: u32 = 15;
+    // Another application may still hold the clipboard right after copying
+    const OPEN_ATTEMPTS: u32 = 5;
+    const OPEN_RETRY: Duration = Duration::from_millis(20);
+
+    fn open_clipboard() -> bool {
+        for _ in 0..OPEN_ATTEMPTS {
+            if unsafe { OpenClipboard(std::ptr::null_mut()) } != 0 {
+                return true;
+            }
+            thread::sleep(OPEN_RETRY);
+        }
+        false
+    }
+
+    pub fn file_list() -> Vec<PathBuf> {
+        unsafe {
+            if IsClipboardFormatAvailable(CF_HDROP) == 0 || !open_clipboard() {
+                return Vec::new();
+            }
+            let mut paths = Vec::new();
+            let drop = GetClipboardData(CF_HDROP);
+            if !drop.is_null() {
+                let count = DragQueryFileW(drop, u32::MAX, std::ptr::null_mut(), 0);
+                for index in 0..count {
+                    let len = DragQueryFileW(drop, index, std::ptr::null_mut(), 0);
+                    let mut buffer = vec![0u16; len as usize + 1];
+                    let copied = DragQueryFileW(drop, index, buffer.as_mut_ptr(), buffer.len() as u32);
+                    if copied > 0 {
+                        paths.push(PathBuf::from(OsString::from_wide(&buffer[..copied as usize])));
+                    }
+                }
+            }
+            CloseClipboard();
+            paths
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod imp {
+    use std::path::PathBuf;
+
+    pub fn file_list() -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+/// Files currently on the clipboard, in the order they were copied.
+pub fn read() -> Vec<ClipboardFile> {
+    imp::file_list()
+        .into_iter()
+        .map(|path| ClipboardFile {
+            name: path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+            kind: kind(&path),
+            path: path.to_string_lossy().into_owned(),
+        })
+        .collect()
+}
+
+/// Opens the popup with the clipboard's file list, if it has one. Returns false
+/// when the clipboard holds no files.
+pub fn offer(app: &AppHandle, mouse_x: f64, mouse_y: f64, source_window: Option<WindowHandle>) -> bool {
+    let files = read();
+    if files.is_empty() {
+        return false;
+    }
+    popup::show_file_popup(app, files, mouse_x, mouse_y, source_window);
+    true
+}
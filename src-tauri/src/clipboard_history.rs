@@ -0,0 +1,58 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+use crate::AppState;
+
+// Short in-memory ring of recent Ctrl+C captures, so the popup can offer an earlier
+// copy when an extra Ctrl+C replaced the text the user meant to translate.
+// Never persisted.
+
+const CAPACITY: usize = 10;
+
+#[derive(Clone, serde::Serialize)]
+pub struct ClipboardEntry {
+    text: String,
+    // Seconds since UNIX epoch
+    timestamp: u64,
+}
+
+#[derive(Default)]
+pub struct ClipboardHistory {
+    entries: Mutex<VecDeque<ClipboardEntry>>,
+}
+
+impl ClipboardHistory {
+    /// Adds a capture, skipping blanks and repeats of the newest entry.
+    pub fn record(&self, text: &str) {
+        if text.trim().is_empty() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.front().is_some_and(|newest| newest.text == text) {
+            return;
+        }
+        entries.push_front(ClipboardEntry {
+            text: text.to_string(),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        });
+        entries.truncate(CAPACITY);
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+/// Recent captures, newest first.
+#[tauri::command]
+pub async fn get_clipboard_history(state: State<'_, AppState>) -> Result<Vec<ClipboardEntry>, String> {
+    Ok(state.clipboard_history.entries.lock().unwrap().iter().cloned().collect())
+}
+
+#[tauri::command]
+pub async fn clear_clipboard_history(state: State<'_, AppState>) -> Result<(), String> {
+    state.clipboard_history.clear();
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use rusqlite::Connection;
+use tauri::{AppHandle, Manager};
+
+use crate::encryption;
+
+// SQLite database for data that outlives a session (projects, glossary, TM, ...),
+// stored in the app data directory. Schema changes are appended to MIGRATIONS
+// and applied in order on startup, tracked with SQLite's user_version.
+
+const DB_FILE: &str = "spark.db";
+
+const MIGRATIONS: &[&str] = &[
+    // 1: translation projects and their files
+    "CREATE TABLE projects (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        source_lang TEXT NOT NULL,
+        target_lang TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        opened_at INTEGER NOT NULL
+    );
+    CREATE TABLE project_files (
+        id INTEGER PRIMARY KEY,
+        project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+        path TEXT NOT NULL,
+        output_path TEXT,
+        status TEXT NOT NULL DEFAULT 'pending',
+        error TEXT,
+        UNIQUE (project_id, path)
+    );",
+    // 2: glossary and translation memory, per project (project_id set) or global (NULL)
+    "CREATE TABLE glossary_terms (
+        id INTEGER PRIMARY KEY,
+        project_id INTEGER REFERENCES projects(id) ON DELETE CASCADE,
+        source_lang TEXT NOT NULL,
+        target_lang TEXT NOT NULL,
+        source_term TEXT NOT NULL,
+        target_term TEXT NOT NULL,
+        note TEXT
+    );
+    CREATE INDEX glossary_terms_pair ON glossary_terms (source_lang, target_lang);
+    CREATE TABLE tm_entries (
+        id INTEGER PRIMARY KEY,
+        project_id INTEGER REFERENCES projects(id) ON DELETE CASCADE,
+        source_lang TEXT NOT NULL,
+        target_lang TEXT NOT NULL,
+        source_key TEXT NOT NULL,
+        source_text TEXT NOT NULL,
+        target_text TEXT NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE INDEX tm_entries_key ON tm_entries (source_lang, target_lang, source_key);",
+    // 3: reviewed segments of project files
+    "CREATE TABLE segments (
+        id INTEGER PRIMARY KEY,
+        project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+        file_id INTEGER NOT NULL REFERENCES project_files(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        source_text TEXT NOT NULL,
+        target_text TEXT NOT NULL,
+        state TEXT NOT NULL DEFAULT 'machine',
+        updated_at INTEGER NOT NULL
+    );
+    CREATE INDEX segments_file ON segments (file_id, position);",
+    // 4: few-shot examples collected from human edits
+    "CREATE TABLE fewshot_examples (
+        id INTEGER PRIMARY KEY,
+        project_id INTEGER REFERENCES projects(id) ON DELETE CASCADE,
+        source_lang TEXT NOT NULL,
+        target_lang TEXT NOT NULL,
+        source_text TEXT NOT NULL,
+        target_text TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );",
+    // 5: prompt/parameter experiments and their per-variant results
+    "CREATE TABLE experiments (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        source_file TEXT NOT NULL,
+        reference_file TEXT,
+        source_lang TEXT NOT NULL,
+        target_lang TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE TABLE experiment_results (
+        id INTEGER PRIMARY KEY,
+        experiment_id INTEGER NOT NULL REFERENCES experiments(id) ON DELETE CASCADE,
+        config TEXT NOT NULL,
+        bleu REAL,
+        chrf REAL,
+        duration_ms INTEGER NOT NULL,
+        outputs TEXT NOT NULL
+    );",
+    // 6: translation history, see history.rs
+    "CREATE TABLE history (
+        id INTEGER PRIMARY KEY,
+        source_text TEXT NOT NULL,
+        translated_text TEXT NOT NULL,
+        source_lang TEXT NOT NULL,
+        target_lang TEXT NOT NULL,
+        model_id TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX history_created ON history (created_at);",
+    // 7: per-chunk timings of history entries (JSON), see timings.rs
+    "ALTER TABLE history ADD COLUMN timings TEXT;",
+    // 8: entries kept from cancelled translations
+    "ALTER TABLE history ADD COLUMN partial INTEGER NOT NULL DEFAULT 0;",
+    // 9: how often each language pair is used, see languages.rs
+    "CREATE TABLE language_pairs (
+        source_lang TEXT NOT NULL,
+        target_lang TEXT NOT NULL,
+        uses INTEGER NOT NULL,
+        last_used INTEGER NOT NULL,
+        PRIMARY KEY (source_lang, target_lang)
+    );",
+    // 10: what each history entry was made with (JSON), see environment.rs
+    "ALTER TABLE history ADD COLUMN environment TEXT;",
+    // 11: text that followed each segment in its source file, see segments.rs.
+    // Older segments were always exported one per line.
+    "ALTER TABLE segments ADD COLUMN separator TEXT NOT NULL DEFAULT '\n';",
+];
+
+/// Seconds since UNIX epoch, as stored in timestamp columns
+pub fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct Database {
+    conn: Mutex<Option<Connection>>,
+}
+
+pub fn path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(DB_FILE))
+}
+
+fn connect(path: &Path) -> Result<Connection, String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+    }
+    let encrypted = encryption::is_encrypted(path);
+    let mut conn = Connection::open(path).map_err(|e| format!("Failed to open database: {}", e))?;
+    if encrypted {
+        encryption::unlock(&conn)?;
+    }
+    conn.pragma_update(None, "foreign_keys", true).map_err(|e| e.to_string())?;
+    migrate(&mut conn).map_err(|e| format!("Failed to migrate database: {}", e))?;
+    Ok(conn)
+}
+
+impl Database {
+    /// Opens (creating if needed) the database and brings its schema up to date.
+    pub fn open(&self, app: &AppHandle) -> Result<(), String> {
+        let conn = connect(&path(app)?)?;
+        *self.conn.lock().unwrap() = Some(conn);
+        Ok(())
+    }
+
+    /// Replaces the database file with a copy written by `export` (e.g. an
+    /// encrypted one) and reopens it. Other callers wait on the lock meanwhile.
+    pub fn rewrite(&self, app: &AppHandle, export: impl FnOnce(&Connection, &Path) -> rusqlite::Result<()>) -> Result<(), String> {
+        let path = path(app)?;
+        let copy = path.with_extension("db.new");
+        let mut guard = self.conn.lock().unwrap();
+        let conn = guard.as_ref().ok_or("Database is not available")?;
+        let _ = std::fs::remove_file(&copy);
+        if let Err(e) = export(conn, &copy) {
+            let _ = std::fs::remove_file(&copy);
+            return Err(format!("Database error: {}", e));
+        }
+        // The file can't be replaced while open on Windows
+        *guard = None;
+        std::fs::rename(&copy, &path).map_err(|e| format!("Failed to replace database: {}", e))?;
+        *guard = Some(connect(&path)?);
+        Ok(())
+    }
+
+    /// Runs `f` on the open connection. Fails if the database could not be opened.
+    pub fn with<T>(&self, f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+        let mut guard = self.conn.lock().unwrap();
+        let conn = guard.as_mut().ok_or("Database is not available")?;
+        f(conn).map_err(|e| format!("Database error: {}", e))
+    }
+}
@@ -0,0 +1,167 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+use tauri::{AppHandle, State, Window};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+use crate::shortcut::{self, TriggerBackend};
+use crate::{db, model_registry, models, priority, AppState, TranslateOptions};
+
+// Self-test behind the diagnostics panel: checks each piece a translation
+// depends on, in order, so "nothing happens" reports can be narrowed down.
+
+// Input events are continuous while the user works; a longer silence suggests a dead hook
+const LISTENER_QUIET_SECS: i64 = 10 * 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    // Not run because an earlier check failed
+    Skipped,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticCheck {
+    name: &'static str,
+    status: CheckStatus,
+    detail: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsReport {
+    // No check failed (warnings allowed)
+    passed: bool,
+    checks: Vec<DiagnosticCheck>,
+}
+
+fn check(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> DiagnosticCheck {
+    DiagnosticCheck { name, status, detail: detail.into() }
+}
+
+fn models_directory(state: &AppState) -> (DiagnosticCheck, Vec<(String, PathBuf)>) {
+    let found: Vec<(String, PathBuf)> = model_registry::ids(state)
+        .into_iter()
+        .filter_map(|id| models::resolve(state, &id).ok().map(|path| (id, path)))
+        .collect();
+    if found.is_empty() {
+        let dirs: Vec<String> = models::search_dirs().iter().map(|dir| dir.to_string_lossy().into_owned()).collect();
+        return (check("Models directory", CheckStatus::Fail, format!("No model files found in: {}", dirs.join(", "))), found);
+    }
+    let list: Vec<String> = found.iter().map(|(id, path)| format!("{} ({:?})", id, path)).collect();
+    (check("Models directory", CheckStatus::Pass, list.join(", ")), found)
+}
+
+fn gguf_files(found: &[(String, PathBuf)]) -> DiagnosticCheck {
+    if found.is_empty() {
+        return check("Model files", CheckStatus::Skipped, "No model files to read");
+    }
+    let mut problems = Vec::new();
+    for (id, path) in found {
+        let mut magic = [0u8; 4];
+        match File::open(path).and_then(|mut file| file.read_exact(&mut magic)) {
+            Ok(()) if &magic == models::GGUF_MAGIC => {}
+            Ok(()) => problems.push(format!("{}: not a GGUF file", id)),
+            Err(e) => problems.push(format!("{}: {}", id, e)),
+        }
+    }
+    if problems.is_empty() {
+        check("Model files", CheckStatus::Pass, format!("{} readable GGUF file(s)", found.len()))
+    } else {
+        check("Model files", CheckStatus::Fail, problems.join("; "))
+    }
+}
+
+async fn generation(found: &[(String, PathBuf)], state: &AppState, window: &Window) -> DiagnosticCheck {
+    // Prefer the loaded model so the test doesn't force a reload
+    let loaded = state.current_model_id.lock().unwrap().clone();
+    let model_id = match loaded.filter(|id| found.iter().any(|(found_id, _)| found_id == id)) {
+        Some(id) => id,
+        None => match found.first() {
+            Some((id, _)) => id.clone(),
+            None => return check("Test translation", CheckStatus::Skipped, "No model available"),
+        },
+    };
+    // Waits for earlier jobs and resets the cancellation flag
+    let _turn = match state.jobs.start(state, window, priority::JobKind::Interactive).await {
+        Ok(turn) => turn,
+        Err(e) => return check("Test translation", CheckStatus::Skipped, e),
+    };
+    let started = Instant::now();
+    let options = TranslateOptions::default();
+    match crate::run_translation("Good morning.", "English", "Japanese", &model_id, state, window, &options) {
+        Ok(output) if !output.trim().is_empty() => check(
+            "Test translation",
+            CheckStatus::Pass,
+            format!("{} produced output in {} ms", model_id, started.elapsed().as_millis()),
+        ),
+        Ok(_) => check("Test translation", CheckStatus::Fail, format!("{} produced no output", model_id)),
+        Err(e) => check("Test translation", CheckStatus::Fail, e),
+    }
+}
+
+fn clipboard(app: &AppHandle) -> DiagnosticCheck {
+    match app.clipboard().read_text() {
+        Ok(_) => check("Clipboard", CheckStatus::Pass, "Readable"),
+        // Also returned when the clipboard simply holds no text
+        Err(e) => check("Clipboard", CheckStatus::Warn, format!("Could not read text: {}", e)),
+    }
+}
+
+fn global_shortcut(app: &AppHandle, state: &AppState) -> DiagnosticCheck {
+    let configured = state.settings.lock().unwrap().global_shortcut.clone();
+    let shortcut = configured.as_deref().unwrap_or(shortcut::DEFAULT_SHORTCUT);
+    if app.global_shortcut().is_registered(shortcut) {
+        check("Global shortcut", CheckStatus::Pass, format!("{} registered", shortcut))
+    } else {
+        check("Global shortcut", CheckStatus::Fail, format!("{} is not registered", shortcut))
+    }
+}
+
+fn key_listener(state: &AppState) -> DiagnosticCheck {
+    if !state.key_listener_running.load(Ordering::Relaxed) {
+        return check("Hotkey listener", CheckStatus::Fail, "The keyboard listener is not running; hotkeys won't work");
+    }
+    let last_event = state.last_input_event.load(Ordering::Relaxed) as i64;
+    if last_event == 0 {
+        return check("Hotkey listener", CheckStatus::Warn, "Running, but no input events received yet");
+    }
+    let quiet = db::now() - last_event;
+    if quiet > LISTENER_QUIET_SECS {
+        check("Hotkey listener", CheckStatus::Warn, format!("Running, but no input events for {} s", quiet))
+    } else {
+        check("Hotkey listener", CheckStatus::Pass, format!("Running, last input event {} s ago", quiet))
+    }
+}
+
+/// Runs every check and returns the report; the test translation can take a
+/// while when the model isn't loaded yet.
+#[tauri::command]
+pub async fn run_diagnostics(app: AppHandle, state: State<'_, AppState>, window: Window) -> Result<DiagnosticsReport, String> {
+    let (directory, found) = models_directory(&state);
+    let files = gguf_files(&found);
+    let backend = check(
+        "Inference backend",
+        CheckStatus::Pass,
+        format!("llama.cpp initialized, {} CPU threads", std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)),
+    );
+    let generation = if files.status == CheckStatus::Pass {
+        generation(&found, &state, &window).await
+    } else {
+        check("Test translation", CheckStatus::Skipped, "Model files are not usable")
+    };
+    let trigger = match shortcut::backend(&app) {
+        TriggerBackend::KeyboardHook => key_listener(&state),
+        TriggerBackend::GlobalShortcut => global_shortcut(&app, &state),
+    };
+    let checks = vec![directory, files, backend, generation, clipboard(&app), trigger];
+    let passed = checks.iter().all(|c| c.status != CheckStatus::Fail);
+    Ok(DiagnosticsReport { passed, checks })
+}
@@ -0,0 +1,38 @@
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::os_window;
+use crate::AppState;
+
+// Do-not-disturb: while active, hotkey triggers and clipboard captures are ignored
+// so Spark never pops up over a game or presentation. It is either switched on
+// manually (tray/command) or, if enabled in settings, whenever a fullscreen
+// application is in the foreground.
+
+/// Checked by the key listener on every trigger.
+pub fn is_active(app: &AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    if state.do_not_disturb.load(Ordering::Relaxed) {
+        return true;
+    }
+    let auto_fullscreen = state.settings.lock().unwrap().auto_do_not_disturb;
+    auto_fullscreen && os_window::foreground_is_fullscreen()
+}
+
+/// Switches the manual toggle and keeps the tray checkbox in sync.
+pub fn set(app: &AppHandle, enabled: bool) {
+    app.state::<AppState>().do_not_disturb.store(enabled, Ordering::Relaxed);
+    crate::tray::set_do_not_disturb_checked(enabled);
+    app.emit("do-not-disturb-changed", enabled).unwrap_or(());
+}
+
+#[tauri::command]
+pub async fn get_do_not_disturb(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.do_not_disturb.load(Ordering::Relaxed))
+}
+
+#[tauri::command]
+pub async fn set_do_not_disturb(enabled: bool, app: AppHandle) -> Result<(), String> {
+    set(&app, enabled);
+    Ok(())
+}
@@ -0,0 +1,376 @@
+use base64::Engine;
+use tauri::{State, Window};
+
+use crate::{priority, AppState, SegmentTranslator};
+
+// Email (.eml / pasted mail) translation helper.
+// Splits a message into headers, the new body, quoted history and the signature so
+// only the new body gets translated by default and `>` quoting survives intact.
+
+#[derive(Clone, serde::Serialize)]
+pub struct EmailHeader {
+    name: String,
+    value: String,
+}
+
+#[derive(Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockKind {
+    // Newly written text
+    Body,
+    // `>`-prefixed lines, "On ... wrote:" lines and forwarded/original messages
+    Quote,
+    Signature,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct EmailBlock {
+    kind: BlockKind,
+    // Quote marker shared by every line of the block (e.g. "> > "), empty otherwise
+    prefix: String,
+    // Block text with the prefix stripped from each line
+    text: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct ParsedEmail {
+    headers: Vec<EmailHeader>,
+    blocks: Vec<EmailBlock>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct TranslatedEmail {
+    headers: Vec<EmailHeader>,
+    body: String,
+}
+
+const KNOWN_HEADERS: &[&str] = &[
+    "from", "to", "cc", "subject", "date", "message-id", "received", "mime-version",
+    "content-type", "reply-to", "return-path", "in-reply-to", "references",
+];
+
+const HISTORY_SEPARATORS: &[&str] = &[
+    "-----original message-----",
+    "---------- forwarded message ---------",
+    "-------- forwarded message --------",
+    "-------- original message --------",
+    "-----元のメッセージ-----",
+    "---------- 転送メッセージ ----------",
+];
+
+fn header_value<'a>(headers: &'a [EmailHeader], name: &str) -> Option<&'a str> {
+    headers.iter()
+        .find(|h| h.name.eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str())
+}
+
+// Pulls `key=value` out of a header like `text/plain; charset="utf-8"`.
+fn header_param(value: &str, key: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|param| {
+        let (k, v) = param.split_once('=')?;
+        if k.trim().eq_ignore_ascii_case(key) {
+            Some(v.trim().trim_matches('"').to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// Splits a header section from the body. Returns no headers when the text
+/// doesn't start like an RFC 822 message (e.g. mail pasted from a client).
+fn split_headers(raw: &str) -> (Vec<EmailHeader>, &str) {
+    let raw = raw.trim_start_matches('\u{feff}');
+    let Some(header_end) = raw.find("\r\n\r\n").map(|i| (i, 4)).or_else(|| raw.find("\n\n").map(|i| (i, 2))) else {
+        return (Vec::new(), raw);
+    };
+    let (section, sep_len) = (&raw[..header_end.0], header_end.1);
+
+    let mut headers: Vec<EmailHeader> = Vec::new();
+    for line in section.lines() {
+        if line.starts_with(' ') || line.starts_with('\t') {
+            // Folded continuation line
+            match headers.last_mut() {
+                Some(last) => {
+                    last.value.push(' ');
+                    last.value.push_str(line.trim());
+                }
+                None => return (Vec::new(), raw),
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return (Vec::new(), raw);
+            }
+            headers.push(EmailHeader { name: name.to_string(), value: value.trim().to_string() });
+        } else {
+            return (Vec::new(), raw);
+        }
+    }
+
+    let looks_like_mail = headers.iter()
+        .any(|h| KNOWN_HEADERS.contains(&h.name.to_lowercase().as_str()));
+    if !looks_like_mail {
+        return (Vec::new(), raw);
+    }
+    (headers, &raw[header_end.0 + sep_len..])
+}
+
+fn decode_quoted_printable(input: &str) -> Vec<u8> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'=' {
+            // Soft line break
+            if bytes[i + 1..].starts_with(b"\r\n") {
+                i += 3;
+                continue;
+            }
+            if bytes[i + 1..].starts_with(b"\n") {
+                i += 2;
+                continue;
+            }
+            if let Some(hex) = input.get(i + 1..i + 3) {
+                if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                    out.push(byte);
+                    i += 3;
+                    continue;
+                }
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+fn decode_charset(bytes: &[u8], charset: Option<&str>) -> String {
+    let encoding = charset
+        .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+fn decode_part_body(headers: &[EmailHeader], body: &str) -> String {
+    let charset = header_value(headers, "content-type").and_then(|ct| header_param(ct, "charset"));
+    let transfer = header_value(headers, "content-transfer-encoding").unwrap_or("7bit").to_lowercase();
+
+    let bytes = match transfer.as_str() {
+        "quoted-printable" => decode_quoted_printable(body),
+        "base64" => {
+            let compact: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+            base64::engine::general_purpose::STANDARD
+                .decode(compact)
+                .unwrap_or_else(|_| body.as_bytes().to_vec())
+        }
+        _ => body.as_bytes().to_vec(),
+    };
+    decode_charset(&bytes, charset.as_deref())
+}
+
+/// Finds the first text/plain part, descending into nested multiparts.
+fn extract_text_body(headers: &[EmailHeader], body: &str) -> Option<String> {
+    let content_type = header_value(headers, "content-type").unwrap_or("text/plain");
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+
+    if mime.starts_with("multipart/") {
+        let boundary = header_param(content_type, "boundary")?;
+        let delimiter = format!("--{}", boundary);
+        return body
+            .split(delimiter.as_str())
+            .skip(1)
+            .take_while(|part| !part.starts_with("--"))
+            .find_map(|part| {
+                let part = part.trim_start_matches(['\r', '\n']);
+                let (part_headers, part_body) = split_part_headers(part);
+                extract_text_body(&part_headers, part_body)
+            });
+    }
+
+    if mime.is_empty() || mime == "text/plain" {
+        return Some(decode_part_body(headers, body));
+    }
+    None
+}
+
+// MIME part headers don't need to contain "known" mail headers, so parse them leniently.
+fn split_part_headers(part: &str) -> (Vec<EmailHeader>, &str) {
+    let Some((section, body)) = part.split_once("\r\n\r\n").or_else(|| part.split_once("\n\n")) else {
+        return (Vec::new(), part);
+    };
+    let mut headers: Vec<EmailHeader> = Vec::new();
+    for line in section.lines() {
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some(last) = headers.last_mut() {
+                last.value.push(' ');
+                last.value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push(EmailHeader { name: name.trim().to_string(), value: value.trim().to_string() });
+        }
+    }
+    (headers, body)
+}
+
+/// Decodes RFC 2047 encoded words (`=?UTF-8?B?...?=`) in header values.
+fn decode_encoded_words(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    let mut last_was_encoded = false;
+    while let Some(start) = rest.find("=?") {
+        let parts: Vec<&str> = rest[start + 2..].splitn(4, '?').collect();
+        if parts.len() == 4 && parts[3].starts_with('=') {
+            let (charset, encoding, text) = (parts[0], parts[1], parts[2]);
+            let bytes = if encoding.eq_ignore_ascii_case("b") {
+                base64::engine::general_purpose::STANDARD.decode(text).ok()
+            } else if encoding.eq_ignore_ascii_case("q") {
+                Some(decode_quoted_printable(&text.replace('_', " ")))
+            } else {
+                None
+            };
+            if let Some(bytes) = bytes {
+                let between = &rest[..start];
+                // Whitespace between adjacent encoded words is not significant
+                if !(last_was_encoded && between.trim().is_empty()) {
+                    out.push_str(between);
+                }
+                out.push_str(&decode_charset(&bytes, Some(charset)));
+                rest = &rest[start + 2 + charset.len() + 1 + encoding.len() + 1 + text.len() + 2..];
+                last_was_encoded = true;
+                continue;
+            }
+        }
+        out.push_str(&rest[..start + 2]);
+        rest = &rest[start + 2..];
+        last_was_encoded = false;
+    }
+    out.push_str(rest);
+    out
+}
+
+fn quote_prefix(line: &str) -> &str {
+    let end = line
+        .char_indices()
+        .find(|(_, c)| *c != '>' && *c != ' ')
+        .map(|(i, _)| i)
+        .unwrap_or(line.len());
+    let prefix = &line[..end];
+    if prefix.contains('>') { prefix } else { "" }
+}
+
+fn is_attribution(line: &str) -> bool {
+    let trimmed = line.trim_end();
+    (trimmed.starts_with("On ") && trimmed.ends_with("wrote:"))
+        || trimmed.ends_with("書きました:")
+        || trimmed.ends_with("書きました：")
+}
+
+fn classify_body(body: &str) -> Vec<EmailBlock> {
+    let mut blocks: Vec<EmailBlock> = Vec::new();
+    let mut in_history = false;
+    let mut in_signature = false;
+
+    for line in body.lines() {
+        let lowered = line.trim().to_lowercase();
+        if !in_history && HISTORY_SEPARATORS.iter().any(|sep| lowered.starts_with(sep)) {
+            in_history = true;
+            in_signature = false;
+        }
+        if !in_history && !in_signature && (line == "-- " || line == "--") {
+            in_signature = true;
+        }
+
+        let prefix = quote_prefix(line);
+        let (kind, prefix) = if in_history || is_attribution(line) || !prefix.is_empty() {
+            (BlockKind::Quote, prefix)
+        } else if in_signature {
+            (BlockKind::Signature, "")
+        } else {
+            (BlockKind::Body, "")
+        };
+        let content = &line[prefix.len()..];
+
+        match blocks.last_mut() {
+            Some(last) if last.kind == kind && last.prefix == prefix => {
+                last.text.push('\n');
+                last.text.push_str(content);
+            }
+            _ => blocks.push(EmailBlock { kind, prefix: prefix.to_string(), text: content.to_string() }),
+        }
+    }
+    blocks
+}
+
+pub fn parse(raw: &str) -> ParsedEmail {
+    let (mut headers, body) = split_headers(raw);
+    let body = if headers.is_empty() {
+        body.to_string()
+    } else {
+        extract_text_body(&headers, body).unwrap_or_else(|| body.to_string())
+    };
+    for header in headers.iter_mut() {
+        header.value = decode_encoded_words(&header.value);
+    }
+    ParsedEmail { headers, blocks: classify_body(&body) }
+}
+
+// Translates paragraph by paragraph so blank-line layout is preserved.
+fn translate_paragraphs(text: &str, translator: &SegmentTranslator) -> Result<String, String> {
+    let mut out = Vec::new();
+    for paragraph in text.split("\n\n") {
+        out.push(translator.translate(paragraph)?);
+    }
+    Ok(out.join("\n\n"))
+}
+
+fn with_prefix(text: &str, prefix: &str) -> String {
+    if prefix.is_empty() {
+        return text.to_string();
+    }
+    text.split('\n')
+        .map(|line| format!("{}{}", prefix, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[tauri::command]
+pub async fn parse_email(raw: String) -> Result<ParsedEmail, String> {
+    Ok(parse(&raw))
+}
+
+#[tauri::command]
+pub async fn translate_email(
+    raw: String,
+    source_lang: String,
+    target_lang: String,
+    model_id: String,
+    include_quotes: Option<bool>,
+    state: State<'_, AppState>,
+    window: Window,
+) -> Result<TranslatedEmail, String> {
+    // Waits for earlier jobs and resets the cancellation flag
+    let _turn = state.jobs.start(&state, &window, priority::JobKind::Interactive).await?;
+    let include_quotes = include_quotes.unwrap_or(false);
+    let parsed = parse(&raw);
+    let translator = SegmentTranslator {
+        source_lang: &source_lang,
+        target_lang: &target_lang,
+        model_id: &model_id,
+        state: &state,
+        window: &window,
+    };
+
+    let mut sections = Vec::with_capacity(parsed.blocks.len());
+    for block in &parsed.blocks {
+        let text = match block.kind {
+            BlockKind::Body => translate_paragraphs(&block.text, &translator)?,
+            BlockKind::Quote if include_quotes => translate_paragraphs(&block.text, &translator)?,
+            _ => block.text.clone(),
+        };
+        sections.push(with_prefix(&text, &block.prefix));
+    }
+
+    let body = sections.join("\n");
+    crate::emit_result(&window, &state, &body, &target_lang)?;
+    Ok(TranslatedEmail { headers: parsed.headers, body })
+}
@@ -0,0 +1,225 @@
+// Emoji and kaomoji handling. Small models tend to duplicate, drop or "translate"
+// them, so before prompting each one is either swapped for a numbered placeholder
+// (and restored afterwards), replaced by a short description, or removed.
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmojiPolicy {
+    // Keep them exactly as written
+    #[default]
+    Preserve,
+    // Replace with a short description the model translates along with the text
+    Describe,
+    Drop,
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1FAFF   // Mahjong/cards, enclosed, pictographs, emoticons, transport, supplemental
+        | 0x2600..=0x27BF   // Misc symbols, dingbats
+        | 0x2B50 | 0x2B55 | 0x2B1B | 0x2B1C
+        | 0x3030 | 0x303D | 0x3297 | 0x3299
+    )
+}
+
+// Arrows are plain text (→ in "A → B") unless U+FE0F asks for emoji presentation (↔️)
+fn is_emoji_at(chars: &[char], i: usize) -> bool {
+    let c = chars[i];
+    is_emoji(c) || ((0x2190..=0x21FF).contains(&(c as u32)) && chars.get(i + 1) == Some(&'\u{FE0F}'))
+}
+
+// Joiners, variation selectors, skin tones and tags that continue an emoji sequence
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(c as u32, 0x200D | 0xFE0E | 0xFE0F | 0x20E3 | 0x1F3FB..=0x1F3FF | 0xE0020..=0xE007F)
+}
+
+// Characters that mark a parenthesized group as a face: (^_^) (´・ω・`) (╯°□°).
+// It takes at least two, so (^) or (see ^ above) isn't one.
+const KAOMOJI_FACE_CHARS: &str = "^_;´`°□▽ω・∀＾≧≦○◕‿ツд╥﹏≖ﾟ∇Д￣ε○･ᴗ";
+// Arms and extras that attach directly outside the parentheses: ヽ(^o^)ノ (╯°□°)╯︵ ┻━┻
+const KAOMOJI_ARM_CHARS: &str = "ヽノ╯╰┐┌＼／\\/σｏ٩۶︵┻━彡☆*✧⌒¯";
+const MAX_KAOMOJI_CHARS: usize = 16;
+const MIN_FACE_CHARS: usize = 2;
+
+fn kaomoji_len(chars: &[char]) -> Option<usize> {
+    let mut start = 0;
+    while start < chars.len() && KAOMOJI_ARM_CHARS.contains(chars[start]) {
+        start += 1;
+    }
+    let open = *chars.get(start)?;
+    let close = match open {
+        '(' => ')',
+        '（' => '）',
+        _ => return None,
+    };
+    let body_len = chars[start + 1..].iter().take(MAX_KAOMOJI_CHARS).position(|c| *c == close)?;
+    let body = &chars[start + 1..start + 1 + body_len];
+
+    let face_chars = body.iter().filter(|c| KAOMOJI_FACE_CHARS.contains(**c)).count();
+    let letters = body.iter().filter(|c| c.is_alphanumeric() && !KAOMOJI_FACE_CHARS.contains(**c)).count();
+    if face_chars < MIN_FACE_CHARS || letters > 1 {
+        return None;
+    }
+
+    let mut end = start + body_len + 2;
+    while end < chars.len() && KAOMOJI_ARM_CHARS.contains(chars[end]) && end - start < MAX_KAOMOJI_CHARS * 2 {
+        end += 1;
+    }
+    Some(end)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Symbol {
+    Emoji(String),
+    Kaomoji(String),
+}
+
+/// Splits text into plain runs and emoji/kaomoji symbols.
+fn scan(text: &str) -> Vec<Result<Symbol, String>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut parts = Vec::new();
+    let mut plain = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if is_emoji_at(&chars, i) {
+            let mut end = i + 1;
+            while end < chars.len() && (is_emoji_modifier(chars[end]) || (is_emoji_at(&chars, end) && chars[end - 1] == '\u{200D}')) {
+                end += 1;
+            }
+            // Regional indicator pairs form a single flag
+            if (0x1F1E6..=0x1F1FF).contains(&(chars[i] as u32)) && end < chars.len() && (0x1F1E6..=0x1F1FF).contains(&(chars[end] as u32)) {
+                end += 1;
+            }
+            if !plain.is_empty() {
+                parts.push(Err(std::mem::take(&mut plain)));
+            }
+            parts.push(Ok(Symbol::Emoji(chars[i..end].iter().collect())));
+            i = end;
+        } else if let Some(len) = kaomoji_len(&chars[i..]) {
+            if !plain.is_empty() {
+                parts.push(Err(std::mem::take(&mut plain)));
+            }
+            parts.push(Ok(Symbol::Kaomoji(chars[i..i + len].iter().collect())));
+            i += len;
+        } else {
+            plain.push(chars[i]);
+            i += 1;
+        }
+    }
+    if !plain.is_empty() {
+        parts.push(Err(plain));
+    }
+    parts
+}
+
+pub fn has_symbols(text: &str) -> bool {
+    scan(text).iter().any(|part| part.is_ok())
+}
+
+fn describe(symbol: &Symbol) -> String {
+    let description = match symbol {
+        Symbol::Kaomoji(_) => "emoticon",
+        Symbol::Emoji(emoji) => match emoji.chars().next().map(|c| c as u32).unwrap_or(0) {
+            0x1F602 | 0x1F923 => "laughing face",
+            0x1F64F => "folded hands",
+            0x1F600..=0x1F60F | 0x1F642 | 0x1F917 | 0x1F929 | 0x1F970 => "smiling face",
+            0x1F610..=0x1F62F | 0x1F641 | 0x1F97A => "sad or worried face",
+            0x1F630..=0x1F64E => "face",
+            0x1F44D => "thumbs up",
+            0x1F44E => "thumbs down",
+            0x1F44F => "clapping hands",
+            0x2764 | 0x1F493..=0x1F49F | 0x1F9E1 | 0x1F90D | 0x1F90E | 0x1F5A4 => "heart",
+            0x1F389 | 0x1F38A => "celebration",
+            0x1F525 => "fire",
+            0x2728 | 0x2B50 | 0x1F31F => "sparkles",
+            0x1F1E6..=0x1F1FF => "flag",
+            0x1F300..=0x1F5FF => "symbol",
+            0x1F680..=0x1F6FF => "vehicle",
+            0x1F900..=0x1FAFF => "emoji",
+            _ => "emoji",
+        },
+    };
+    format!("({})", description)
+}
+
+// Placeholders are short and bracketed so models copy them through untouched
+fn placeholder(index: usize) -> String {
+    format!("[E{}]", index + 1)
+}
+
+/// Originals removed from the text by `protect`, restored by `restore`.
+#[derive(Default)]
+pub struct Protected {
+    originals: Vec<String>,
+}
+
+/// Applies `policy` to the source text before prompting. With `Preserve` and
+/// `use_placeholders`, symbols are swapped for placeholders to restore later;
+/// otherwise they are left as-is.
+pub fn protect(text: &str, policy: EmojiPolicy, use_placeholders: bool) -> (String, Protected) {
+    let mut protected = Protected::default();
+    let mut out = String::with_capacity(text.len());
+
+    for part in scan(text) {
+        match part {
+            Err(plain) => out.push_str(&plain),
+            Ok(symbol) => match policy {
+                EmojiPolicy::Preserve if use_placeholders => {
+                    out.push_str(&placeholder(protected.originals.len()));
+                    protected.originals.push(match symbol {
+                        Symbol::Emoji(s) | Symbol::Kaomoji(s) => s,
+                    });
+                }
+                EmojiPolicy::Preserve => match symbol {
+                    Symbol::Emoji(s) | Symbol::Kaomoji(s) => out.push_str(&s),
+                },
+                EmojiPolicy::Describe => out.push_str(&describe(&symbol)),
+                EmojiPolicy::Drop => {
+                    // Avoid leaving double spaces where the symbol was
+                    if out.ends_with(' ') {
+                        out.pop();
+                    }
+                }
+            },
+        }
+    }
+    (out, protected)
+}
+
+/// Puts the original symbols back. Each placeholder is restored once; duplicates the
+/// model invented are removed and placeholders it dropped are appended at the end.
+pub fn restore(output: &str, protected: &Protected) -> String {
+    // Nothing was generated (e.g. cancelled), don't return a row of bare emoji
+    if output.trim().is_empty() {
+        return output.to_string();
+    }
+    let mut out = output.to_string();
+    let mut missing = Vec::new();
+
+    for (i, original) in protected.originals.iter().enumerate() {
+        let marker = placeholder(i);
+        // Some models switch to full-width brackets in CJK output
+        let fullwidth = marker.replace('[', "［").replace(']', "］");
+        out = out.replace(&fullwidth, &marker);
+        // and RTL output can come with the brackets mirrored
+        let mirrored = format!("]{}[", &marker[1..marker.len() - 1]);
+        out = out.replace(&mirrored, &marker);
+
+        match out.find(&marker) {
+            Some(pos) => {
+                let rest = out[pos + marker.len()..].replace(&marker, "");
+                out = format!("{}{}{}", &out[..pos], original, rest);
+            }
+            None => missing.push(original.as_str()),
+        }
+    }
+
+    if !missing.is_empty() {
+        let trimmed_len = out.trim_end().len();
+        out.truncate(trimmed_len);
+        out.push(' ');
+        out.push_str(&missing.join(" "));
+    }
+    out
+}
@@ -0,0 +1,138 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+use crate::{db, AppState};
+
+// Optional encryption at rest for spark.db (translation memory, history,
+// projects), using SQLCipher. The key is random and kept in the OS keychain,
+// never next to the database. Needs a build with `--features encryption`;
+// without it the database stays plain and enabling reports it as unavailable.
+
+// Plain SQLite files start with this header; SQLCipher files are random bytes throughout
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionStatus {
+    // Built with encryption support
+    available: bool,
+    enabled: bool,
+}
+
+/// Whether the database file at `path` is encrypted. A missing or empty file
+/// counts as plain, since it will be created as such.
+pub fn is_encrypted(path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    match File::open(path).and_then(|mut file| file.read_exact(&mut header)) {
+        Ok(()) => &header != SQLITE_HEADER,
+        Err(_) => false,
+    }
+}
+
+#[cfg(feature = "encryption")]
+mod imp {
+    use std::path::Path;
+    use rusqlite::{params, Connection, DatabaseName};
+
+    pub const AVAILABLE: bool = true;
+
+    const KEYCHAIN_SERVICE: &str = "Spark";
+    const KEYCHAIN_ACCOUNT: &str = "database-key";
+
+    fn entry() -> Result<keyring::Entry, String> {
+        keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT).map_err(|e| format!("Keychain unavailable: {}", e))
+    }
+
+    /// The database key as a SQLCipher raw key literal, created on first use.
+    pub fn key(create: bool) -> Result<String, String> {
+        let entry = entry()?;
+        let hex = match entry.get_password() {
+            Ok(hex) => hex,
+            Err(keyring::Error::NoEntry) if create => {
+                let mut bytes = [0u8; 32];
+                getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate a key: {}", e))?;
+                let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                entry.set_password(&hex).map_err(|e| format!("Failed to store the key in the keychain: {}", e))?;
+                hex
+            }
+            Err(e) => return Err(format!("Failed to read the database key from the keychain: {}", e)),
+        };
+        Ok(format!("x'{}'", hex))
+    }
+
+    pub fn forget_key() -> Result<(), String> {
+        match entry()?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("Failed to remove the key from the keychain: {}", e)),
+        }
+    }
+
+    /// Must run before anything else on a freshly opened encrypted database.
+    pub fn unlock(conn: &Connection) -> Result<(), String> {
+        conn.pragma_update(None, "key", key(false)?).map_err(|e| format!("Failed to unlock database: {}", e))
+    }
+
+    /// Copies the open database to `target`, encrypted with `key` or plain with None.
+    pub fn export(conn: &Connection, target: &Path, key: Option<&str>) -> rusqlite::Result<()> {
+        conn.execute("ATTACH DATABASE ?1 AS export KEY ?2", params![target.to_string_lossy(), key.unwrap_or("")])?;
+        conn.query_row("SELECT sqlcipher_export('export')", [], |_| Ok(()))?;
+        // sqlcipher_export doesn't carry over the migration version
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        conn.pragma_update(Some(DatabaseName::Attached("export")), "user_version", version)?;
+        conn.execute("DETACH DATABASE export", [])?;
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "encryption"))]
+mod imp {
+    use std::path::Path;
+    use rusqlite::Connection;
+
+    pub const AVAILABLE: bool = false;
+
+    const UNAVAILABLE: &str = "This build of Spark doesn't include database encryption";
+
+    pub fn key(_create: bool) -> Result<String, String> {
+        Err(UNAVAILABLE.to_string())
+    }
+
+    pub fn forget_key() -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn unlock(_conn: &Connection) -> Result<(), String> {
+        Err(format!("The database is encrypted. {}", UNAVAILABLE))
+    }
+
+    pub fn export(_conn: &Connection, _target: &Path, _key: Option<&str>) -> rusqlite::Result<()> {
+        Err(rusqlite::Error::InvalidQuery)
+    }
+}
+
+pub use imp::unlock;
+
+fn status(path: &Path) -> EncryptionStatus {
+    EncryptionStatus { available: imp::AVAILABLE, enabled: is_encrypted(path) }
+}
+
+#[tauri::command]
+pub async fn get_database_encryption(app: AppHandle) -> Result<EncryptionStatus, String> {
+    Ok(status(&db::path(&app)?))
+}
+
+/// Encrypts or decrypts the database in place (a full copy, then a swap).
+#[tauri::command]
+pub async fn set_database_encryption(enabled: bool, app: AppHandle, state: State<'_, AppState>) -> Result<EncryptionStatus, String> {
+    let path = db::path(&app)?;
+    if is_encrypted(&path) != enabled {
+        let key = if enabled { Some(imp::key(true)?) } else { None };
+        state.db.rewrite(&app, |conn, target| imp::export(conn, target, key.as_deref()))?;
+        if !enabled {
+            imp::forget_key()?;
+        }
+    }
+    Ok(status(&path))
+}
@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use crate::prompts::PromptTemplates;
+use crate::sampler::SamplerSettings;
+use crate::{model_check, model_registry, AppState};
+
+// What a translation was made with, stored with its history entry so it can be
+// reproduced, or explained when two translations of the same text differ: the
+// app version, the model file and its SHA-256, a fingerprint of the prompt
+// templates and the sampler settings. Hashing a model of several gigabytes
+// takes a while, so it runs in the background once the model has loaded
+// (unless model_check.rs already knows the hash); entries finished before
+// that have no hash.
+
+// Hex digits of the prompt template fingerprint
+const PROMPT_VERSION_LEN: usize = 12;
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobEnvironment {
+    pub app_version: String,
+    // File name of the loaded model
+    pub model_file: Option<String>,
+    pub model_sha256: Option<String>,
+    // Changes whenever the prompt templates are edited or updated
+    pub prompt_version: String,
+    pub sampler: SamplerSettings,
+}
+
+// The loaded model file and its hash, once known
+struct LoadedModel {
+    path: PathBuf,
+    sha256: Option<String>,
+}
+
+static LOADED: OnceLock<Mutex<Option<LoadedModel>>> = OnceLock::new();
+
+fn with_loaded<T>(f: impl FnOnce(&mut Option<LoadedModel>) -> T) -> T {
+    f(&mut LOADED.get_or_init(Default::default).lock().unwrap())
+}
+
+/// Notes the model file that was just loaded and hashes it in the background,
+/// unless its hash is already known (see model_check.rs).
+pub fn model_loaded(app: &AppHandle, path: PathBuf) {
+    let known = model_check::known(app, &path);
+    let hashed = known.is_some();
+    with_loaded(|loaded| *loaded = Some(LoadedModel { path: path.clone(), sha256: known }));
+    if hashed {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || match model_check::hash(&app, &path, || false) {
+        Ok(hash) => with_loaded(|loaded| match loaded {
+            // Unless another model was loaded meanwhile
+            Some(loaded) if loaded.path == path => loaded.sha256 = hash,
+            _ => {}
+        }),
+        Err(e) => eprintln!("Failed to hash model {:?}: {}", path, e),
+    });
+}
+
+// The loaded model's file name and hash, if it has been hashed yet
+fn loaded_model() -> (Option<String>, Option<String>) {
+    with_loaded(|loaded| match loaded {
+        Some(loaded) => (loaded.path.file_name().map(|name| name.to_string_lossy().into_owned()), loaded.sha256.clone()),
+        None => (None, None),
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn prompt_version(templates: &PromptTemplates) -> String {
+    let mut hasher = Sha256::new();
+    for template in [&templates.system, &templates.chat, &templates.example] {
+        hasher.update(template.as_bytes());
+        // Keeps "ab" + "c" apart from "a" + "bc"
+        hasher.update([0]);
+    }
+    let mut version = hex(&hasher.finalize());
+    version.truncate(PROMPT_VERSION_LEN);
+    version
+}
+
+/// The environment of a job with `model_id` that just finished with `sampler`.
+pub fn snapshot(state: &AppState, model_id: &str, sampler: SamplerSettings) -> JobEnvironment {
+    let (model_file, model_sha256) = loaded_model();
+    JobEnvironment {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        model_file,
+        model_sha256,
+        prompt_version: prompt_version(&model_registry::templates(state, model_id)),
+        sampler,
+    }
+}
@@ -0,0 +1,128 @@
+use llama_cpp_2::model::params::LlamaModelParams;
+use llama_cpp_2::model::{AddBos, LlamaModel};
+use tauri::State;
+
+use crate::segmenter::Segmenter;
+use crate::{chunk_size, history, model_registry, models, prompts, token_cache, AppState};
+
+// Up-front estimate of a translation job: chunk count, token counts and
+// duration, so the UI can warn before a long job starts. The text is tokenized
+// with the model's own vocabulary; speeds come from the timings of recent
+// translations with the same model (see timings.rs), or rough defaults for a
+// model that hasn't been used yet.
+
+// Translations recorded with timings that are averaged for the speeds
+const SAMPLE_JOBS: u32 = 50;
+// Output tokens per source token; translations run a little longer than their source
+const OUTPUT_RATIO: f64 = 1.2;
+
+#[derive(Clone, Copy)]
+struct Speeds {
+    prompt_tokens_per_second: f64,
+    output_tokens_per_second: f64,
+    load_seconds: f64,
+}
+
+// Typical CPU speeds of the bundled models, before any job has been measured
+fn default_speeds(model_id: &str) -> Speeds {
+    let (prompt, output, load) = match model_id {
+        "high" => (60.0, 6.0, 8.0),
+        "balanced" => (150.0, 14.0, 4.0),
+        _ => (400.0, 35.0, 2.0),
+    };
+    Speeds { prompt_tokens_per_second: prompt, output_tokens_per_second: output, load_seconds: load }
+}
+
+// Speeds measured over recent jobs, where there is enough data for each
+fn measured_speeds(state: &AppState, model_id: &str) -> Option<Speeds> {
+    let jobs = history::recent_timings(state, model_id, SAMPLE_JOBS);
+    let chunks = || jobs.iter().flat_map(|job| &job.chunks);
+    let rate = |tokens: usize, ms: u64| (ms > 0 && tokens > 0).then(|| tokens as f64 * 1000.0 / ms as f64);
+    let prompt = rate(chunks().map(|c| c.prompt_tokens).sum(), chunks().map(|c| c.prompt_ms).sum())?;
+    let output = rate(chunks().map(|c| c.output_tokens).sum(), chunks().map(|c| c.generation_ms).sum())?;
+    let loads: Vec<u64> = jobs.iter().map(|job| job.load_ms).filter(|&ms| ms > 0).collect();
+    let load_seconds = match loads.len() {
+        0 => default_speeds(model_id).load_seconds,
+        n => loads.iter().sum::<u64>() as f64 / n as f64 / 1000.0,
+    };
+    Some(Speeds { prompt_tokens_per_second: prompt, output_tokens_per_second: output, load_seconds })
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobEstimate {
+    chunks: usize,
+    source_tokens: usize,
+    // Including the instructions repeated in every chunk's prompt
+    prompt_tokens: usize,
+    output_tokens: usize,
+    // Zero when the model is already loaded
+    load_seconds: f64,
+    estimated_seconds: f64,
+    prompt_tokens_per_second: f64,
+    output_tokens_per_second: f64,
+    // False when the speeds are defaults rather than measured on this machine
+    measured: bool,
+}
+
+fn count_tokens(model: &LlamaModel, model_id: &str, chunks: &[String], state: &AppState) -> Result<(usize, usize), String> {
+    let tokenize = |text: &str| token_cache::tokenize(model, model_id, text, AddBos::Never).map(|tokens| tokens.len());
+    // Glossary and example turns vary per chunk and are left out
+    let templates = model_registry::templates(state, model_id);
+    let empty_prompt = prompts::render(
+        &templates.chat,
+        &[("system", &templates.system), ("target_lang", ""), ("examples", ""), ("source", "")],
+    );
+    let overhead = tokenize(&empty_prompt)?;
+    let mut source_tokens = 0;
+    for chunk in chunks {
+        source_tokens += tokenize(chunk)?;
+    }
+    Ok((source_tokens, source_tokens + overhead * chunks.len()))
+}
+
+/// Estimates how long translating `text` with `model_id` will take.
+#[tauri::command]
+pub async fn estimate_job(text: String, model_id: String, state: State<'_, AppState>) -> Result<JobEstimate, String> {
+    let segmenter = Segmenter::for_language("", &text);
+    let chunks: Vec<String> = crate::split_into_chunks(&text, chunk_size::limit(&state, "", &text), segmenter)
+        .into_iter()
+        .map(|(chunk, _)| chunk)
+        .collect();
+    let loaded = state.current_model_id.lock().unwrap().as_deref() == Some(model_id.as_str());
+
+    // The loaded model's vocabulary if it is free, otherwise just the vocabulary from the file
+    let counted = match state.model.try_lock() {
+        Ok(model) if loaded && model.is_some() => model.as_ref().map(|model| count_tokens(model, &model_id, &chunks, &state)),
+        _ => None,
+    };
+    let (source_tokens, prompt_tokens) = match counted {
+        Some(counts) => counts?,
+        None => {
+            let path = models::resolve(&state, &model_id)?;
+            let params = LlamaModelParams::default().with_vocab_only(true);
+            let vocab = LlamaModel::load_from_file(&state._backend, &path, &params)
+                .map_err(|e| format!("Failed to load vocabulary: {}", e))?;
+            count_tokens(&vocab, &model_id, &chunks, &state)?
+        }
+    };
+
+    let measured = measured_speeds(&state, &model_id);
+    let speeds = measured.unwrap_or_else(|| default_speeds(&model_id));
+    let output_tokens = (source_tokens as f64 * OUTPUT_RATIO).round() as usize;
+    let load_seconds = if loaded { 0.0 } else { speeds.load_seconds };
+    let estimated_seconds = load_seconds
+        + prompt_tokens as f64 / speeds.prompt_tokens_per_second
+        + output_tokens as f64 / speeds.output_tokens_per_second;
+    Ok(JobEstimate {
+        chunks: chunks.len(),
+        source_tokens,
+        prompt_tokens,
+        output_tokens,
+        load_seconds,
+        estimated_seconds,
+        prompt_tokens_per_second: speeds.prompt_tokens_per_second,
+        output_tokens_per_second: speeds.output_tokens_per_second,
+        measured: measured.is_some(),
+    })
+}
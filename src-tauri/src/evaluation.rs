@@ -0,0 +1,122 @@
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use tauri::{Emitter, State, Window};
+
+use crate::documents::read_document;
+use crate::metrics::{self, Corpus, Scores};
+use crate::{priority, AppState, TranslateOptions};
+
+// Quantitative evaluation over a line-aligned test set: line N of the source
+// (or hypothesis) file pairs with line N of the reference file.
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluationPair {
+    source: Option<String>,
+    reference: String,
+    hypothesis: String,
+    chrf: f64,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluationReport {
+    model_id: Option<String>,
+    segments: usize,
+    scores: Scores,
+    pairs: Vec<EvaluationPair>,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct EvaluationProgress {
+    done: usize,
+    total: usize,
+}
+
+// Non-empty lines; blank lines are skipped in both files alike
+pub fn test_lines(path: &str) -> Result<Vec<String>, String> {
+    let text = read_document(Path::new(path))?;
+    Ok(text.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect())
+}
+
+// Tab-separated export of the scored pairs, one per line
+fn export_pairs(path: &str, pairs: &[EvaluationPair]) -> Result<(), String> {
+    let clean = |text: &str| text.replace(['\t', '\n', '\r'], " ");
+    let mut out = String::from("source\treference\thypothesis\tchrf\n");
+    for pair in pairs {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{:.2}\n",
+            clean(pair.source.as_deref().unwrap_or("")),
+            clean(&pair.reference),
+            clean(&pair.hypothesis),
+            pair.chrf
+        ));
+    }
+    std::fs::write(path, out).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
+
+/// Scores translations of a test set against `reference_file` with BLEU and chrF.
+/// `hypothesis_source` is normally the source side of the test set, translated
+/// here with `model_id`; with `pretranslated` it already holds the hypotheses
+/// (e.g. output of another tool). `export_path` receives the scored pairs as TSV.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn evaluate(
+    reference_file: String,
+    hypothesis_source: String,
+    source_lang: String,
+    target_lang: String,
+    model_id: Option<String>,
+    pretranslated: Option<bool>,
+    export_path: Option<String>,
+    state: State<'_, AppState>,
+    window: Window,
+) -> Result<EvaluationReport, String> {
+    let references = test_lines(&reference_file)?;
+    let inputs = test_lines(&hypothesis_source)?;
+    if references.len() != inputs.len() {
+        return Err(format!(
+            "Test set is not aligned: {} reference lines but {} source lines",
+            references.len(),
+            inputs.len()
+        ));
+    }
+    let pretranslated = pretranslated.unwrap_or(false);
+    let model_id = match (&model_id, pretranslated) {
+        (None, false) => return Err("A model is required to translate the test set".to_string()),
+        (_, true) => None,
+        (Some(id), false) => Some(id.clone()),
+    };
+
+    // Waits for earlier jobs and resets the cancellation flag
+    let _turn = state.jobs.start(&state, &window, priority::JobKind::Background).await?;
+    // No await points below, so the guard is dropped on this thread
+    let _priority = priority::PriorityGuard::new(&state, priority::JobKind::Background);
+
+    let total = references.len();
+    let mut corpus = Corpus::default();
+    let mut pairs = Vec::with_capacity(total);
+    let options = TranslateOptions::default();
+    for (input, reference) in inputs.into_iter().zip(references) {
+        if state.is_cancelled.load(Ordering::Relaxed) {
+            return Err("Evaluation cancelled".to_string());
+        }
+        let (source, hypothesis) = match &model_id {
+            Some(id) => {
+                let hypothesis = crate::run_translation(&input, &source_lang, &target_lang, id, &state, &window, &options)?;
+                (Some(input), hypothesis.trim().to_string())
+            }
+            None => (None, input),
+        };
+        corpus.add(&hypothesis, &reference);
+        let chrf = metrics::sentence_chrf(&hypothesis, &reference);
+        pairs.push(EvaluationPair { source, reference, hypothesis, chrf });
+        let progress = EvaluationProgress { done: pairs.len(), total };
+        window.emit("evaluation-progress", progress).unwrap_or(());
+    }
+
+    if let Some(path) = export_path {
+        export_pairs(&path, &pairs)?;
+    }
+    Ok(EvaluationReport { model_id, segments: pairs.len(), scores: corpus.scores(), pairs })
+}
@@ -0,0 +1,44 @@
+use rusqlite::params;
+
+use crate::{db, projects, AppState};
+
+// Few-shot example pool: human-corrected translations that are replayed to the
+// model as earlier conversation turns, so it picks up the reviewer's wording.
+// Scoped like the glossary and TM (project first, then global).
+
+// Examples are repeated in every prompt, so only a few short ones are used
+const MAX_EXAMPLES: usize = 3;
+const MAX_EXAMPLE_BYTES: usize = 400;
+
+pub fn add(state: &AppState, source_text: &str, target_text: &str, source_lang: &str, target_lang: &str, project_id: Option<i64>) -> Result<(), String> {
+    state.db.with(|conn| {
+        conn.execute(
+            "INSERT INTO fewshot_examples (project_id, source_lang, target_lang, source_text, target_text, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![project_id, source_lang, target_lang, source_text, target_text, db::now()],
+        )
+    })?;
+    Ok(())
+}
+
+/// Newest short examples for the language pair, for the project (default: the
+/// active one) or global.
+pub fn for_prompt(state: &AppState, source_lang: &str, target_lang: &str, project_id: Option<i64>) -> Vec<(String, String)> {
+    let project_id = projects::scope(state, project_id);
+    state
+        .db
+        .with(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT source_text, target_text FROM fewshot_examples
+                 WHERE source_lang = ?1 AND target_lang = ?2 AND (project_id IS NULL OR project_id = ?3)
+                   AND length(CAST(source_text AS BLOB)) + length(CAST(target_text AS BLOB)) <= ?4
+                 ORDER BY project_id IS NULL, created_at DESC LIMIT ?5",
+            )?;
+            let rows = stmt.query_map(
+                params![source_lang, target_lang, project_id, MAX_EXAMPLE_BYTES as i64, MAX_EXAMPLES as i64],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            rows.collect()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,220 @@
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+use rusqlite::{params, Connection, OptionalExtension};
+use tauri::{Emitter, State, Window};
+
+use crate::evaluation::test_lines;
+use crate::metrics::{Corpus, Scores};
+use crate::sampler::SamplerSettings;
+use crate::{db, priority, AppState, TranslateOptions};
+
+// Prompt/parameter experiments: run several variants (model, system prompt,
+// sampler) over the same input set, store every output with its metrics, and
+// report them side by side. Saves rebuilding the app to tune a prompt.
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Variant {
+    name: String,
+    model_id: String,
+    // None keeps the built-in translation prompt
+    #[serde(default)]
+    system_prompt: Option<String>,
+    #[serde(default)]
+    sampler: SamplerSettings,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariantResult {
+    variant: Variant,
+    // None when the input set has no references
+    scores: Option<Scores>,
+    duration_ms: u64,
+    outputs: Vec<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentReport {
+    id: i64,
+    name: String,
+    source_file: String,
+    reference_file: Option<String>,
+    source_lang: String,
+    target_lang: String,
+    created_at: i64,
+    // Best chrF first when scored, otherwise in run order
+    results: Vec<VariantResult>,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentSummary {
+    id: i64,
+    name: String,
+    created_at: i64,
+    variants: usize,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ExperimentProgress<'a> {
+    variant: &'a str,
+    done: usize,
+    total: usize,
+}
+
+fn sort_results(results: &mut [VariantResult]) {
+    results.sort_by(|a, b| {
+        let chrf = |r: &VariantResult| r.scores.map(|s| s.chrf).unwrap_or(f64::MIN);
+        chrf(b).total_cmp(&chrf(a))
+    });
+}
+
+fn load_report(conn: &Connection, id: i64) -> rusqlite::Result<Option<ExperimentReport>> {
+    let report = conn
+        .query_row(
+            "SELECT name, source_file, reference_file, source_lang, target_lang, created_at FROM experiments WHERE id = ?1",
+            [id],
+            |row| {
+                Ok(ExperimentReport {
+                    id,
+                    name: row.get(0)?,
+                    source_file: row.get(1)?,
+                    reference_file: row.get(2)?,
+                    source_lang: row.get(3)?,
+                    target_lang: row.get(4)?,
+                    created_at: row.get(5)?,
+                    results: Vec::new(),
+                })
+            },
+        )
+        .optional()?;
+    let Some(mut report) = report else {
+        return Ok(None);
+    };
+    let mut stmt = conn.prepare(
+        "SELECT config, bleu, chrf, duration_ms, outputs FROM experiment_results WHERE experiment_id = ?1 ORDER BY id",
+    )?;
+    let rows = stmt.query_map([id], |row| {
+        let config: String = row.get(0)?;
+        let bleu: Option<f64> = row.get(1)?;
+        let chrf: Option<f64> = row.get(2)?;
+        let outputs: String = row.get(4)?;
+        Ok((config, bleu.zip(chrf), row.get::<_, i64>(3)?, outputs))
+    })?;
+    for row in rows {
+        let (config, scores, duration_ms, outputs) = row?;
+        // Rows are written by this module, so they always parse; skip rather than fail if not
+        let (Ok(variant), Ok(outputs)) = (serde_json::from_str(&config), serde_json::from_str(&outputs)) else {
+            continue;
+        };
+        report.results.push(VariantResult {
+            variant,
+            scores: scores.map(|(bleu, chrf)| Scores { bleu, chrf }),
+            duration_ms: duration_ms as u64,
+            outputs,
+        });
+    }
+    sort_results(&mut report.results);
+    Ok(Some(report))
+}
+
+/// Translates every line of `source_file` with each variant, scores the outputs
+/// against `reference_file` (line-aligned) when given, and stores the run.
+/// Emits `experiment-progress` per line.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn run_experiment(
+    name: String,
+    source_file: String,
+    reference_file: Option<String>,
+    source_lang: String,
+    target_lang: String,
+    variants: Vec<Variant>,
+    state: State<'_, AppState>,
+    window: Window,
+) -> Result<ExperimentReport, String> {
+    if variants.is_empty() {
+        return Err("An experiment needs at least one variant".to_string());
+    }
+    let inputs = test_lines(&source_file)?;
+    let references = reference_file.as_deref().map(test_lines).transpose()?;
+    if references.as_ref().is_some_and(|refs| refs.len() != inputs.len()) {
+        return Err("Reference file is not aligned with the input set".to_string());
+    }
+
+    let id = state.db.with(|conn| {
+        conn.execute(
+            "INSERT INTO experiments (name, source_file, reference_file, source_lang, target_lang, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![name, source_file, reference_file, source_lang, target_lang, db::now()],
+        )?;
+        Ok(conn.last_insert_rowid())
+    })?;
+
+    // Waits for earlier jobs and resets the cancellation flag
+    let _turn = state.jobs.start(&state, &window, priority::JobKind::Background).await?;
+    // No await points below, so the guard is dropped on this thread
+    let _priority = priority::PriorityGuard::new(&state, priority::JobKind::Background);
+
+    for variant in variants {
+        let options = TranslateOptions {
+            system_prompt: variant.system_prompt.clone(),
+            sampler: Some(variant.sampler.clone()),
+            ..Default::default()
+        };
+        let started = Instant::now();
+        let mut outputs = Vec::with_capacity(inputs.len());
+        for input in &inputs {
+            if state.is_cancelled.load(Ordering::Relaxed) {
+                return Err("Experiment cancelled".to_string());
+            }
+            let output = crate::run_translation(input, &source_lang, &target_lang, &variant.model_id, &state, &window, &options)?;
+            outputs.push(output.trim().to_string());
+            let progress = ExperimentProgress { variant: &variant.name, done: outputs.len(), total: inputs.len() };
+            window.emit("experiment-progress", progress).unwrap_or(());
+        }
+        let duration_ms = started.elapsed().as_millis() as i64;
+
+        let scores = references.as_ref().map(|refs| {
+            let mut corpus = Corpus::default();
+            for (output, reference) in outputs.iter().zip(refs) {
+                corpus.add(output, reference);
+            }
+            corpus.scores()
+        });
+        let config = serde_json::to_string(&variant).map_err(|e| e.to_string())?;
+        let outputs = serde_json::to_string(&outputs).map_err(|e| e.to_string())?;
+        state.db.with(|conn| {
+            conn.execute(
+                "INSERT INTO experiment_results (experiment_id, config, bleu, chrf, duration_ms, outputs)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![id, config, scores.map(|s| s.bleu), scores.map(|s| s.chrf), duration_ms, outputs],
+            )
+        })?;
+    }
+
+    state.db.with(|conn| load_report(conn, id))?.ok_or_else(|| format!("Experiment {} not found", id))
+}
+
+#[tauri::command]
+pub async fn get_experiment(id: i64, state: State<'_, AppState>) -> Result<ExperimentReport, String> {
+    state.db.with(|conn| load_report(conn, id))?.ok_or_else(|| format!("Experiment {} not found", id))
+}
+
+/// Stored experiments, newest first.
+#[tauri::command]
+pub async fn list_experiments(state: State<'_, AppState>) -> Result<Vec<ExperimentSummary>, String> {
+    state.db.with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT e.id, e.name, e.created_at, COUNT(r.id) FROM experiments e
+             LEFT JOIN experiment_results r ON r.experiment_id = e.id
+             GROUP BY e.id ORDER BY e.created_at DESC, e.id DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ExperimentSummary { id: row.get(0)?, name: row.get(1)?, created_at: row.get(2)?, variants: row.get(3)? })
+        })?;
+        rows.collect()
+    })
+}
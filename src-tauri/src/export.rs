@@ -0,0 +1,214 @@
+use std::path::{Path, PathBuf};
+
+// Output naming and layouts for file jobs: the plain translation, the source and
+// translation side by side (.txt columns or an .html table), or each source line
+// followed by its translation. Layouts are produced chunk by chunk so they can be
+// streamed to disk as the translation is generated.
+
+/// Default output name: "notes.txt" -> "notes.japanese.txt"
+pub const DEFAULT_FILE_NAME: &str = "{name}.{target}.{ext}";
+
+// Display cells per column in the side-by-side text layout
+const COLUMN_WIDTH: usize = 40;
+const COLUMN_SEPARATOR: &str = " | ";
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputTemplate {
+    // Translation only, same format as the input
+    #[default]
+    Translation,
+    SideBySideText,
+    SideBySideHtml,
+    // Each source line followed by its translation
+    Interleaved,
+}
+
+impl OutputTemplate {
+    // Extension forced by the layout, if any
+    fn extension(self) -> Option<&'static str> {
+        match self {
+            OutputTemplate::SideBySideText => Some("txt"),
+            OutputTemplate::SideBySideHtml => Some("html"),
+            OutputTemplate::Translation | OutputTemplate::Interleaved => None,
+        }
+    }
+}
+
+// "Simplified Chinese" -> "simplified-chinese"
+fn language_tag(language: &str) -> String {
+    language.trim().to_lowercase().replace(' ', "-")
+}
+
+/// Resolves an output file name pattern against the input path. Supports
+/// `{name}` (input file stem), `{ext}`, `{source}` and `{target}`; relative
+/// patterns (including subfolders) are placed next to the input.
+pub fn output_path(pattern: &str, input: &Path, source_lang: &str, target_lang: &str, template: OutputTemplate) -> PathBuf {
+    let stem = input.file_stem().and_then(|s| s.to_str()).unwrap_or("document");
+    let ext = template
+        .extension()
+        .or_else(|| input.extension().and_then(|e| e.to_str()))
+        .unwrap_or("");
+    // Files without an extension shouldn't end up as "notes.japanese."
+    let pattern = if ext.is_empty() { pattern.replace(".{ext}", "") } else { pattern.to_string() };
+    let name = pattern
+        .replace("{name}", stem)
+        .replace("{ext}", ext)
+        .replace("{source}", &language_tag(source_lang))
+        .replace("{target}", &language_tag(target_lang));
+    match input.parent() {
+        Some(dir) => dir.join(name),
+        None => PathBuf::from(name),
+    }
+}
+
+fn char_width(c: char) -> usize {
+    let wide = matches!(c as u32,
+        0x1100..=0x115F | 0x2E80..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF |
+        0xFE30..=0xFE4F | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6 | 0x1F300..=0x1FAFF | 0x20000..=0x3FFFD);
+    if wide { 2 } else { 1 }
+}
+
+fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+// Wraps a line to `width` display cells, breaking after spaces where possible
+// and anywhere in unspaced scripts or overlong words.
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let mut rows = Vec::new();
+    let mut current = String::new();
+    let mut current_width = 0;
+    // Byte offset just after the last space in `current`
+    let mut last_break = None;
+
+    for c in line.chars() {
+        let w = char_width(c);
+        if current_width + w > width && !current.is_empty() {
+            let rest = match last_break {
+                Some(at) if at < current.len() => current.split_off(at),
+                _ => String::new(),
+            };
+            rows.push(current.trim_end().to_string());
+            current_width = display_width(&rest);
+            current = rest;
+            last_break = None;
+        }
+        current.push(c);
+        current_width += w;
+        if c == ' ' {
+            last_break = Some(current.len());
+        }
+    }
+    rows.push(current.trim_end().to_string());
+    rows
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// Pairs source and translated lines when the model kept the line structure;
+// otherwise the whole chunk becomes a single pair.
+fn pair_lines<'a>(source: &'a str, translated: &'a str) -> Vec<(&'a str, &'a str)> {
+    let source_lines: Vec<&str> = source.lines().collect();
+    let translated_lines: Vec<&str> = translated.lines().collect();
+    if source_lines.len() == translated_lines.len() {
+        source_lines.into_iter().zip(translated_lines).collect()
+    } else {
+        vec![(source, translated)]
+    }
+}
+
+/// Renders a file job's output in the chosen template.
+pub struct Layout {
+    template: OutputTemplate,
+    source_lang: String,
+    target_lang: String,
+}
+
+impl Layout {
+    pub fn new(template: OutputTemplate, source_lang: &str, target_lang: &str) -> Self {
+        Layout { template, source_lang: source_lang.to_string(), target_lang: target_lang.to_string() }
+    }
+
+    /// Start of the file; `summary` is an optional paragraph shown above the text.
+    pub fn header(&self, title: &str, summary: Option<&str>) -> String {
+        match self.template {
+            OutputTemplate::SideBySideHtml => {
+                let mut out = format!(
+                    "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n\
+                     table {{ border-collapse: collapse; width: 100%; }}\n\
+                     th, td {{ width: 50%; padding: 4px 8px; border-bottom: 1px solid #ddd; text-align: left; vertical-align: top; white-space: pre-wrap; }}\n\
+                     </style>\n</head>\n<body>\n",
+                    escape_html(title)
+                );
+                if let Some(summary) = summary {
+                    out.push_str(&format!("<p class=\"summary\">{}</p>\n<hr>\n", escape_html(summary)));
+                }
+                out.push_str(&format!(
+                    "<table>\n<thead><tr><th>{}</th><th>{}</th></tr></thead>\n<tbody>\n",
+                    escape_html(&self.source_lang),
+                    escape_html(&self.target_lang)
+                ));
+                out
+            }
+            _ => summary.map(|s| format!("{}\n\n---\n\n", s)).unwrap_or_default(),
+        }
+    }
+
+    /// One translated chunk. `first` is false for every chunk after the first.
+    pub fn chunk(&self, source: &str, translated: &str, first: bool) -> String {
+        let mut out = String::new();
+        match self.template {
+            OutputTemplate::Translation => {
+                if !first {
+                    out.push('\n');
+                }
+                out.push_str(translated);
+            }
+            OutputTemplate::Interleaved => {
+                for (source_line, translated_line) in pair_lines(source, translated) {
+                    if source_line.trim().is_empty() && translated_line.trim().is_empty() {
+                        continue;
+                    }
+                    out.push_str(&format!("{}\n{}\n\n", source_line, translated_line));
+                }
+            }
+            OutputTemplate::SideBySideText => {
+                if !first {
+                    out.push('\n');
+                }
+                for (source_line, translated_line) in pair_lines(source, translated) {
+                    let left: Vec<String> = source_line.lines().flat_map(|l| wrap(l, COLUMN_WIDTH)).collect();
+                    let right: Vec<String> = translated_line.lines().flat_map(|l| wrap(l, COLUMN_WIDTH)).collect();
+                    for row in 0..left.len().max(right.len()).max(1) {
+                        let l = left.get(row).map(String::as_str).unwrap_or("");
+                        let r = right.get(row).map(String::as_str).unwrap_or("");
+                        let padding = " ".repeat(COLUMN_WIDTH.saturating_sub(display_width(l)));
+                        out.push_str(format!("{}{}{}{}", l, padding, COLUMN_SEPARATOR, r).trim_end());
+                        out.push('\n');
+                    }
+                }
+            }
+            OutputTemplate::SideBySideHtml => {
+                for (source_line, translated_line) in pair_lines(source, translated) {
+                    out.push_str(&format!(
+                        "<tr><td>{}</td><td>{}</td></tr>\n",
+                        escape_html(source_line),
+                        escape_html(translated_line)
+                    ));
+                }
+            }
+        }
+        out
+    }
+
+    /// End of the file.
+    pub fn footer(&self) -> String {
+        match self.template {
+            OutputTemplate::SideBySideHtml => "</tbody>\n</table>\n</body>\n</html>\n".to_string(),
+            _ => String::new(),
+        }
+    }
+}
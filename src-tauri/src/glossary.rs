@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use rusqlite::{params, Transaction};
+use tauri::State;
+
+use crate::{projects, AppState};
+
+// Terminology glossary. Terms belong either to a project or to the global
+// glossary; lookups use the active project's terms and fall back to global ones,
+// with a project term overriding a global entry for the same source term.
+// Every prompt looks the terms up, so they are cached per language pair and
+// project until the glossary changes.
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlossaryTerm {
+    id: i64,
+    // None for global terms
+    project_id: Option<i64>,
+    pub source_term: String,
+    pub target_term: String,
+    note: Option<String>,
+}
+
+type CacheKey = (String, String, Option<i64>);
+
+static TERMS: OnceLock<Mutex<HashMap<CacheKey, Vec<GlossaryTerm>>>> = OnceLock::new();
+
+fn with_cache<T>(f: impl FnOnce(&mut HashMap<CacheKey, Vec<GlossaryTerm>>) -> T) -> T {
+    f(&mut TERMS.get_or_init(Default::default).lock().unwrap())
+}
+
+// Called after every change to the glossary
+fn invalidate() {
+    with_cache(|cache| cache.clear());
+}
+
+// A global term as stored in a settings export, see settings.rs
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortableTerm {
+    source_lang: String,
+    target_lang: String,
+    source_term: String,
+    target_term: String,
+    #[serde(default)]
+    note: Option<String>,
+}
+
+// Replaces any entry for the same source term in the same scope
+fn upsert(tx: &Transaction, scope: Option<i64>, term: &PortableTerm) -> rusqlite::Result<()> {
+    tx.execute(
+        "DELETE FROM glossary_terms WHERE project_id IS ?1 AND source_lang = ?2 AND target_lang = ?3 AND lower(source_term) = lower(?4)",
+        params![scope, term.source_lang, term.target_lang, term.source_term],
+    )?;
+    tx.execute(
+        "INSERT INTO glossary_terms (project_id, source_lang, target_lang, source_term, target_term, note) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![scope, term.source_lang, term.target_lang, term.source_term, term.target_term, term.note],
+    )?;
+    Ok(())
+}
+
+/// The global glossary, all language pairs. Project terms stay with their project.
+pub fn export_global(state: &AppState) -> Result<Vec<PortableTerm>, String> {
+    state.db.with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT source_lang, target_lang, source_term, target_term, note FROM glossary_terms
+             WHERE project_id IS NULL ORDER BY source_lang, target_lang, source_term",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(PortableTerm {
+                source_lang: row.get(0)?,
+                target_lang: row.get(1)?,
+                source_term: row.get(2)?,
+                target_term: row.get(3)?,
+                note: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+/// Merges exported terms into the global glossary; imported entries win.
+pub fn import_global(state: &AppState, terms: &[PortableTerm]) -> Result<(), String> {
+    let result = state.db.with(|conn| {
+        let tx = conn.transaction()?;
+        for term in terms.iter().filter(|t| !t.source_term.trim().is_empty() && !t.target_term.trim().is_empty()) {
+            upsert(&tx, None, term)?;
+        }
+        tx.commit()
+    });
+    invalidate();
+    result
+}
+
+/// Terms for a language pair visible from `project_id`, project terms first.
+pub fn terms(state: &AppState, source_lang: &str, target_lang: &str, project_id: Option<i64>) -> Result<Vec<GlossaryTerm>, String> {
+    let key = (source_lang.to_string(), target_lang.to_string(), project_id);
+    if let Some(cached) = with_cache(|cache| cache.get(&key).cloned()) {
+        return Ok(cached);
+    }
+    let all = state.db.with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, source_term, target_term, note FROM glossary_terms
+             WHERE source_lang = ?1 AND target_lang = ?2 AND (project_id IS NULL OR project_id = ?3)
+             ORDER BY project_id IS NULL, source_term",
+        )?;
+        let rows = stmt.query_map(params![source_lang, target_lang, project_id], |row| {
+            Ok(GlossaryTerm {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                source_term: row.get(2)?,
+                target_term: row.get(3)?,
+                note: row.get(4)?,
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+    })?;
+    let mut seen = std::collections::HashSet::new();
+    let visible: Vec<GlossaryTerm> = all.into_iter().filter(|term| seen.insert(term.source_term.to_lowercase())).collect();
+    with_cache(|cache| cache.insert(key, visible.clone()));
+    Ok(visible)
+}
+
+/// Loads the terms of a language pair for the active project ahead of the
+/// first translation that needs them.
+pub fn warm(state: &AppState, source_lang: &str, target_lang: &str) {
+    if let Err(e) = terms(state, source_lang, target_lang, projects::scope(state, None)) {
+        eprintln!("Failed to load glossary for {} -> {}: {}", source_lang, target_lang, e);
+    }
+}
+
+/// Glossary terms of the project (default: the active one) that occur in
+/// `text` (case-insensitive).
+pub fn matching_terms(state: &AppState, text: &str, source_lang: &str, target_lang: &str, project_id: Option<i64>) -> Vec<GlossaryTerm> {
+    let project_id = projects::scope(state, project_id);
+    let lower = text.to_lowercase();
+    terms(state, source_lang, target_lang, project_id)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|term| lower.contains(&term.source_term.to_lowercase()))
+        .collect()
+}
+
+/// Prompt addition asking the model to use the glossary terms found in `text`.
+/// Empty when nothing matches.
+pub fn instruction(state: &AppState, text: &str, source_lang: &str, target_lang: &str, project_id: Option<i64>) -> String {
+    let matches = matching_terms(state, text, source_lang, target_lang, project_id);
+    if matches.is_empty() {
+        return String::new();
+    }
+    let pairs: Vec<String> = matches.iter().map(|t| format!("\"{}\" -> \"{}\"", t.source_term, t.target_term)).collect();
+    format!(" Always translate these terms exactly as given: {}.", pairs.join(", "))
+}
+
+/// Lists the terms visible from a project (default: the active one), including
+/// the global terms it doesn't override.
+#[tauri::command]
+pub async fn list_glossary(
+    source_lang: String,
+    target_lang: String,
+    project_id: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<GlossaryTerm>, String> {
+    terms(&state, &source_lang, &target_lang, projects::scope(&state, project_id))
+}
+
+/// Adds or replaces a term in a project's glossary (default: the active
+/// project), or in the global glossary when `global` is set or no project is open.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn add_glossary_term(
+    source_term: String,
+    target_term: String,
+    source_lang: String,
+    target_lang: String,
+    note: Option<String>,
+    project_id: Option<i64>,
+    global: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let source_term = source_term.trim().to_string();
+    let target_term = target_term.trim().to_string();
+    if source_term.is_empty() || target_term.is_empty() {
+        return Err("Glossary terms must not be empty".to_string());
+    }
+    let scope = if global.unwrap_or(false) { None } else { projects::scope(&state, project_id) };
+    let term = PortableTerm { source_lang, target_lang, source_term, target_term, note };
+    let result = state.db.with(|conn| {
+        let tx = conn.transaction()?;
+        upsert(&tx, scope, &term)?;
+        tx.commit()
+    });
+    invalidate();
+    result
+}
+
+#[tauri::command]
+pub async fn remove_glossary_term(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    let result = state.db.with(|conn| conn.execute("DELETE FROM glossary_terms WHERE id = ?1", [id]));
+    invalidate();
+    result?;
+    Ok(())
+}
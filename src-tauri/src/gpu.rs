@@ -0,0 +1,126 @@
+use llama_cpp_2::model::params::{LlamaModelParams, LlamaSplitMode};
+use llama_cpp_2::{list_llama_ggml_backend_devices, LlamaBackendDeviceType};
+use tauri::State;
+
+use crate::{model_registry, AppState};
+
+// GPU offload. How many of a model's layers llama.cpp puts on the GPU is the
+// `gpuLayers` setting, applied the next time a model is loaded; a number above
+// the model's layer count offloads all of them. It only helps in builds with a
+// GPU backend (CUDA, Vulkan, Metal), which get_gpu_info reports along with the
+// devices llama.cpp found. With more than one GPU, a model's registry entry
+// (see model_registry.rs) can pick the devices to split it across, the split
+// mode and the main GPU. llama-cpp-2 doesn't expose llama.cpp's tensor_split
+// proportions, so layers are divided by each device's free memory.
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SplitMode {
+    // Everything on the main GPU
+    None,
+    // Whole layers per device
+    Layer,
+    // Tensors split by rows across devices; needs fast links between them
+    Row,
+}
+
+impl From<SplitMode> for LlamaSplitMode {
+    fn from(mode: SplitMode) -> LlamaSplitMode {
+        match mode {
+            SplitMode::None => LlamaSplitMode::None,
+            SplitMode::Layer => LlamaSplitMode::Layer,
+            SplitMode::Row => LlamaSplitMode::Row,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeviceKind {
+    Gpu,
+    IntegratedGpu,
+    Accelerator,
+    Unknown,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuDevice {
+    // Position among llama.cpp's backend devices
+    pub index: usize,
+    pub name: String,
+    pub description: String,
+    // e.g. "CUDA", "Vulkan", "Metal"
+    pub backend: String,
+    pub kind: DeviceKind,
+    pub memory_total_bytes: u64,
+    pub memory_free_bytes: u64,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuInfo {
+    // False in CPU-only builds, where the setting has no effect
+    pub offload_supported: bool,
+    pub devices: Vec<GpuDevice>,
+    // The setting; None keeps llama.cpp's default
+    pub gpu_layers: Option<u32>,
+}
+
+/// Devices llama.cpp can offload to; the CPU itself is left out.
+pub fn devices() -> Vec<GpuDevice> {
+    list_llama_ggml_backend_devices()
+        .into_iter()
+        .filter_map(|device| {
+            let kind = match device.device_type {
+                LlamaBackendDeviceType::Cpu => return None,
+                LlamaBackendDeviceType::Gpu => DeviceKind::Gpu,
+                LlamaBackendDeviceType::IntegratedGpu => DeviceKind::IntegratedGpu,
+                LlamaBackendDeviceType::Accelerator => DeviceKind::Accelerator,
+                LlamaBackendDeviceType::Unknown => DeviceKind::Unknown,
+            };
+            Some(GpuDevice {
+                index: device.index,
+                name: device.name,
+                description: device.description,
+                backend: device.backend,
+                kind,
+                memory_total_bytes: device.memory_total as u64,
+                memory_free_bytes: device.memory_free as u64,
+            })
+        })
+        .collect()
+}
+
+/// Parameters for loading `model_id` with the offload settings and its
+/// registry entry's device placement.
+pub fn model_params(state: &AppState, model_id: &str) -> Result<LlamaModelParams, String> {
+    let mut params = LlamaModelParams::default();
+    if let Some(layers) = state.settings.lock().unwrap().gpu_layers {
+        params = params.with_n_gpu_layers(layers);
+    }
+    let Some(entry) = model_registry::entry(state, model_id) else {
+        return Ok(params);
+    };
+    if let Some(devices) = entry.gpu_devices.as_deref().filter(|devices| !devices.is_empty()) {
+        params = params
+            .with_devices(devices)
+            .map_err(|e| format!("Invalid GPU devices {:?} for model '{}': {}", devices, model_id, e))?;
+    }
+    if let Some(mode) = entry.split_mode {
+        params = params.with_split_mode(mode.into());
+    }
+    if let Some(main_gpu) = entry.main_gpu {
+        params = params.with_main_gpu(main_gpu as i32);
+    }
+    Ok(params)
+}
+
+#[tauri::command]
+pub async fn get_gpu_info(state: State<'_, AppState>) -> Result<GpuInfo, String> {
+    Ok(GpuInfo {
+        offload_supported: state._backend.supports_gpu_offload(),
+        devices: devices(),
+        gpu_layers: state.settings.lock().unwrap().gpu_layers,
+    })
+}
@@ -0,0 +1,201 @@
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+use rusqlite::{params, OptionalExtension};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::environment::JobEnvironment;
+use crate::timings::{JobReport, JobTimings};
+use crate::{db, AppState};
+
+// Translation history: every finished popup/main-window translation, kept
+// according to the retention setting and pruned in the background. Incognito
+// mode (per session, never saved) suppresses history, clipboard captures and
+// source text in debug logs entirely (even with verbose logs, see redact.rs).
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_LIST_LIMIT: u32 = 100;
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HistoryRetention {
+    Forever,
+    // Entries older than this many days are deleted
+    Days(u32),
+    // Only the newest entries are kept
+    Entries(u32),
+    // History is not recorded at all
+    Nothing,
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        HistoryRetention::Days(30)
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    id: i64,
+    source_text: String,
+    translated_text: String,
+    source_lang: String,
+    target_lang: String,
+    model_id: String,
+    created_at: i64,
+    // Cut short by cancellation, see keep_result in main.rs
+    partial: bool,
+    // What the translation was made with; None for stored translations and
+    // entries from before it was recorded
+    environment: Option<JobEnvironment>,
+}
+
+fn retention(state: &AppState) -> HistoryRetention {
+    state.settings.lock().unwrap().history_retention
+}
+
+/// True while incognito mode is on: nothing about the text may be kept.
+pub fn is_incognito(state: &AppState) -> bool {
+    state.incognito.load(Ordering::Relaxed)
+}
+
+/// Stores a finished translation unless incognito or retention forbids it.
+/// `timings` and `environment` are None for translations that weren't
+/// generated (memory hits).
+#[allow(clippy::too_many_arguments)]
+pub fn record(
+    state: &AppState,
+    source: &str,
+    translated: &str,
+    source_lang: &str,
+    target_lang: &str,
+    model_id: &str,
+    timings: Option<&JobTimings>,
+    environment: Option<&JobEnvironment>,
+    partial: bool,
+) {
+    if is_incognito(state) || retention(state) == HistoryRetention::Nothing || translated.trim().is_empty() {
+        return;
+    }
+    let timings = timings.and_then(|t| serde_json::to_string(t).ok());
+    let environment = environment.and_then(|e| serde_json::to_string(e).ok());
+    let result = state.db.with(|conn| {
+        conn.execute(
+            "INSERT INTO history (source_text, translated_text, source_lang, target_lang, model_id, created_at, timings, partial, environment)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![source, translated, source_lang, target_lang, model_id, db::now(), timings, partial, environment],
+        )
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to record history: {}", e);
+    }
+}
+
+/// Deletes entries the retention setting no longer allows.
+pub fn prune(state: &AppState) -> Result<(), String> {
+    state.db.with(|conn| match retention(state) {
+        HistoryRetention::Forever => Ok(0),
+        HistoryRetention::Days(days) => {
+            conn.execute("DELETE FROM history WHERE created_at < ?1", [db::now() - days as i64 * DAY_SECS])
+        }
+        HistoryRetention::Entries(keep) => conn.execute(
+            "DELETE FROM history WHERE id NOT IN (SELECT id FROM history ORDER BY created_at DESC, id DESC LIMIT ?1)",
+            [keep],
+        ),
+        HistoryRetention::Nothing => conn.execute("DELETE FROM history", []),
+    })?;
+    Ok(())
+}
+
+/// Prunes once now and then every hour; settings changes prune immediately.
+pub fn start_pruner(app: AppHandle) {
+    thread::spawn(move || loop {
+        if let Err(e) = prune(&app.state::<AppState>()) {
+            eprintln!("Failed to prune history: {}", e);
+        }
+        thread::sleep(PRUNE_INTERVAL);
+    });
+}
+
+/// Newest entries first, optionally only those containing `query`.
+#[tauri::command]
+pub async fn list_history(query: Option<String>, limit: Option<u32>, state: State<'_, AppState>) -> Result<Vec<HistoryEntry>, String> {
+    let pattern = query
+        .filter(|q| !q.trim().is_empty())
+        .map(|q| format!("%{}%", q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")));
+    state.db.with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, source_text, translated_text, source_lang, target_lang, model_id, created_at, partial, environment FROM history
+             WHERE ?1 IS NULL OR source_text LIKE ?1 ESCAPE '\\' OR translated_text LIKE ?1 ESCAPE '\\'
+             ORDER BY created_at DESC, id DESC LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![pattern, limit.unwrap_or(DEFAULT_LIST_LIMIT)], |row| {
+            Ok(HistoryEntry {
+                id: row.get(0)?,
+                source_text: row.get(1)?,
+                translated_text: row.get(2)?,
+                source_lang: row.get(3)?,
+                target_lang: row.get(4)?,
+                model_id: row.get(5)?,
+                created_at: row.get(6)?,
+                partial: row.get(7)?,
+                environment: row.get::<_, Option<String>>(8)?.and_then(|json| serde_json::from_str(&json).ok()),
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+/// Timings of the newest `limit` translations made with `model_id`, for estimates.
+pub fn recent_timings(state: &AppState, model_id: &str, limit: u32) -> Vec<JobTimings> {
+    let rows: Result<Vec<String>, String> = state.db.with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT timings FROM history WHERE model_id = ?1 AND timings IS NOT NULL
+             ORDER BY created_at DESC, id DESC LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![model_id, limit], |row| row.get(0))?;
+        rows.collect()
+    });
+    rows.unwrap_or_default().iter().filter_map(|json| serde_json::from_str(json).ok()).collect()
+}
+
+/// Where the time of a history entry's translation went: model loading, prompt
+/// decoding and generation, in total and per chunk.
+#[tauri::command]
+pub async fn get_job_report(id: i64, state: State<'_, AppState>) -> Result<JobReport, String> {
+    let row: Option<(String, i64, Option<String>)> = state.db.with(|conn| {
+        conn.query_row("SELECT model_id, created_at, timings FROM history WHERE id = ?1", [id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .optional()
+    })?;
+    let (model_id, created_at, timings) = row.ok_or_else(|| format!("History entry {} not found", id))?;
+    let timings = timings.ok_or_else(|| format!("No timings were recorded for history entry {}", id))?;
+    let timings: JobTimings = serde_json::from_str(&timings).map_err(|e| e.to_string())?;
+    Ok(JobReport::new(id, model_id, created_at, timings))
+}
+
+/// Deletes all entries, and the language pair usage counts with them.
+#[tauri::command]
+pub async fn clear_history(state: State<'_, AppState>) -> Result<(), String> {
+    state.db.with(|conn| conn.execute_batch("DELETE FROM history; DELETE FROM language_pairs;"))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_incognito(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(is_incognito(&state))
+}
+
+/// Switches incognito mode for the rest of this session.
+#[tauri::command]
+pub async fn set_incognito(enabled: bool, app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    state.incognito.store(enabled, Ordering::Relaxed);
+    if enabled {
+        state.clipboard_history.clear();
+    }
+    app.emit("incognito-changed", enabled).unwrap_or(());
+    Ok(())
+}
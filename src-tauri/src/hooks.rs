@@ -0,0 +1,115 @@
+use std::process::Command;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use crate::AppState;
+
+// Completion hooks, so document and batch jobs can slot into existing
+// automation (e.g. moving translated files into a DMS). When a job ends, its
+// metadata (never the text) is POSTed as JSON to a webhook URL, and after a
+// successful job a program is run with the output path as its last argument.
+// Hooks come from the settings (`completionHooks`) unless the job passes its
+// own. Both run on a background thread; failures are only logged, the job's
+// result stands either way.
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CompletionHooks {
+    pub webhook_url: Option<String>,
+    pub command: Option<HookCommand>,
+}
+
+/// A program and its arguments, run without a shell so paths need no quoting.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookCommand {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileJobKind {
+    Document,
+    Batch,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobOutcome {
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobOutcome {
+    /// How a job that returned `result` ended. A cancelled batch returns Ok
+    /// with the remaining files marked as cancelled.
+    pub fn of<T>(state: &AppState, result: &Result<T, String>) -> JobOutcome {
+        match result {
+            _ if state.is_cancelled.load(Ordering::Relaxed) => JobOutcome::Cancelled,
+            Ok(_) => JobOutcome::Completed,
+            Err(_) => JobOutcome::Failed,
+        }
+    }
+}
+
+/// What the hooks are told about a finished job.
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FinishedJob {
+    pub kind: FileJobKind,
+    pub status: JobOutcome,
+    pub input: String,
+    // Output file, or the output folder (or ZIP) of a batch; None if nothing was written
+    pub output: Option<String>,
+    pub source_lang: String,
+    pub target_lang: String,
+    pub model_id: String,
+    pub error: Option<String>,
+    // Batches only
+    pub files: Option<usize>,
+    pub failed_files: Option<usize>,
+}
+
+fn post(url: &str, report: &FinishedJob) {
+    if let Err(e) = ureq::post(url).timeout(WEBHOOK_TIMEOUT).send_json(report) {
+        eprintln!("Completion webhook {} failed: {}", url, e);
+    }
+}
+
+// The input and languages are also passed as SPARK_* environment variables
+fn run_command(command: &HookCommand, output: &str, report: &FinishedJob) {
+    let result = Command::new(&command.program)
+        .args(&command.args)
+        .arg(output)
+        .env("SPARK_SOURCE_LANG", &report.source_lang)
+        .env("SPARK_TARGET_LANG", &report.target_lang)
+        .env("SPARK_INPUT", &report.input)
+        .status();
+    match result {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("Completion command {:?} exited with {}", command.program, status),
+        Err(e) => eprintln!("Failed to run completion command {:?}: {}", command.program, e),
+    }
+}
+
+/// Runs the job's hooks (or else the configured ones) for `report`.
+pub fn job_finished(state: &AppState, job_hooks: Option<CompletionHooks>, report: FinishedJob) {
+    let hooks = job_hooks.unwrap_or_else(|| state.settings.lock().unwrap().completion_hooks.clone());
+    if hooks.webhook_url.is_none() && hooks.command.is_none() {
+        return;
+    }
+    thread::spawn(move || {
+        if let Some(url) = &hooks.webhook_url {
+            post(url, &report);
+        }
+        if let (Some(command), Some(output), JobOutcome::Completed) = (&hooks.command, &report.output, report.status) {
+            run_command(command, output, &report);
+        }
+    });
+}
@@ -0,0 +1,98 @@
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, State, Window};
+
+use crate::lang_detect;
+use crate::priority;
+use crate::AppState;
+
+// Conversation interpreter mode: two people share one machine, each input is
+// language-detected and translated into the *other* configured language.
+
+#[derive(Clone, serde::Serialize)]
+pub struct InterpreterTurn {
+    // Seconds since UNIX epoch
+    timestamp: u64,
+    source_lang: String,
+    target_lang: String,
+    source_text: String,
+    translated_text: String,
+}
+
+#[derive(Default)]
+pub struct InterpreterSession {
+    transcript: Mutex<Vec<InterpreterTurn>>,
+}
+
+fn languages_match(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+/// Picks (source, target) for a turn. Defaults to a -> b unless the text looks like `lang_b`.
+fn resolve_direction(text: &str, lang_a: &str, lang_b: &str) -> (String, String) {
+    match lang_detect::detect(text) {
+        Some(detected) if languages_match(detected, lang_b) => (lang_b.to_string(), lang_a.to_string()),
+        _ => (lang_a.to_string(), lang_b.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn interpret(
+    text: String,
+    lang_a: String,
+    lang_b: String,
+    model_id: String,
+    state: State<'_, AppState>,
+    window: Window,
+) -> Result<InterpreterTurn, String> {
+    let (source_lang, target_lang) = resolve_direction(&text, &lang_a, &lang_b);
+    window.emit("interpreter-direction", (&source_lang, &target_lang)).unwrap_or(());
+
+    // Waits for earlier jobs and resets the cancellation flag
+    let _turn = state.jobs.start(&state, &window, priority::JobKind::Interactive).await?;
+    let options = crate::TranslateOptions { stream: true, ..Default::default() };
+    let translated_text = crate::run_translation(&text, &source_lang, &target_lang, &model_id, &state, &window, &options)?;
+
+    let turn = InterpreterTurn {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        source_lang,
+        target_lang,
+        source_text: text,
+        translated_text,
+    };
+    state.interpreter.transcript.lock().unwrap().push(turn.clone());
+    Ok(turn)
+}
+
+#[tauri::command]
+pub async fn get_interpreter_transcript(state: State<'_, AppState>) -> Result<Vec<InterpreterTurn>, String> {
+    Ok(state.interpreter.transcript.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn clear_interpreter_transcript(state: State<'_, AppState>) -> Result<(), String> {
+    state.interpreter.transcript.lock().unwrap().clear();
+    Ok(())
+}
+
+/// Writes the transcript to `path`. A `.json` extension exports structured data,
+/// anything else a readable plain-text log.
+#[tauri::command]
+pub async fn export_interpreter_transcript(path: String, state: State<'_, AppState>) -> Result<(), String> {
+    let transcript = state.interpreter.transcript.lock().unwrap().clone();
+
+    let contents = if path.to_lowercase().ends_with(".json") {
+        serde_json::to_string_pretty(&transcript).map_err(|e| e.to_string())?
+    } else {
+        transcript
+            .iter()
+            .map(|turn| format!(
+                "[{}] {} -> {}\n{}\n{}\n",
+                turn.timestamp, turn.source_lang, turn.target_lang, turn.source_text, turn.translated_text
+            ))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to write transcript: {}", e))
+}
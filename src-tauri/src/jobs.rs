@@ -0,0 +1,227 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use tauri::{Emitter, Window};
+use tokio::sync::Notify;
+
+use crate::priority::JobKind;
+use crate::{api, AppState};
+
+// Jobs that use the model (popup translations, documents, batches, mail, code,
+// evaluations, ...) run one at a time. Several quick hotkey triggers used to
+// run concurrently and race the shared cancellation flag and model mutex; now
+// each job waits for its turn, and the cancellation flag is only ever reset by
+// the job that is about to run. Interactive jobs are served before waiting
+// background ones, and a running background job steps aside between segments
+// (see `yield_to_interactive`) so a lookup doesn't wait for a whole document.
+// A new job from a window supersedes (cancels) that window's earlier job of
+// the same kind, running or waiting. Jobs wait asynchronously, so queued
+// commands don't hold on to runtime threads, and a cancelled job leaves the
+// queue at once instead of when its turn would have come.
+
+#[derive(Default)]
+struct QueueState {
+    next_ticket: u64,
+    // The job allowed to run
+    running: Option<(u64, JobKind)>,
+    // Interactive jobs first, then background ones, each in arrival order
+    waiting: VecDeque<(u64, JobKind)>,
+    // Cancelled jobs that haven't noticed it yet
+    cancelled: HashMap<u64, Cancelled>,
+    // Background job that stepped aside for interactive ones; it is in `waiting`
+    // but has already started, so cancelling it works like cancelling a running job
+    yielded: Option<u64>,
+    // Newest job per window label and kind
+    latest: HashMap<(String, JobKind), u64>,
+}
+
+impl QueueState {
+    // Where a job of `kind` joins the queue: behind the jobs it doesn't overtake
+    fn queue_position(&self, kind: JobKind) -> usize {
+        match kind {
+            JobKind::Interactive => {
+                self.waiting.iter().position(|&(_, waiting)| waiting == JobKind::Background).unwrap_or(self.waiting.len())
+            }
+            JobKind::Background => self.waiting.len(),
+        }
+    }
+
+    fn is_turn(&self, ticket: u64) -> bool {
+        self.running.is_none() && self.waiting.front().is_some_and(|&(front, _)| front == ticket)
+    }
+
+    fn is_waiting(&self, ticket: u64) -> bool {
+        self.waiting.iter().any(|&(waiting, _)| waiting == ticket)
+    }
+
+    fn forget(&mut self, ticket: u64, label: &str, kind: JobKind) {
+        self.cancelled.remove(&ticket);
+        let key = (label.to_string(), kind);
+        if self.latest.get(&key) == Some(&ticket) {
+            self.latest.remove(&key);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cancelled {
+    // A newer job from the same window replaced it
+    Superseded,
+    // cancel_translation
+    ByUser,
+}
+
+#[derive(Default)]
+pub struct JobQueue {
+    state: Mutex<QueueState>,
+    turn: Notify,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QueuedEvent {
+    // Jobs that will run before this one
+    ahead: u64,
+    api_version: u32,
+}
+
+/// Holds the queue position of a job; dropping it lets the next job run.
+pub struct JobTurn<'a> {
+    queue: &'a JobQueue,
+    ticket: u64,
+    label: String,
+    kind: JobKind,
+}
+
+impl Drop for JobTurn<'_> {
+    fn drop(&mut self) {
+        let mut state = self.queue.state.lock().unwrap();
+        state.running = None;
+        state.forget(self.ticket, &self.label, self.kind);
+        self.queue.turn.notify_waiters();
+    }
+}
+
+impl JobQueue {
+    // Cancels the newest job of `label` and `kind`, if any. Returns false when it has none.
+    fn cancel_locked(&self, state: &mut QueueState, app_state: &AppState, label: &str, kind: JobKind, reason: Cancelled) -> bool {
+        match state.latest.get(&(label.to_string(), kind)).copied() {
+            Some(ticket) if state.running.is_some_and(|(running, _)| running == ticket) => {
+                app_state.is_cancelled.store(true, Ordering::Relaxed)
+            }
+            // Stops when it resumes
+            Some(ticket) if state.yielded == Some(ticket) => {
+                state.cancelled.insert(ticket, reason);
+            }
+            // Leaves the queue now; its waiter wakes up and fails
+            Some(ticket) => {
+                state.cancelled.insert(ticket, reason);
+                state.waiting.retain(|&(waiting, _)| waiting != ticket);
+                self.turn.notify_waiters();
+            }
+            None => return false,
+        }
+        true
+    }
+
+    // Returns once `ticket` may run or has been taken out of the queue
+    async fn wait_for_turn(&self, ticket: u64) {
+        loop {
+            // Created before checking, so a notification in between isn't missed
+            let notified = self.turn.notified();
+            {
+                let state = self.state.lock().unwrap();
+                if state.is_turn(ticket) || !state.is_waiting(ticket) {
+                    return;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    /// Waits until it is this window's turn. Tells the window how many jobs are
+    /// ahead on `translation-queued-{label}` when it has to wait. Fails as soon as
+    /// the job is cancelled or superseded while waiting.
+    pub async fn enter<'a>(&'a self, app_state: &AppState, window: &Window, kind: JobKind) -> Result<JobTurn<'a>, Cancelled> {
+        let label = window.label().to_string();
+        let ticket = {
+            let mut state = self.state.lock().unwrap();
+            self.cancel_locked(&mut state, app_state, &label, kind, Cancelled::Superseded);
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.latest.insert((label.clone(), kind), ticket);
+            let position = state.queue_position(kind);
+            state.waiting.insert(position, (ticket, kind));
+
+            if !state.is_turn(ticket) {
+                let ahead = position as u64 + u64::from(state.running.is_some());
+                let event_name = format!("translation-queued-{}", label);
+                window.emit(&event_name, QueuedEvent { ahead, api_version: api::API_VERSION }).unwrap_or(());
+            }
+            ticket
+        };
+        self.wait_for_turn(ticket).await;
+
+        let mut state = self.state.lock().unwrap();
+        if !state.is_waiting(ticket) {
+            let reason = state.cancelled.get(&ticket).copied().unwrap_or(Cancelled::Superseded);
+            state.forget(ticket, &label, kind);
+            return Err(reason);
+        }
+        state.waiting.pop_front();
+        state.running = Some((ticket, kind));
+        app_state.is_cancelled.store(false, Ordering::Relaxed);
+        Ok(JobTurn { queue: self, ticket, label, kind })
+    }
+
+    /// Like `enter`, for commands that report a cancelled job as an error.
+    pub async fn start<'a>(&'a self, app_state: &AppState, window: &Window, kind: JobKind) -> Result<JobTurn<'a>, String> {
+        self.enter(app_state, window, kind).await.map_err(|reason| match reason {
+            Cancelled::Superseded => "Replaced by a newer job from this window".to_string(),
+            Cancelled::ByUser => "Translation cancelled".to_string(),
+        })
+    }
+
+    /// Called between segments (see run_translation): if the running job is a
+    /// background job and interactive jobs are waiting, lets them run first and
+    /// blocks until they are done. The caller must not hold the model lock.
+    /// Segments are translated synchronously inside the job's command, so the
+    /// runtime is told to move its other tasks off this thread meanwhile.
+    pub fn yield_to_interactive(&self, app_state: &AppState) {
+        let ticket = {
+            let mut state = self.state.lock().unwrap();
+            let Some((ticket, JobKind::Background)) = state.running else {
+                return;
+            };
+            let interactive_waiting = state.waiting.front().is_some_and(|&(_, kind)| kind == JobKind::Interactive);
+            // A job that is stopping anyway doesn't need to step aside
+            if !interactive_waiting || app_state.is_cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            state.running = None;
+            state.yielded = Some(ticket);
+            // Ahead of the other background jobs, so it resumes next
+            let position = state.queue_position(JobKind::Interactive);
+            state.waiting.insert(position, (ticket, JobKind::Background));
+            self.turn.notify_waiters();
+            ticket
+        };
+        tokio::task::block_in_place(|| tauri::async_runtime::block_on(self.wait_for_turn(ticket)));
+
+        let mut state = self.state.lock().unwrap();
+        state.waiting.pop_front();
+        state.yielded = None;
+        state.running = Some((ticket, JobKind::Background));
+        // The interactive jobs reset the flag for themselves; restore this job's
+        let cancelled = state.cancelled.contains_key(&ticket);
+        app_state.is_cancelled.store(cancelled, Ordering::Relaxed);
+    }
+
+    /// Cancels the window's queued or running jobs. Returns false when it has none.
+    pub fn cancel(&self, app_state: &AppState, label: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let interactive = self.cancel_locked(&mut state, app_state, label, JobKind::Interactive, Cancelled::ByUser);
+        let background = self.cancel_locked(&mut state, app_state, label, JobKind::Background, Cancelled::ByUser);
+        interactive || background
+    }
+}
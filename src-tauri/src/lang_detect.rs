@@ -0,0 +1,103 @@
+// Lightweight language detection: script ranges first, then stop-word scoring
+// for Latin-script languages. Names match the ones the frontend passes to `translate`.
+
+#[derive(Default)]
+struct ScriptCounts {
+    kana: usize,
+    han: usize,
+    hangul: usize,
+    cyrillic: usize,
+    arabic: usize,
+    hebrew: usize,
+    thai: usize,
+    greek: usize,
+    devanagari: usize,
+    latin: usize,
+}
+
+fn count_scripts(text: &str) -> ScriptCounts {
+    let mut counts = ScriptCounts::default();
+    for c in text.chars() {
+        match c as u32 {
+            0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F => counts.kana += 1,
+            0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF => counts.han += 1,
+            0xAC00..=0xD7AF | 0x1100..=0x11FF | 0x3130..=0x318F => counts.hangul += 1,
+            0x0400..=0x04FF => counts.cyrillic += 1,
+            0x0600..=0x06FF | 0x0750..=0x077F => counts.arabic += 1,
+            0x0590..=0x05FF => counts.hebrew += 1,
+            0x0E00..=0x0E7F => counts.thai += 1,
+            0x0370..=0x03FF => counts.greek += 1,
+            0x0900..=0x097F => counts.devanagari += 1,
+            _ if c.is_ascii_alphabetic() => counts.latin += 1,
+            0x00C0..=0x024F => counts.latin += 1,
+            _ => {}
+        }
+    }
+    counts
+}
+
+const LATIN_STOP_WORDS: &[(&str, &[&str])] = &[
+    ("English", &["the", "and", "is", "are", "of", "to", "in", "that", "it", "you", "this", "with", "for", "was"]),
+    ("French", &["le", "la", "les", "et", "est", "des", "une", "un", "pas", "que", "pour", "dans", "vous", "je"]),
+    ("German", &["der", "die", "das", "und", "ist", "nicht", "ich", "ein", "eine", "zu", "mit", "sie", "es", "auf"]),
+    ("Spanish", &["el", "la", "los", "las", "y", "es", "que", "de", "en", "un", "una", "por", "para", "no"]),
+    ("Italian", &["il", "lo", "gli", "e", "che", "di", "un", "una", "non", "per", "sono", "con", "della", "ho"]),
+    ("Portuguese", &["o", "os", "as", "e", "que", "de", "um", "uma", "não", "para", "com", "em", "do", "da"]),
+];
+
+fn detect_latin(text: &str) -> &'static str {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+
+    let mut best = ("English", 0usize);
+    for (lang, stop_words) in LATIN_STOP_WORDS {
+        let score = words.iter().filter(|w| stop_words.contains(&w.as_str())).count();
+        // Ties keep the earlier (more common) language
+        if score > best.1 {
+            best = (lang, score);
+        }
+    }
+    best.0
+}
+
+/// Returns the most likely language name, or None when the text has no letters.
+pub fn detect(text: &str) -> Option<&'static str> {
+    let counts = count_scripts(text);
+
+    // Any kana means Japanese even if most characters are kanji
+    if counts.kana > 0 {
+        return Some("Japanese");
+    }
+
+    let scripts = [
+        ("Chinese", counts.han),
+        ("Korean", counts.hangul),
+        ("Russian", counts.cyrillic),
+        ("Arabic", counts.arabic),
+        ("Hebrew", counts.hebrew),
+        ("Thai", counts.thai),
+        ("Greek", counts.greek),
+        ("Hindi", counts.devanagari),
+    ];
+    let (best_script, best_count) = scripts
+        .iter()
+        .copied()
+        .max_by_key(|(_, count)| *count)
+        .unwrap_or(("", 0));
+
+    if best_count == 0 && counts.latin == 0 {
+        return None;
+    }
+    if best_count >= counts.latin {
+        return Some(best_script);
+    }
+    Some(detect_latin(text))
+}
+
+#[tauri::command]
+pub async fn detect_language(text: String) -> Result<Option<String>, String> {
+    Ok(detect(&text).map(|lang| lang.to_string()))
+}
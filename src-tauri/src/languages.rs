@@ -0,0 +1,92 @@
+use rusqlite::params;
+use tauri::{AppHandle, Manager, State};
+
+use crate::{db, glossary, history, AppState};
+
+// Language pairs. The user keeps an ordered list of targets (`targetCycle` in
+// the settings, e.g. English, Japanese, Chinese) and the popup's Ctrl+L moves
+// to the next one and re-translates. How often each pair is used is counted
+// (except in incognito mode), so the UI can offer frequent pairs first and
+// their glossaries can be loaded at startup.
+
+// Used while the settings have no list of their own
+const DEFAULT_CYCLE: &[&str] = &["English", "Japanese"];
+const DEFAULT_RECENT_LIMIT: u32 = 10;
+// Pairs whose glossary is loaded at startup
+const WARM_PAIRS: u32 = 3;
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguagePair {
+    source_lang: String,
+    target_lang: String,
+    uses: i64,
+    // Seconds since UNIX epoch
+    last_used: i64,
+}
+
+// The entry after `current` in `cycle`, skipping the source language (nothing
+// to translate into it). Unknown targets start over at the first entry.
+fn next_in(cycle: &[String], current: &str, source_lang: &str) -> Option<String> {
+    let start = cycle.iter().position(|lang| lang.eq_ignore_ascii_case(current)).map_or(0, |i| i + 1);
+    (0..cycle.len())
+        .map(|offset| &cycle[(start + offset) % cycle.len()])
+        .find(|lang| !lang.eq_ignore_ascii_case(source_lang) && !lang.eq_ignore_ascii_case(current))
+        .cloned()
+}
+
+/// The target language to switch to after `current`, or `current` itself when
+/// the list offers nothing else.
+#[tauri::command]
+pub async fn next_target_language(current: String, source_lang: String, state: State<'_, AppState>) -> Result<String, String> {
+    let mut cycle = state.settings.lock().unwrap().target_cycle.clone();
+    if cycle.is_empty() {
+        cycle = DEFAULT_CYCLE.iter().map(|lang| lang.to_string()).collect();
+    }
+    Ok(next_in(&cycle, &current, &source_lang).unwrap_or(current))
+}
+
+/// Counts one translation from `source_lang` into `target_lang`.
+pub fn record_use(state: &AppState, source_lang: &str, target_lang: &str) {
+    if history::is_incognito(state) {
+        return;
+    }
+    let result = state.db.with(|conn| {
+        conn.execute(
+            "INSERT INTO language_pairs (source_lang, target_lang, uses, last_used) VALUES (?1, ?2, 1, ?3)
+             ON CONFLICT (source_lang, target_lang) DO UPDATE SET uses = uses + 1, last_used = ?3",
+            params![source_lang, target_lang, db::now()],
+        )
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to record language pair: {}", e);
+    }
+}
+
+fn recent_pairs(state: &AppState, limit: u32) -> Result<Vec<LanguagePair>, String> {
+    state.db.with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT source_lang, target_lang, uses, last_used FROM language_pairs
+             ORDER BY uses DESC, last_used DESC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map([limit], |row| {
+            Ok(LanguagePair { source_lang: row.get(0)?, target_lang: row.get(1)?, uses: row.get(2)?, last_used: row.get(3)? })
+        })?;
+        rows.collect()
+    })
+}
+
+/// Loads the glossaries of the most used pairs, so the first translation
+/// after startup doesn't wait for them.
+pub fn warm_up(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    for pair in recent_pairs(&state, WARM_PAIRS).unwrap_or_default() {
+        glossary::warm(&state, &pair.source_lang, &pair.target_lang);
+    }
+}
+
+/// Language pairs by how often they were used, most used first.
+#[tauri::command]
+pub async fn get_recent_language_pairs(limit: Option<u32>, state: State<'_, AppState>) -> Result<Vec<LanguagePair>, String> {
+    recent_pairs(&state, limit.unwrap_or(DEFAULT_RECENT_LIMIT))
+}
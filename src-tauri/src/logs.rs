@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use tauri::{State, Window};
+
+use crate::{priority, AppState, SegmentTranslator};
+
+// Log translation: timestamps, levels, bracketed tags and key=value fields are
+// copied byte-for-byte and only the free-text message is translated, so the
+// output stays greppable with the same patterns as the original.
+
+const LEVELS: &[&str] = &[
+    "TRACE", "DEBUG", "INFO", "NOTICE", "WARN", "WARNING", "ERROR", "ERR", "FATAL", "CRITICAL", "SEVERE",
+    "情報", "警告", "エラー", "致命的", "デバッグ",
+];
+
+const JSON_MESSAGE_KEYS: &[&str] = &["\"message\"", "\"msg\""];
+
+const MONTHS: &[&str] = &["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+// Timestamps, dates, pids, durations: digits mixed only with separators
+fn is_numeric_token(token: &str) -> bool {
+    token.chars().any(|c| c.is_ascii_digit())
+        && token.chars().all(|c| c.is_ascii_digit() || "-:/.,+TZ()ms".contains(c))
+}
+
+fn is_level(token: &str) -> bool {
+    let token = token.trim_end_matches(':');
+    LEVELS.iter().any(|level| token.eq_ignore_ascii_case(level))
+}
+
+fn is_field(token: &str) -> bool {
+    match token.split_once('=') {
+        Some((key, _)) => !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c)),
+        None => false,
+    }
+}
+
+fn is_identifier(token: &str) -> bool {
+    !token.is_empty() && token.chars().all(|c| c.is_ascii_alphanumeric() || "_.-/:[]#@".contains(c))
+}
+
+// "com.example.Service:", "main.rs:42:" or syslog "sshd[123]:" tags
+fn is_logger(token: &str) -> bool {
+    token.strip_suffix(':').is_some_and(is_identifier)
+}
+
+fn is_structural(token: &str) -> bool {
+    is_numeric_token(token)
+        || is_level(token)
+        || is_field(token)
+        || is_logger(token)
+        || MONTHS.contains(&token)
+        || ["-", "--", "|", ":"].contains(&token)
+        // Ruby Logger severity letter: "I, [...]"
+        || (token.len() == 2 && token.ends_with(',') && token.starts_with(|c: char| c.is_ascii_uppercase()))
+}
+
+// Returns the length of a bracketed group at the start of `text`, e.g. "[main]" or "<12>"
+fn bracket_len(text: &str) -> Option<usize> {
+    let close = match text.chars().next()? {
+        '[' => ']',
+        '(' => ')',
+        '<' => '>',
+        _ => return None,
+    };
+    text.find(close).map(|i| i + close.len_utf8())
+}
+
+/// Splits a log line into (structured prefix, message, trailing fields).
+pub fn split_line(line: &str) -> (&str, &str, &str) {
+    // Stack trace frames carry no prose
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("at ") || trimmed.starts_with("File \"") {
+        return (line, "", "");
+    }
+
+    let mut pos = 0;
+    loop {
+        let rest = &line[pos..];
+        let ws = rest.len() - rest.trim_start().len();
+        let rest = &rest[ws..];
+        if rest.is_empty() {
+            break;
+        }
+        let token_len = match bracket_len(rest) {
+            Some(len) => len,
+            None => {
+                let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                if !is_structural(&rest[..len]) {
+                    // A hostname directly followed by a tag ("host sshd[1]:") is structural too
+                    let next = rest[len..].split_whitespace().next().unwrap_or("");
+                    if !(is_identifier(&rest[..len]) && is_logger(next)) {
+                        break;
+                    }
+                }
+                len
+            }
+        };
+        pos += ws + token_len;
+    }
+    // Keep the separator before the message in the prefix
+    let message_start = pos + (line[pos..].len() - line[pos..].trim_start().len());
+
+    // Trailing key=value fields after the message stay untouched
+    let body = &line[message_start..];
+    let mut message_end = body.len();
+    for (i, _) in body.match_indices(' ') {
+        let tail = body[i..].trim_start();
+        if !tail.is_empty() && tail.split_whitespace().all(is_field) {
+            message_end = i;
+            break;
+        }
+    }
+
+    (&line[..message_start], &body[..message_end], &body[message_end..])
+}
+
+// Finds the string value of a "message"/"msg" key in a JSON log line.
+// Returns the byte range of the value between its quotes.
+fn json_message_range(line: &str) -> Option<(usize, usize)> {
+    for key in JSON_MESSAGE_KEYS {
+        let Some(key_pos) = line.find(key) else { continue };
+        let after_key = key_pos + key.len();
+        let Some(rest) = line[after_key..].trim_start().strip_prefix(':') else { continue };
+        let rest = rest.trim_start();
+        if !rest.starts_with('"') {
+            continue;
+        }
+        let value_start = line.len() - rest.len() + 1;
+        let bytes = line.as_bytes();
+        let mut i = value_start;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'"' => return Some((value_start, i)),
+                _ => i += 1,
+            }
+        }
+    }
+    None
+}
+
+struct LogTranslator<'a> {
+    translator: SegmentTranslator<'a>,
+    // Log files repeat the same messages constantly
+    cache: HashMap<String, String>,
+}
+
+impl LogTranslator<'_> {
+    fn translate(&mut self, message: &str) -> Result<String, String> {
+        if !message.chars().any(|c| c.is_alphabetic()) {
+            return Ok(message.to_string());
+        }
+        if let Some(cached) = self.cache.get(message) {
+            return Ok(cached.clone());
+        }
+        let translated = self.translator.translate(message)?.replace('\n', " ");
+        self.cache.insert(message.to_string(), translated.clone());
+        Ok(translated)
+    }
+
+    fn translate_line(&mut self, line: &str) -> Result<String, String> {
+        if line.trim_start().starts_with('{') {
+            return match json_message_range(line) {
+                Some((start, end)) => {
+                    let raw = &line[start..end];
+                    let message: String = serde_json::from_str(&format!("\"{}\"", raw)).map_err(|e| e.to_string())?;
+                    let encoded = serde_json::to_string(&self.translate(&message)?).map_err(|e| e.to_string())?;
+                    Ok(format!("{}{}{}", &line[..start], &encoded[1..encoded.len() - 1], &line[end..]))
+                }
+                None => Ok(line.to_string()),
+            };
+        }
+
+        let (prefix, message, fields) = split_line(line);
+        Ok(format!("{}{}{}", prefix, self.translate(message)?, fields))
+    }
+}
+
+#[tauri::command]
+pub async fn translate_log(
+    text: String,
+    source_lang: String,
+    target_lang: String,
+    model_id: String,
+    state: State<'_, AppState>,
+    window: Window,
+) -> Result<String, String> {
+    // Waits for earlier jobs and resets the cancellation flag
+    let _turn = state.jobs.start(&state, &window, priority::JobKind::Interactive).await?;
+
+    let mut log_translator = LogTranslator {
+        translator: SegmentTranslator {
+            source_lang: &source_lang,
+            target_lang: &target_lang,
+            model_id: &model_id,
+            state: &state,
+            window: &window,
+        },
+        cache: HashMap::new(),
+    };
+
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let (content, ending) = match line.strip_suffix("\r\n") {
+            Some(content) => (content, "\r\n"),
+            None => match line.strip_suffix('\n') {
+                Some(content) => (content, "\n"),
+                None => (line, ""),
+            },
+        };
+        output.push_str(&log_translator.translate_line(content)?);
+        output.push_str(ending);
+    }
+
+    crate::emit_result(&window, &state, &output, &target_lang)?;
+    Ok(output)
+}
@@ -0,0 +1,44 @@
+// Event boundaries for streamed Markdown/HTML. Tokens end anywhere, so a
+// streamed event can stop after the first '*' of "**", between ']' and '(' of
+// a link or inside "<span", and an incremental renderer shows broken markup
+// until the next event arrives. With the `markup` option, run_translation holds
+// back such an unfinished tail and sends it with the next event instead.
+
+// Characters that may be the first of a longer marker ("**", "~~", "```", "__")
+const REPEATABLE: &[char] = &['*', '_', '~', '`'];
+// An unclosed tag or entity longer than this isn't markup; it is sent as is
+const MAX_TAG_CHARS: usize = 64;
+const MAX_ENTITY_CHARS: usize = 10;
+
+/// End of the part of `text` that can be sent now, at least `from` (what was
+/// already sent). The rest waits for more output or the end of the chunk.
+pub fn safe_end(text: &str, from: usize) -> usize {
+    let tail = &text[from..];
+    let held = [open_tag(tail), open_entity(tail), open_marker(tail)].into_iter().flatten().min();
+    from + held.unwrap_or(tail.len())
+}
+
+// "<span cla" or "</": a tag without its closing '>'
+fn open_tag(tail: &str) -> Option<usize> {
+    let start = tail.rfind('<')?;
+    let tag = &tail[start..];
+    (!tag.contains('>') && tag.chars().count() <= MAX_TAG_CHARS).then_some(start)
+}
+
+// "&nbs" or "&#12": an entity without its ';'
+fn open_entity(tail: &str) -> Option<usize> {
+    let start = tail.rfind('&')?;
+    let entity = &tail[start + 1..];
+    let unfinished = entity.chars().all(|c| c.is_ascii_alphanumeric() || c == '#');
+    (unfinished && entity.chars().count() < MAX_ENTITY_CHARS).then_some(start)
+}
+
+// A trailing run of emphasis/code characters, a ']' that may be followed by
+// "(" or "[" and a '!' that may start an image
+fn open_marker(tail: &str) -> Option<usize> {
+    let last = tail.chars().next_back()?;
+    if REPEATABLE.contains(&last) {
+        return Some(tail.trim_end_matches(last).len());
+    }
+    matches!(last, ']' | '!' | '\\').then(|| tail.len() - last.len_utf8())
+}
@@ -0,0 +1,113 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use sysinfo::System;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::AppState;
+
+// Memory pressure monitor. A 4B model plus its context can take most of the
+// free memory on an 8 GB machine, so when available system memory runs low the
+// monitor steps in before the OS starts swapping or kills the app: first new
+// contexts are created smaller, then an idle model is unloaded (it is loaded
+// again on the next translation). Every step is reported on `memory-pressure`.
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+const MIB: u64 = 1024 * 1024;
+// Below this much available memory, contexts are created with REDUCED_CONTEXT_TOKENS
+const LOW_MEMORY: u64 = 1024 * MIB;
+// Below this, an idle model is unloaded as well
+const CRITICAL_MEMORY: u64 = 512 * MIB;
+// Full-size contexts again once this much is available (above LOW_MEMORY, to avoid flapping)
+const RECOVERED_MEMORY: u64 = 2048 * MIB;
+pub const REDUCED_CONTEXT_TOKENS: u32 = 2048;
+
+#[derive(Default)]
+pub struct MemoryState {
+    reduced_context: AtomicBool,
+}
+
+#[derive(Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+enum Mitigation {
+    ReducedContext,
+    UnloadedModel,
+    // Pressure is over; full-size contexts again
+    Recovered,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MemoryPressure {
+    action: Mitigation,
+    available_bytes: u64,
+    total_bytes: u64,
+    message: String,
+}
+
+/// Context size for new translation contexts: `full` unless memory is low.
+pub fn context_tokens(state: &AppState, full: u32) -> u32 {
+    if state.memory.reduced_context.load(Ordering::Relaxed) {
+        full.min(REDUCED_CONTEXT_TOKENS)
+    } else {
+        full
+    }
+}
+
+fn report(app: &AppHandle, system: &System, action: Mitigation, message: String) {
+    eprintln!("{}", message);
+    let payload = MemoryPressure {
+        action,
+        available_bytes: system.available_memory(),
+        total_bytes: system.total_memory(),
+        message,
+    };
+    app.emit("memory-pressure", payload).unwrap_or(());
+}
+
+// Unloads the model unless a translation is using it right now
+fn unload_idle_model(state: &AppState) -> Option<String> {
+    let mut model = state.model.try_lock().ok()?;
+    model.take()?;
+    state.current_model_id.lock().unwrap().take()
+}
+
+fn check(app: &AppHandle, system: &mut System) {
+    system.refresh_memory();
+    let available = system.available_memory();
+    // Zero means the platform doesn't report it
+    if available == 0 {
+        return;
+    }
+    let state = app.state::<AppState>();
+    let reduced = &state.memory.reduced_context;
+
+    if available < LOW_MEMORY && !reduced.swap(true, Ordering::Relaxed) {
+        let message = format!(
+            "Low memory ({} MiB available): new translations use a {}-token context",
+            available / MIB,
+            REDUCED_CONTEXT_TOKENS
+        );
+        report(app, system, Mitigation::ReducedContext, message);
+    }
+    if available < CRITICAL_MEMORY {
+        if let Some(model_id) = unload_idle_model(&state) {
+            let message = format!("Very low memory ({} MiB available): unloaded idle model '{}'", available / MIB, model_id);
+            report(app, system, Mitigation::UnloadedModel, message);
+        }
+    }
+    if available > RECOVERED_MEMORY && reduced.swap(false, Ordering::Relaxed) {
+        let message = format!("Memory recovered ({} MiB available): full-size contexts again", available / MIB);
+        report(app, system, Mitigation::Recovered, message);
+    }
+}
+
+pub fn start_monitor(app: AppHandle) {
+    thread::spawn(move || {
+        let mut system = System::new();
+        loop {
+            check(&app, &mut system);
+            thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
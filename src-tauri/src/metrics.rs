@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::word_diff;
+
+// Reference-based translation metrics, computed the usual way (corpus BLEU-4
+// with brevity penalty, chrF with character 6-grams and beta = 2) so scores are
+// comparable between models, prompts and sampler settings. Both are 0-100.
+
+const BLEU_ORDER: usize = 4;
+const CHRF_ORDER: usize = 6;
+const CHRF_BETA: f64 = 2.0;
+
+#[derive(Clone, Copy, Debug, Default, serde::Serialize)]
+pub struct Scores {
+    pub bleu: f64,
+    pub chrf: f64,
+}
+
+// Words for spaced scripts, single characters for CJK, punctuation on its own
+fn bleu_tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for word in word_diff::tokenize(text) {
+        let mut current = String::new();
+        for c in word.chars() {
+            if c.is_alphanumeric() {
+                current.push(c);
+            } else {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                tokens.push(c.to_string());
+            }
+        }
+        if !current.is_empty() {
+            tokens.push(current);
+        }
+    }
+    tokens
+}
+
+fn ngram_counts<T: Eq + Hash + Clone>(items: &[T], n: usize) -> HashMap<&[T], usize> {
+    let mut counts = HashMap::new();
+    if items.len() >= n {
+        for gram in items.windows(n) {
+            *counts.entry(gram).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+// Clipped matches between hypothesis and reference n-grams
+fn overlap<T: Eq + Hash>(hypothesis: &HashMap<&[T], usize>, reference: &HashMap<&[T], usize>) -> usize {
+    hypothesis.iter().map(|(gram, count)| (*count).min(*reference.get(gram).unwrap_or(&0))).sum()
+}
+
+#[derive(Clone, Copy, Default)]
+struct NgramStats {
+    matches: usize,
+    hypothesis: usize,
+    reference: usize,
+}
+
+/// Accumulates sufficient statistics over a test set.
+#[derive(Default)]
+pub struct Corpus {
+    bleu: [NgramStats; BLEU_ORDER],
+    chrf: [NgramStats; CHRF_ORDER],
+    hypothesis_len: usize,
+    reference_len: usize,
+}
+
+fn chrf_stats(hypothesis: &str, reference: &str) -> [NgramStats; CHRF_ORDER] {
+    let hyp: Vec<char> = hypothesis.chars().filter(|c| !c.is_whitespace()).collect();
+    let refr: Vec<char> = reference.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut stats = [NgramStats::default(); CHRF_ORDER];
+    for (i, stat) in stats.iter_mut().enumerate() {
+        let (h, r) = (ngram_counts(&hyp, i + 1), ngram_counts(&refr, i + 1));
+        *stat = NgramStats {
+            matches: overlap(&h, &r),
+            hypothesis: h.values().sum(),
+            reference: r.values().sum(),
+        };
+    }
+    stats
+}
+
+fn chrf_score(stats: &[NgramStats]) -> f64 {
+    let orders: Vec<&NgramStats> = stats.iter().filter(|s| s.hypothesis > 0 && s.reference > 0).collect();
+    if orders.is_empty() {
+        return 0.0;
+    }
+    let precision = orders.iter().map(|s| s.matches as f64 / s.hypothesis as f64).sum::<f64>() / orders.len() as f64;
+    let recall = orders.iter().map(|s| s.matches as f64 / s.reference as f64).sum::<f64>() / orders.len() as f64;
+    if precision + recall == 0.0 {
+        return 0.0;
+    }
+    let beta2 = CHRF_BETA * CHRF_BETA;
+    100.0 * (1.0 + beta2) * precision * recall / (beta2 * precision + recall)
+}
+
+/// chrF of a single sentence pair.
+pub fn sentence_chrf(hypothesis: &str, reference: &str) -> f64 {
+    chrf_score(&chrf_stats(hypothesis, reference))
+}
+
+impl Corpus {
+    pub fn add(&mut self, hypothesis: &str, reference: &str) {
+        let hyp = bleu_tokens(hypothesis);
+        let refr = bleu_tokens(reference);
+        self.hypothesis_len += hyp.len();
+        self.reference_len += refr.len();
+        for (i, stat) in self.bleu.iter_mut().enumerate() {
+            let (h, r) = (ngram_counts(&hyp, i + 1), ngram_counts(&refr, i + 1));
+            stat.matches += overlap(&h, &r);
+            stat.hypothesis += h.values().sum::<usize>();
+        }
+        for (total, sentence) in self.chrf.iter_mut().zip(chrf_stats(hypothesis, reference)) {
+            total.matches += sentence.matches;
+            total.hypothesis += sentence.hypothesis;
+            total.reference += sentence.reference;
+        }
+    }
+
+    fn bleu(&self) -> f64 {
+        if self.hypothesis_len == 0 || self.bleu.iter().any(|s| s.matches == 0) {
+            return 0.0;
+        }
+        let log_precision = self
+            .bleu
+            .iter()
+            .map(|s| (s.matches as f64 / s.hypothesis as f64).ln())
+            .sum::<f64>()
+            / BLEU_ORDER as f64;
+        let brevity = if self.hypothesis_len >= self.reference_len {
+            1.0
+        } else {
+            (1.0 - self.reference_len as f64 / self.hypothesis_len as f64).exp()
+        };
+        100.0 * brevity * log_precision.exp()
+    }
+
+    pub fn scores(&self) -> Scores {
+        Scores { bleu: self.bleu(), chrf: chrf_score(&self.chrf) }
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::UNIX_EPOCH;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager};
+
+// SHA-256 of model files: checked against `sha256` in the model registry
+// before loading (see model_registry.rs), so a truncated or corrupted file is
+// reported as such instead of as an opaque llama.cpp load failure, and
+// recorded with history entries (see environment.rs). Hashing takes seconds
+// per gigabyte, so hashes are kept in `model_hashes.json` in the app data dir
+// with the file's size and modification time, and a file is only hashed again
+// once it changes.
+
+const HASHES_FILE: &str = "model_hashes.json";
+const HASH_BUFFER_BYTES: usize = 1024 * 1024;
+
+// A model file as last hashed; edited or replaced files don't match
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileStamp {
+    len: u64,
+    modified_secs: Option<u64>,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<FileStamp> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified_secs = metadata.modified().ok().and_then(|time| time.duration_since(UNIX_EPOCH).ok()).map(|d| d.as_secs());
+        Some(FileStamp { len: metadata.len(), modified_secs })
+    }
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct KnownHash {
+    #[serde(flatten)]
+    stamp: FileStamp,
+    sha256: String,
+}
+
+static HASHES: OnceLock<Mutex<HashMap<PathBuf, KnownHash>>> = OnceLock::new();
+
+fn hashes_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_data_dir().ok().map(|dir| dir.join(HASHES_FILE))
+}
+
+fn with_hashes<T>(app: &AppHandle, f: impl FnOnce(&mut HashMap<PathBuf, KnownHash>) -> T) -> T {
+    let hashes = HASHES.get_or_init(|| {
+        let stored = hashes_path(app)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|raw| serde_json::from_str(&raw).ok());
+        Mutex::new(stored.unwrap_or_default())
+    });
+    f(&mut hashes.lock().unwrap())
+}
+
+fn store(app: &AppHandle, path: PathBuf, known: KnownHash) {
+    let json = with_hashes(app, |hashes| {
+        hashes.insert(path, known);
+        serde_json::to_string_pretty(hashes)
+    });
+    let result = match (hashes_path(app), json) {
+        (Some(file), Ok(json)) => file
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&file, json))
+            .map_err(|e| e.to_string()),
+        (None, _) => Err("no app data dir".to_string()),
+        (_, Err(e)) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to save model hashes: {}", e);
+    }
+}
+
+/// The hash of `path` from an earlier run, if the file hasn't changed since.
+pub fn known(app: &AppHandle, path: &Path) -> Option<String> {
+    let stamp = FileStamp::of(path)?;
+    with_hashes(app, |hashes| hashes.get(path).filter(|known| known.stamp == stamp).map(|known| known.sha256.clone()))
+}
+
+/// Hashes `path` unless its hash is known. Returns None if `cancelled` turned
+/// true while reading.
+pub fn hash(app: &AppHandle, path: &Path, cancelled: impl Fn() -> bool) -> std::io::Result<Option<String>> {
+    if let Some(hash) = known(app, path) {
+        return Ok(Some(hash));
+    }
+    let stamp = FileStamp::of(path);
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; HASH_BUFFER_BYTES];
+    loop {
+        if cancelled() {
+            return Ok(None);
+        }
+        match file.read(&mut buffer)? {
+            0 => break,
+            read => hasher.update(&buffer[..read]),
+        }
+    }
+    let sha256: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    // A file that changed while being read is hashed again next time
+    if let Some(stamp) = stamp.filter(|stamp| FileStamp::of(path).as_ref() == Some(stamp)) {
+        store(app, path.to_path_buf(), KnownHash { stamp, sha256: sha256.clone() });
+    }
+    Ok(Some(sha256))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckFailure {
+    // Read fine, but the contents differ: truncated, corrupted or another file
+    Mismatch,
+    Unreadable,
+}
+
+// Payload of `model-check-failed`
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelCheckFailed {
+    model_id: String,
+    path: PathBuf,
+    reason: CheckFailure,
+    expected_sha256: String,
+    actual_sha256: Option<String>,
+    size_bytes: Option<u64>,
+    message: String,
+}
+
+pub enum CheckError {
+    Cancelled,
+    Failed(String),
+}
+
+/// Checks `path` against the `expected` SHA-256, reading it only if it changed
+/// since it last passed. A failure is also sent as `model-check-failed`.
+pub fn verify(app: &AppHandle, model_id: &str, path: &Path, expected: &str, cancelled: impl Fn() -> bool) -> Result<(), CheckError> {
+    let (reason, actual, message) = match hash(app, path, cancelled) {
+        Ok(None) => return Err(CheckError::Cancelled),
+        Ok(Some(actual)) if actual.eq_ignore_ascii_case(expected.trim()) => return Ok(()),
+        Ok(Some(actual)) => {
+            let message = format!(
+                "Model file {:?} is damaged or incomplete: its SHA-256 is {} but {} was expected. Download it again.",
+                path, actual, expected
+            );
+            (CheckFailure::Mismatch, Some(actual), message)
+        }
+        Err(e) => (CheckFailure::Unreadable, None, format!("Failed to read model file {:?}: {}", path, e)),
+    };
+    let payload = ModelCheckFailed {
+        model_id: model_id.to_string(),
+        path: path.to_path_buf(),
+        reason,
+        expected_sha256: expected.to_string(),
+        actual_sha256: actual,
+        size_bytes: std::fs::metadata(path).ok().map(|m| m.len()),
+        message: message.clone(),
+    };
+    app.emit("model-check-failed", payload).unwrap_or(());
+    Err(CheckError::Failed(message))
+}
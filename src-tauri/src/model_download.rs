@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::models;
+use crate::updater::{self, CatalogEntry};
+
+// Downloads of catalog models (see updater.rs), or of any GGUF file on the
+// Hugging Face Hub, into `{app data dir}/models`, which models::resolve and
+// list_models search. Progress goes out on `model-download-progress`. The file
+// is written as `{name}.part` and renamed once complete, so an interrupted
+// download is never mistaken for a model; a later download of the same file
+// resumes from the part file with a Range request. Cancelling deletes it.
+
+const MODELS_DIR: &str = "models";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+// Per read, not for the whole multi-gigabyte transfer
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+const BUFFER_BYTES: usize = 256 * 1024;
+const HF_ENDPOINT: &str = "https://huggingface.co";
+// Read for gated or private repos
+const HF_TOKEN_VAR: &str = "HF_TOKEN";
+
+// What to fetch; `id` names the download in progress events and for cancelling
+struct Source {
+    id: String,
+    file_name: String,
+    url: String,
+    // Expected size, if known before the server says
+    size_bytes: Option<u64>,
+    token: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadProgress<'a> {
+    id: &'a str,
+    downloaded_bytes: u64,
+    // From the server, else from the catalog; None if neither knows
+    total_bytes: Option<u64>,
+    percent: Option<f64>,
+    // Average since the download started
+    bytes_per_second: u64,
+    done: bool,
+}
+
+impl Source {
+    fn catalog(entry: CatalogEntry) -> Source {
+        Source { id: entry.id, file_name: entry.file_name, url: entry.url, size_bytes: entry.size_bytes, token: None }
+    }
+}
+
+// Models being downloaded, mapped to whether they were asked to stop
+static ACTIVE: OnceLock<Mutex<HashMap<String, bool>>> = OnceLock::new();
+
+fn with_active<T>(f: impl FnOnce(&mut HashMap<String, bool>) -> T) -> T {
+    f(&mut ACTIVE.get_or_init(Default::default).lock().unwrap())
+}
+
+fn models_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(MODELS_DIR))
+}
+
+/// Adds the download folder to the model search paths.
+pub fn init(app: &AppHandle) {
+    match models_dir(app) {
+        Ok(dir) => models::set_download_dir(dir),
+        Err(e) => eprintln!("No folder for downloaded models: {}", e),
+    }
+}
+
+// "name.gguf" -> "name.gguf.part"
+fn partial_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".part");
+    target.with_file_name(name)
+}
+
+fn emit_progress(app: &AppHandle, id: &str, downloaded: u64, total: Option<u64>, started: Instant, done: bool) {
+    let elapsed = started.elapsed().as_secs_f64();
+    let progress = DownloadProgress {
+        id,
+        downloaded_bytes: downloaded,
+        total_bytes: total,
+        percent: total.filter(|&total| total > 0).map(|total| downloaded as f64 * 100.0 / total as f64),
+        bytes_per_second: if elapsed > 0.0 { (downloaded as f64 / elapsed) as u64 } else { 0 },
+        done,
+    };
+    app.emit("model-download-progress", progress).unwrap_or(());
+}
+
+// "bytes 100-999/1000" -> 1000
+fn content_range_total(value: &str) -> Option<u64> {
+    value.rsplit('/').next().and_then(|total| total.trim().parse().ok())
+}
+
+fn download(app: &AppHandle, source: &Source) -> Result<PathBuf, String> {
+    // Names come from a server or the user; never let them write outside the models folder
+    if Path::new(&source.file_name).file_name().and_then(|name| name.to_str()) != Some(source.file_name.as_str()) {
+        return Err(format!("Invalid model file name '{}'", source.file_name));
+    }
+    let dir = models_dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    let target = dir.join(&source.file_name);
+    let part = partial_path(&target);
+    let resume_from = std::fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+
+    let agent = ureq::AgentBuilder::new().timeout_connect(CONNECT_TIMEOUT).timeout_read(READ_TIMEOUT).build();
+    let mut request = agent.get(&source.url);
+    if let Some(token) = &source.token {
+        request = request.set("Authorization", &format!("Bearer {}", token));
+    }
+    if resume_from > 0 {
+        request = request.set("Range", &format!("bytes={}-", resume_from));
+    }
+    let response = request.call().map_err(|e| format!("Failed to download {}: {}", source.url, e))?;
+    // A server that ignores the range sends the whole file again
+    let resumed = resume_from > 0 && response.status() == 206;
+    let mut downloaded = if resumed { resume_from } else { 0 };
+    let total = if resumed {
+        response.header("Content-Range").and_then(content_range_total)
+    } else {
+        response.header("Content-Length").and_then(|len| len.parse().ok())
+    }
+    .or(source.size_bytes);
+    let mut reader = response.into_reader();
+    let file = if resumed {
+        OpenOptions::new().append(true).open(&part)
+    } else {
+        File::create(&part)
+    };
+    let mut file = file.map_err(|e| format!("Failed to open {:?}: {}", part, e))?;
+
+    let started = Instant::now();
+    let mut last_progress = started;
+    let mut buffer = vec![0u8; BUFFER_BYTES];
+    let mut cancelled = false;
+    let result = loop {
+        if with_active(|active| active.get(&source.id).copied().unwrap_or(false)) {
+            cancelled = true;
+            break Err("Download cancelled".to_string());
+        }
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break Ok(()),
+            Ok(read) => read,
+            Err(e) => break Err(format!("Failed to download {}: {}", source.url, e)),
+        };
+        if let Err(e) = file.write_all(&buffer[..read]) {
+            break Err(format!("Failed to write {:?}: {}", part, e));
+        }
+        downloaded += read as u64;
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            emit_progress(app, &source.id, downloaded, total, started, false);
+            last_progress = Instant::now();
+        }
+    };
+    let result = result.and_then(|()| match total {
+        Some(total) if total != downloaded => {
+            Err(format!("Download of {} ended after {} of {} bytes", source.file_name, downloaded, total))
+        }
+        _ => file.sync_all().map_err(|e| format!("Failed to write {:?}: {}", part, e)),
+    });
+    drop(file);
+    if let Err(e) = result {
+        // Anything short of the expected size is kept to resume from; more than
+        // that can't be part of this file
+        if cancelled || total.is_some_and(|total| downloaded > total) {
+            let _ = std::fs::remove_file(&part);
+        }
+        return Err(e);
+    }
+    std::fs::rename(&part, &target).map_err(|e| format!("Failed to move {:?} to {:?}: {}", part, target, e))?;
+    emit_progress(app, &source.id, downloaded, Some(downloaded), started, true);
+    Ok(target)
+}
+
+// Runs one download per id at a time
+fn run(app: &AppHandle, source: Source) -> Result<String, String> {
+    let id = source.id.clone();
+    let running = with_active(|active| active.contains_key(&id) || active.insert(id.clone(), false).is_some());
+    if running {
+        return Err(format!("Model '{}' is already being downloaded", id));
+    }
+    let result = download(app, &source);
+    with_active(|active| active.remove(&id));
+    result.map(|path| path.to_string_lossy().into_owned())
+}
+
+/// Downloads the catalog model `id` and returns where it was saved. Progress is
+/// reported on `model-download-progress`.
+#[tauri::command]
+pub async fn download_model(id: String, app: AppHandle) -> Result<String, String> {
+    let entry = updater::catalog(&app)
+        .into_iter()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| format!("Model '{}' is not in the catalog; check for updates first", id))?;
+    run(&app, Source::catalog(entry))
+}
+
+// Hugging Face names: "owner/name" repos and branch, tag or commit revisions
+fn valid_hf_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !name.starts_with('.')
+}
+
+/// Downloads `file` from the Hugging Face repo `repo` (e.g.
+/// "Qwen/Qwen2.5-1.5B-Instruct-GGUF") and returns where it was saved. `file`
+/// may be in a folder of the repo; it is saved under its own name. Progress is
+/// reported on `model-download-progress` with "{repo}/{file}" as the id, which
+/// also cancels it.
+#[tauri::command]
+pub async fn download_from_hf(repo: String, file: String, revision: Option<String>, app: AppHandle) -> Result<String, String> {
+    let repo = repo.trim().trim_matches('/');
+    let file = file.trim().trim_start_matches('/');
+    let revision = revision.as_deref().map(str::trim).filter(|r| !r.is_empty()).unwrap_or("main");
+    if !matches!(repo.split('/').collect::<Vec<_>>()[..], [owner, name] if valid_hf_name(owner) && valid_hf_name(name)) {
+        return Err(format!("'{}' is not a Hugging Face repo id (owner/name)", repo));
+    }
+    if !valid_hf_name(revision) {
+        return Err(format!("Invalid revision '{}'", revision));
+    }
+    if file.split('/').any(|segment| segment.is_empty() || segment == "." || segment == "..") {
+        return Err(format!("Invalid file path '{}'", file));
+    }
+    let file_name = file.rsplit('/').next().unwrap_or(file);
+    if !file_name.to_lowercase().ends_with(".gguf") {
+        return Err(format!("'{}' is not a GGUF file", file_name));
+    }
+    let source = Source {
+        id: format!("{}/{}", repo, file),
+        file_name: file_name.to_string(),
+        url: format!("{}/{}/resolve/{}/{}", HF_ENDPOINT, repo, revision, file),
+        size_bytes: None,
+        token: std::env::var(HF_TOKEN_VAR).ok().filter(|token| !token.is_empty()),
+    };
+    run(&app, source)
+}
+
+/// Stops a running download, catalog or Hugging Face; its partial file is deleted.
+#[tauri::command]
+pub async fn cancel_model_download(id: String) -> Result<(), String> {
+    with_active(|active| {
+        if let Some(cancelled) = active.get_mut(&id) {
+            *cancelled = true;
+        }
+    });
+    Ok(())
+}
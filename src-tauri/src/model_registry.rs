@@ -0,0 +1,205 @@
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::gpu::SplitMode;
+use crate::prompts::PromptTemplates;
+use crate::AppState;
+
+// Model ids and the GGUF file each one loads, kept in `models.json` in the app
+// config dir so models can be added without rebuilding. The file is written
+// with the bundled models on first run and reloaded when it changes, like the
+// prompt templates (see prompts.rs). Besides the file name an entry can set
+// its own context size and chat/example templates (same placeholders as
+// chat.txt and example.txt), for models that don't use ChatML, the file's
+// SHA-256 to verify it before loading, and the GPUs to split it across.
+//
+//   { "models": [{ "id": "high", "fileName": "qwen2.5-3b-instruct-q4_k_m.gguf",
+//                  "contextTokens": 8192, "gpuDevices": [0, 1], "mainGpu": 0 }] }
+
+const REGISTRY_FILE: &str = "models.json";
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+// Used for ids the registry doesn't know, as the hardcoded mapping did
+const FALLBACK_MODEL: &str = "light";
+// Smallest contextTokens accepted: room for the output plus a useful prompt
+const MIN_CONTEXT_TOKENS: u32 = crate::MAX_OUTPUT_TOKENS as u32 + 512;
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelEntry {
+    pub id: String,
+    // Looked up in the model search paths, see models.rs
+    pub file_name: String,
+    // Context per chunk; None uses the default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_tokens: Option<u32>,
+    // Replace chat.txt and example.txt for this model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chat_template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub example_template: Option<String>,
+    // Checked before the file is loaded, see model_check.rs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    // Device indexes from get_gpu_info to split the model across, see gpu.rs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_devices: Option<Vec<usize>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split_mode: Option<SplitMode>,
+    // Holds the whole model with split mode "none", else the small tensors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main_gpu: Option<u32>,
+}
+
+impl ModelEntry {
+    fn bundled(id: &str, file_name: &str) -> ModelEntry {
+        ModelEntry {
+            id: id.to_string(),
+            file_name: file_name.to_string(),
+            context_tokens: None,
+            chat_template: None,
+            example_template: None,
+            sha256: None,
+            gpu_devices: None,
+            split_mode: None,
+            main_gpu: None,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RegistryFile {
+    models: Vec<ModelEntry>,
+}
+
+/// The bundled models.
+pub fn defaults() -> Vec<ModelEntry> {
+    vec![
+        ModelEntry::bundled("nano", "qwen2.5-0.5b-instruct-q2_k.gguf"),
+        ModelEntry::bundled("light", "qwen2.5-0.5b-instruct-q4_k_m.gguf"),
+        ModelEntry::bundled("balanced", "qwen2.5-1.5b-instruct-q4_k_m.gguf"),
+        ModelEntry::bundled("high", "qwen2.5-3b-instruct-q4_k_m.gguf"),
+    ]
+}
+
+/// The registry entry for `model_id`, if it has one.
+pub fn entry(state: &AppState, model_id: &str) -> Option<ModelEntry> {
+    state.model_registry.lock().unwrap().iter().find(|entry| entry.id == model_id).cloned()
+}
+
+/// The file to load for `model_id`; unknown ids get the fallback model's file.
+pub fn file_name(state: &AppState, model_id: &str) -> String {
+    entry(state, model_id)
+        .or_else(|| entry(state, FALLBACK_MODEL))
+        .or_else(|| defaults().into_iter().find(|entry| entry.id == FALLBACK_MODEL))
+        .map(|entry| entry.file_name)
+        .unwrap_or_default()
+}
+
+/// Registered model ids, in file order.
+pub fn ids(state: &AppState) -> Vec<String> {
+    state.model_registry.lock().unwrap().iter().map(|entry| entry.id.clone()).collect()
+}
+
+/// The prompt templates for `model_id`: the global ones with the entry's own
+/// chat and example templates, if it has any.
+pub fn templates(state: &AppState, model_id: &str) -> PromptTemplates {
+    let mut templates = state.prompts.lock().unwrap().clone();
+    if let Some(entry) = entry(state, model_id) {
+        templates.chat = entry.chat_template.unwrap_or(templates.chat);
+        templates.example = entry.example_template.unwrap_or(templates.example);
+    }
+    templates
+}
+
+fn registry_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(REGISTRY_FILE))
+}
+
+// An unreadable or invalid file keeps the bundled models, so translation still works
+fn load(path: &Path) -> Vec<ModelEntry> {
+    let parsed = std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|raw| serde_json::from_str::<RegistryFile>(&raw).map_err(|e| e.to_string()));
+    match parsed {
+        Ok(file) => file.models.into_iter().map(checked).collect(),
+        Err(e) => {
+            eprintln!("Invalid model registry {:?}, using the bundled models: {}", path, e);
+            defaults()
+        }
+    }
+}
+
+// A context too small for the output reservation would leave no prompt budget
+fn checked(mut entry: ModelEntry) -> ModelEntry {
+    if let Some(tokens) = entry.context_tokens.filter(|&tokens| tokens < MIN_CONTEXT_TOKENS) {
+        eprintln!(
+            "contextTokens {} for model '{}' is below the minimum of {}; using {}",
+            tokens, entry.id, MIN_CONTEXT_TOKENS, MIN_CONTEXT_TOKENS
+        );
+        entry.context_tokens = Some(MIN_CONTEXT_TOKENS);
+    }
+    entry
+}
+
+fn reload(app: &AppHandle, path: &Path) -> Vec<ModelEntry> {
+    let models = load(path);
+    *app.state::<AppState>().model_registry.lock().unwrap() = models.clone();
+    app.emit("model-registry-reloaded", &models).unwrap_or(());
+    models
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn watch(app: AppHandle, path: PathBuf) {
+    thread::spawn(move || {
+        let mut last = modified_time(&path);
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+            let current = modified_time(&path);
+            if current != last {
+                reload(&app, &path);
+                last = current;
+            }
+        }
+    });
+}
+
+/// Writes the bundled models to `models.json` if it doesn't exist, loads it and
+/// starts watching for edits.
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let path = registry_path(app)?;
+    if !path.exists() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(&RegistryFile { models: defaults() }).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    }
+    *app.state::<AppState>().model_registry.lock().unwrap() = load(&path);
+    watch(app.clone(), path);
+    Ok(())
+}
+
+/// Replaces the registry and writes it to `models.json`, e.g. when settings
+/// are imported.
+pub fn replace(app: &AppHandle, models: Vec<ModelEntry>) -> Result<(), String> {
+    let path = registry_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    }
+    let models: Vec<ModelEntry> = models.into_iter().map(checked).collect();
+    let json = serde_json::to_string_pretty(&RegistryFile { models }).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    reload(app, &path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_model_registry(state: State<'_, AppState>) -> Result<Vec<ModelEntry>, String> {
+    Ok(state.model_registry.lock().unwrap().clone())
+}
@@ -0,0 +1,218 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::{model_registry, settings, AppState};
+
+// Where model files are looked up. Model ids map to file names in the model
+// registry (see model_registry.rs), which are searched for in the directories
+// below. Users can also register any GGUF file under a model id of their own
+// (`customModels` in the settings); it is loaded from exactly that path and
+// prompted with the global templates, so it should be an instruct model that
+// understands them.
+
+// First bytes of every GGUF file
+pub const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+
+/// A GGUF file registered by the user.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomModel {
+    // Model id passed to `translate`
+    pub name: String,
+    pub path: PathBuf,
+}
+
+// Where download_model saves models, see model_download.rs
+static DOWNLOAD_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_download_dir(dir: PathBuf) {
+    let _ = DOWNLOAD_DIR.set(dir);
+}
+
+/// Locations searched for a model file, in priority order.
+pub fn candidate_paths(file_name: &str) -> Vec<PathBuf> {
+    let mut potential_paths = Vec::new();
+
+    // Priority 1: Check SPARK_MODELS_PATH environment variable
+    if let Ok(env_path) = std::env::var("SPARK_MODELS_PATH") {
+        potential_paths.push(PathBuf::from(format!("{}/{}", env_path, file_name)));
+    }
+
+    // Priority 2: Models downloaded from the catalog
+    if let Some(dir) = DOWNLOAD_DIR.get() {
+        potential_paths.push(dir.join(file_name));
+    }
+
+    // Priority 3-6: Fallback paths
+    potential_paths.extend(vec![
+        PathBuf::from(format!("x:/Models/{}", file_name)),
+        PathBuf::from(format!("models/{}", file_name)),
+        PathBuf::from(format!("../models/{}", file_name)),
+        PathBuf::from(format!("C:/models/{}", file_name)),
+    ]);
+    potential_paths
+}
+
+fn custom_path(state: &AppState, model_id: &str) -> Option<PathBuf> {
+    let settings = state.settings.lock().unwrap();
+    settings.custom_models.iter().find(|model| model.name == model_id).map(|model| model.path.clone())
+}
+
+/// The file of a custom model, else the first existing file for `model_id`'s
+/// registry entry, or an error listing where it was searched.
+pub fn resolve(state: &AppState, model_id: &str) -> Result<PathBuf, String> {
+    if let Some(path) = custom_path(state, model_id) {
+        return match path.is_file() {
+            true => Ok(path),
+            false => Err(format!("Model file {:?} registered for '{}' not found", path, model_id)),
+        };
+    }
+    let model_filename = model_registry::file_name(state, model_id);
+    let potential_paths = candidate_paths(&model_filename);
+    potential_paths.iter().find(|p| p.exists()).cloned().ok_or_else(|| {
+        let searched = potential_paths.iter().map(|p| format!("  - {:?}", p)).collect::<Vec<_>>().join("\n");
+        format!(
+            "Model file '{}' not found. Searched locations:\n{}\n\nTip: Set SPARK_MODELS_PATH environment variable to specify custom model directory.",
+            model_filename, searched
+        )
+    })
+}
+
+/// Directories searched for model files, in priority order.
+pub fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = candidate_paths("")
+        .iter()
+        .map(|p| PathBuf::from(p.to_string_lossy().trim_end_matches(['/', '\\'])))
+        .collect();
+    dirs.dedup();
+    dirs
+}
+
+// "qwen2.5-3b-instruct-q4_k_m.gguf" -> "q4_k_m"; also iq*, f16, bf16 and f32
+fn quantization(file_name: &str) -> Option<String> {
+    let stem = file_name.strip_suffix(".gguf").unwrap_or(file_name).to_lowercase();
+    stem.rsplit(['-', '.']).find_map(|part| {
+        let digits = part.strip_prefix("iq").or_else(|| part.strip_prefix('q'));
+        let quantized = digits.is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()));
+        (quantized || matches!(part, "f16" | "bf16" | "f32")).then(|| part.to_string())
+    })
+}
+
+/// A GGUF file found in the search paths or registered as a custom model.
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+    name: String,
+    path: PathBuf,
+    size_bytes: u64,
+    // From the file name; None when it doesn't say
+    quantization: Option<String>,
+    // Bundled or custom model id that resolves to this file, for `translate`
+    model_id: Option<String>,
+    loaded: bool,
+}
+
+/// Every GGUF file in the search paths plus the custom models, with the model
+/// id that loads each (if any) and whether it is the loaded model.
+#[tauri::command]
+pub async fn list_models(state: State<'_, AppState>) -> Result<Vec<ModelInfo>, String> {
+    let custom: Vec<CustomModel> = state.settings.lock().unwrap().custom_models.clone();
+    let mut files: Vec<PathBuf> = custom.iter().map(|model| model.path.clone()).collect();
+    for dir in search_dirs() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        files.extend(
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gguf"))),
+        );
+    }
+
+    // The same file can be reached through several search paths
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let ids: Vec<(String, PathBuf)> = model_registry::ids(&state)
+        .into_iter()
+        .chain(custom.into_iter().map(|model| model.name))
+        .filter_map(|id| resolve(&state, &id).ok().map(|path| (id, canonical(&path))))
+        .collect();
+    let loaded_id = state.current_model_id.lock().unwrap().clone();
+
+    let mut seen = std::collections::HashSet::new();
+    let mut models = Vec::new();
+    for path in files {
+        let canonical_path = canonical(&path);
+        let Ok(metadata) = std::fs::metadata(&canonical_path) else {
+            continue;
+        };
+        if !metadata.is_file() || !seen.insert(canonical_path.clone()) {
+            continue;
+        }
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let model_id = ids.iter().find(|(_, resolved)| *resolved == canonical_path).map(|(id, _)| id.clone());
+        models.push(ModelInfo {
+            quantization: quantization(&name),
+            loaded: model_id.is_some() && model_id == loaded_id,
+            name,
+            path: canonical_path,
+            size_bytes: metadata.len(),
+            model_id,
+        });
+    }
+    Ok(models)
+}
+
+fn check_gguf(path: &Path) -> Result<(), String> {
+    let mut magic = [0u8; 4];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    match &magic == GGUF_MAGIC {
+        true => Ok(()),
+        false => Err(format!("{:?} is not a GGUF model file", path)),
+    }
+}
+
+// Saves the changed list and tells every window about it
+fn update_custom_models(app: &AppHandle, state: &AppState, update: impl FnOnce(&mut Vec<CustomModel>)) -> Result<(), String> {
+    let mut settings = state.settings.lock().unwrap();
+    update(&mut settings.custom_models);
+    settings::save(app, &settings)?;
+    app.emit("settings-changed", &*settings).unwrap_or(());
+    Ok(())
+}
+
+/// Registers the GGUF file at `path` as model `name`, replacing an earlier
+/// registration of that name.
+#[tauri::command]
+pub async fn add_custom_model(name: String, path: String, app: AppHandle, state: State<'_, AppState>) -> Result<CustomModel, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Model name must not be empty".to_string());
+    }
+    if model_registry::entry(&state, &name).is_some() {
+        return Err(format!("'{}' is already a model in the registry (models.json)", name));
+    }
+    let path = PathBuf::from(path);
+    check_gguf(&path)?;
+    let path = path.canonicalize().unwrap_or(path);
+    // A loaded model keeps its old file until unloaded; better to refuse than to
+    // silently keep translating with it
+    if state.current_model_id.lock().unwrap().as_deref() == Some(name.as_str()) {
+        return Err(format!("Model '{}' is loaded; unload it before registering another file under its name", name));
+    }
+    let model = CustomModel { name, path };
+    update_custom_models(&app, &state, |models| {
+        models.retain(|existing| existing.name != model.name);
+        models.push(model.clone());
+    })?;
+    Ok(model)
+}
+
+/// Forgets the custom model `name`; its file is left alone.
+#[tauri::command]
+pub async fn remove_custom_model(name: String, app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    update_custom_models(&app, &state, |models| models.retain(|model| model.name != name))
+}
@@ -0,0 +1,87 @@
+use std::thread;
+use std::time::{Duration, Instant};
+use rdev::{Button, EventType};
+use tauri::{AppHandle, Manager};
+
+use crate::{dnd, os_window, popup, AppState};
+
+// Mouse gestures that open the popup, for users who select text with the mouse
+// rather than copy it with the keyboard. Detected in the rdev listener; when a
+// gesture completes, the selection is copied and translated like a double tap
+// (see popup::translate_selection).
+
+// A middle-button press and release further apart than this is a drag
+const DRAG_MIN_DISTANCE: f64 = 30.0;
+// Consecutive clicks of a triple click: at most this far apart in time and space
+const CLICK_INTERVAL: Duration = Duration::from_millis(500);
+const CLICK_SLOP: f64 = 8.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MouseTrigger {
+    #[default]
+    Off,
+    // Hold the middle button and drag
+    MiddleDrag,
+    // Triple-click with the left button (usually selects a line or paragraph)
+    TripleClick,
+}
+
+pub fn trigger(app: &AppHandle) -> MouseTrigger {
+    app.state::<AppState>().settings.lock().unwrap().mouse_trigger
+}
+
+/// Per-listener gesture state, fed every mouse event along with the current
+/// cursor position.
+#[derive(Default)]
+pub struct GestureDetector {
+    middle_down_at: Option<(f64, f64)>,
+    // Time and position of the last left click, and how many came in a row
+    last_click: Option<(Instant, f64, f64)>,
+    clicks: u32,
+}
+
+impl GestureDetector {
+    /// Returns true when `event` completes the configured gesture.
+    pub fn on_event(&mut self, trigger: MouseTrigger, event: &EventType, x: f64, y: f64) -> bool {
+        match (trigger, event) {
+            (MouseTrigger::MiddleDrag, EventType::ButtonPress(Button::Middle)) => {
+                self.middle_down_at = Some((x, y));
+                false
+            }
+            (MouseTrigger::MiddleDrag, EventType::ButtonRelease(Button::Middle)) => match self.middle_down_at.take() {
+                Some((start_x, start_y)) => (x - start_x).hypot(y - start_y) >= DRAG_MIN_DISTANCE,
+                None => false,
+            },
+            (MouseTrigger::TripleClick, EventType::ButtonPress(Button::Left)) => {
+                let now = Instant::now();
+                let continues = self.last_click.is_some_and(|(at, last_x, last_y)| {
+                    now.duration_since(at) < CLICK_INTERVAL && (x - last_x).hypot(y - last_y) <= CLICK_SLOP
+                });
+                self.clicks = if continues { self.clicks + 1 } else { 1 };
+                self.last_click = Some((now, x, y));
+                false
+            }
+            // Fire on release, once the application has extended the selection
+            (MouseTrigger::TripleClick, EventType::ButtonRelease(Button::Left)) if self.clicks == 3 => {
+                self.clicks = 0;
+                self.last_click = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Copies the current selection and opens the popup at (x, y).
+pub fn fire(app: &AppHandle, x: f64, y: f64) {
+    if dnd::is_active(app) {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        // The source application still has focus at this point
+        let source_window = os_window::foreground_window();
+        popup::translate_selection(&app, x, y, source_window);
+    });
+}
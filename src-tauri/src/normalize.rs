@@ -0,0 +1,36 @@
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+// Normalization of text copied from web pages. The same letter can arrive
+// composed ("é") or decomposed ("e" + combining accent), and pages are full of
+// invisible characters: zero-width spaces between words, byte order marks,
+// soft hyphens inside long words, word joiners. Each of those changes how the
+// text is tokenized and tends to come back as stray characters in the output.
+// Enabled per call with the `normalizeUnicode` option.
+
+// Zero-width space, BOM / zero-width no-break space, soft hyphen, word joiner,
+// Mongolian vowel separator (used as a zero-width space on older pages)
+fn is_invisible(c: char) -> bool {
+    matches!(c, '\u{200B}' | '\u{FEFF}' | '\u{00AD}' | '\u{2060}' | '\u{180E}')
+}
+
+// A zero-width joiner between letters is an artifact; between emoji it builds
+// one emoji from several (👨‍👩‍👧) and stays. Non-joiners are kept, Persian and
+// Indic scripts need them.
+fn is_stray_joiner(prev: Option<char>, c: char, next: Option<char>) -> bool {
+    c == '\u{200D}' && (prev.is_none_or(char::is_alphanumeric) || next.is_none_or(char::is_alphanumeric))
+}
+
+/// `text` in NFC, without invisible characters.
+pub fn clean(text: &str) -> String {
+    let composed: String = if is_nfc(text) { text.to_string() } else { text.nfc().collect() };
+    let chars: Vec<char> = composed.chars().collect();
+    chars
+        .iter()
+        .enumerate()
+        .filter(|&(i, &c)| {
+            let prev = i.checked_sub(1).map(|p| chars[p]);
+            !is_invisible(c) && !is_stray_joiner(prev, c, chars.get(i + 1).copied())
+        })
+        .map(|(_, &c)| c)
+        .collect()
+}
@@ -0,0 +1,81 @@
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+// Accessibility preferences set in the OS, so the popup can drop its streaming
+// animation and switch to plain high-contrast styling. WebView2 doesn't report
+// Windows' animation setting through `prefers-reduced-motion`, hence the query
+// from here. Changes are picked up by polling and sent as
+// `os-preferences-changed`. Only implemented on Windows; elsewhere both are
+// reported off and the page's own media queries apply.
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OsPreferences {
+    // "Show animations in Windows" is off
+    pub reduced_motion: bool,
+    // A high contrast theme is active
+    pub high_contrast: bool,
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::OsPreferences;
+    use windows_sys::Win32::UI::Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        SystemParametersInfoW, SPI_GETCLIENTAREAANIMATION, SPI_GETHIGHCONTRAST,
+    };
+
+    pub fn current() -> OsPreferences {
+        let mut animations = 1i32;
+        let animations_ok = unsafe {
+            SystemParametersInfoW(SPI_GETCLIENTAREAANIMATION, 0, &mut animations as *mut i32 as *mut _, 0) != 0
+        };
+        let mut contrast = HIGHCONTRASTW {
+            cbSize: std::mem::size_of::<HIGHCONTRASTW>() as u32,
+            dwFlags: 0,
+            lpszDefaultScheme: std::ptr::null_mut(),
+        };
+        let contrast_ok = unsafe {
+            SystemParametersInfoW(SPI_GETHIGHCONTRAST, contrast.cbSize, &mut contrast as *mut HIGHCONTRASTW as *mut _, 0)
+                != 0
+        };
+        OsPreferences {
+            reduced_motion: animations_ok && animations == 0,
+            high_contrast: contrast_ok && contrast.dwFlags & HCF_HIGHCONTRASTON != 0,
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod imp {
+    use super::OsPreferences;
+
+    pub fn current() -> OsPreferences {
+        OsPreferences::default()
+    }
+}
+
+pub use imp::current;
+
+/// Emits `os-preferences-changed` whenever the preferences change.
+pub fn start_watcher(app: AppHandle) {
+    thread::spawn(move || {
+        let mut last = current();
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+            let now = current();
+            if now != last {
+                app.emit("os-preferences-changed", now).unwrap_or(());
+                last = now;
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_os_preferences() -> Result<OsPreferences, String> {
+    Ok(current())
+}
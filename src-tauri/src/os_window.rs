@@ -0,0 +1,100 @@
+// Minimal queries about other applications' windows, used for popup placement
+// and focus handling.
+// Only implemented on Windows; elsewhere every query returns None and callers
+// fall back to mouse-based behaviour.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct WindowHandle(isize);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::{WindowHandle, WindowRect};
+    use windows_sys::Win32::Foundation::{HWND, RECT};
+    use windows_sys::Win32::Graphics::Gdi::{GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetDesktopWindow, GetForegroundWindow, GetShellWindow, GetWindowRect, IsIconic, IsWindow, SetForegroundWindow,
+    };
+
+    pub fn foreground_window() -> Option<WindowHandle> {
+        let hwnd = unsafe { GetForegroundWindow() };
+        if hwnd.is_null() {
+            None
+        } else {
+            Some(WindowHandle(hwnd as isize))
+        }
+    }
+
+    /// Outer bounds of a window, or None if it was closed or is minimized.
+    pub fn window_rect(handle: WindowHandle) -> Option<WindowRect> {
+        let hwnd = handle.0 as HWND;
+        let mut rect = RECT { left: 0, top: 0, right: 0, bottom: 0 };
+        let ok = unsafe { IsWindow(hwnd) != 0 && IsIconic(hwnd) == 0 && GetWindowRect(hwnd, &mut rect) != 0 };
+        ok.then(|| WindowRect {
+            x: rect.left,
+            y: rect.top,
+            width: rect.right - rect.left,
+            height: rect.bottom - rect.top,
+        })
+    }
+
+    /// Brings a window back to the foreground. Returns false if it no longer exists
+    /// or Windows refused the focus change.
+    pub fn focus_window(handle: WindowHandle) -> bool {
+        let hwnd = handle.0 as HWND;
+        unsafe { IsWindow(hwnd) != 0 && SetForegroundWindow(hwnd) != 0 }
+    }
+
+    /// True when the foreground window covers its whole monitor (games, slideshows,
+    /// video players). The desktop and shell windows never count.
+    pub fn foreground_is_fullscreen() -> bool {
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.is_null() || hwnd == GetDesktopWindow() || hwnd == GetShellWindow() {
+                return false;
+            }
+            let mut rect = RECT { left: 0, top: 0, right: 0, bottom: 0 };
+            if GetWindowRect(hwnd, &mut rect) == 0 {
+                return false;
+            }
+            let mut info: MONITORINFO = std::mem::zeroed();
+            info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+            if GetMonitorInfoW(MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST), &mut info) == 0 {
+                return false;
+            }
+            let monitor = info.rcMonitor;
+            rect.left <= monitor.left && rect.top <= monitor.top && rect.right >= monitor.right && rect.bottom >= monitor.bottom
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod imp {
+    use super::{WindowHandle, WindowRect};
+
+    pub fn foreground_window() -> Option<WindowHandle> {
+        None
+    }
+
+    pub fn window_rect(_handle: WindowHandle) -> Option<WindowRect> {
+        None
+    }
+
+    pub fn focus_window(_handle: WindowHandle) -> bool {
+        false
+    }
+
+    pub fn foreground_is_fullscreen() -> bool {
+        false
+    }
+}
+
+pub use imp::*;
@@ -0,0 +1,171 @@
+use tauri::{State, Window};
+
+use crate::source_code::{self, LexState, SegmentKind, Syntax};
+use crate::{priority, AppState, SegmentTranslator};
+
+// Unified diff translation: only comments on added/removed lines (or whole lines
+// of prose files) are translated. Headers, hunk markers and context lines are
+// copied verbatim and no lines are added or removed, so the patch still applies.
+
+const PROSE_EXTENSIONS: &[&str] = &["md", "markdown", "txt", "rst", "adoc"];
+
+enum FileKind {
+    Code(&'static Syntax),
+    Prose,
+    Unknown,
+}
+
+fn file_kind(path: &str) -> FileKind {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let ext = match name.rsplit_once('.') {
+        Some((_, ext)) => ext.to_lowercase(),
+        None => name.to_lowercase(),
+    };
+    if PROSE_EXTENSIONS.contains(&ext.as_str()) {
+        return FileKind::Prose;
+    }
+    match source_code::syntax_for(&ext) {
+        Some(syntax) => FileKind::Code(syntax),
+        None => FileKind::Unknown,
+    }
+}
+
+// "+++ b/src/main.rs\t2024-01-01" -> "src/main.rs"
+fn header_path(line: &str) -> &str {
+    let path = line[4..].split('\t').next().unwrap_or("").trim();
+    path.strip_prefix("a/").or_else(|| path.strip_prefix("b/")).unwrap_or(path)
+}
+
+// A translated line must stay a single line or the hunk counts break
+fn single_line(text: String) -> String {
+    if text.contains('\n') {
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    } else {
+        text
+    }
+}
+
+// "@@ -12,5 +12,6 @@ fn main" -> (5, 6). Counts default to 1 when omitted.
+fn hunk_counts(line: &str) -> Option<(usize, usize)> {
+    let mut parts = line.split_whitespace().skip(1);
+    let count = |range: &str| match range.split_once(',') {
+        Some((_, count)) => count.parse().ok(),
+        None => Some(1),
+    };
+    let old = count(parts.next()?.strip_prefix('-')?)?;
+    let new = count(parts.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+struct FileState {
+    kind: FileKind,
+    old: LexState,
+    new: LexState,
+}
+
+impl FileState {
+    fn new(path: &str) -> Self {
+        FileState { kind: file_kind(path), old: LexState::default(), new: LexState::default() }
+    }
+}
+
+fn translate_line(content: &str, side: &mut LexState, kind: &FileKind, translator: &SegmentTranslator) -> Result<String, String> {
+    match kind {
+        FileKind::Prose if content.chars().any(|c| c.is_alphabetic()) => Ok(single_line(translator.translate(content)?)),
+        FileKind::Code(syntax) => {
+            let mut out = String::new();
+            for segment in source_code::lex(content, syntax, side) {
+                if segment.kind == SegmentKind::Comment {
+                    out.push_str(&single_line(source_code::translate_comment(&segment.text, translator)?));
+                } else {
+                    out.push_str(&segment.text);
+                }
+            }
+            Ok(out)
+        }
+        _ => Ok(content.to_string()),
+    }
+}
+
+pub fn translate_patch(patch: &str, translator: &SegmentTranslator) -> Result<String, String> {
+    let mut file = FileState::new("");
+    // Lines still expected in the current hunk (old side, new side)
+    let mut remaining = (0usize, 0usize);
+    let mut out = String::with_capacity(patch.len());
+
+    for raw_line in patch.split_inclusive('\n') {
+        let (line, ending) = match raw_line.strip_suffix("\r\n") {
+            Some(line) => (line, "\r\n"),
+            None => match raw_line.strip_suffix('\n') {
+                Some(line) => (line, "\n"),
+                None => (raw_line, ""),
+            },
+        };
+
+        let in_hunk = remaining != (0, 0);
+        if !in_hunk && line.starts_with("+++ ") {
+            file = FileState::new(header_path(line));
+        } else if !in_hunk && line.starts_with("@@") {
+            remaining = hunk_counts(line).unwrap_or((0, 0));
+        } else if in_hunk {
+            let translated = match line.chars().next() {
+                Some('+') => {
+                    remaining.1 = remaining.1.saturating_sub(1);
+                    Some(translate_line(&line[1..], &mut file.new, &file.kind, translator)?)
+                }
+                Some('-') => {
+                    remaining.0 = remaining.0.saturating_sub(1);
+                    Some(translate_line(&line[1..], &mut file.old, &file.kind, translator)?)
+                }
+                // "\ No newline at end of file" belongs to the hunk but isn't counted
+                Some('\\') => None,
+                _ => {
+                    remaining = (remaining.0.saturating_sub(1), remaining.1.saturating_sub(1));
+                    // Context lines are never translated but still advance both lexers.
+                    // Some tools strip the leading space of blank context lines.
+                    if let FileKind::Code(syntax) = file.kind {
+                        let content = line.get(1..).unwrap_or("");
+                        source_code::lex(content, syntax, &mut file.old);
+                        source_code::lex(content, syntax, &mut file.new);
+                    }
+                    None
+                }
+            };
+            if let Some(translated) = translated {
+                out.push_str(&line[..1]);
+                out.push_str(&translated);
+                out.push_str(ending);
+                continue;
+            }
+        }
+
+        out.push_str(line);
+        out.push_str(ending);
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub async fn translate_diff(
+    patch: String,
+    source_lang: String,
+    target_lang: String,
+    model_id: String,
+    state: State<'_, AppState>,
+    window: Window,
+) -> Result<String, String> {
+    // Waits for earlier jobs and resets the cancellation flag
+    let _turn = state.jobs.start(&state, &window, priority::JobKind::Interactive).await?;
+
+    let translator = SegmentTranslator {
+        source_lang: &source_lang,
+        target_lang: &target_lang,
+        model_id: &model_id,
+        state: &state,
+        window: &window,
+    };
+    let output = translate_patch(&patch, &translator)?;
+
+    crate::emit_result(&window, &state, &output, &target_lang)?;
+    Ok(output)
+}
@@ -0,0 +1,730 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use rdev::{simulate, EventType, Key};
+use tauri::image::Image;
+use tauri::{AppHandle, Emitter, Manager, Monitor, PhysicalSize, WebviewUrl, WebviewWindow, WebviewWindowBuilder, Window};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::clipboard_files::ClipboardFile;
+use crate::os_window::{self, WindowHandle, WindowRect};
+use crate::{chunk_size, db, history, lang_detect, profiles, settings, AppState};
+
+// Popup window behaviour. By default the popup opens above the mouse; in
+// follow-window mode it docks to the bottom-right corner of the window that was
+// focused when the hotkey fired and tracks it while it moves. Short results can
+// be shown as a toast that dismisses itself on a timer. A popup can be pinned;
+// lookups made while a pinned popup is still translating open in extra
+// "popup-N" windows, each with its own events and translation job. Lookups
+// made while an unpinned popup is translating wait in its queue instead of
+// replacing the text being translated. The popup is sized to the result and the
+// monitor's scale factor, unless the user resized it on that monitor before.
+
+// Popup sizes in logical pixels
+const DEFAULT_SIZE: PopupSize = PopupSize { width: 400.0, height: 300.0 };
+const MIN_SIZE: PopupSize = PopupSize { width: 240.0, height: 120.0 };
+const MAX_SIZE: PopupSize = PopupSize { width: 720.0, height: 640.0 };
+// Rough text metrics for sizing the popup to a result; CJK characters take two columns
+const COLUMN_WIDTH: f64 = 8.0;
+const LINE_HEIGHT: f64 = 22.0;
+// Space around the text: header, language bar, buttons and padding
+const CHROME_WIDTH: f64 = 48.0;
+const CHROME_HEIGHT: f64 = 120.0;
+// A resize is remembered once the user has stopped dragging for this long
+const RESIZE_SETTLE: Duration = Duration::from_millis(500);
+// Logical pixels a Resized event may differ from the size Spark set (rounding at
+// fractional scale factors) and still be taken for that size
+const RESIZE_TOLERANCE: f64 = 2.0;
+// Gap between a docked popup and the edges of the followed window
+const DOCK_MARGIN: i32 = 16;
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
+// Results up to this size can be shown as a compact toast instead of the full popup
+const TOAST_MAX_CHARS: usize = 40;
+const TOAST_MAX_WORDS: usize = 4;
+// Captures waiting in a busy popup; older ones are dropped beyond this
+const QUEUE_MAX: usize = 5;
+// Let the application finish the selection before copying it
+const SELECTION_SETTLE: Duration = Duration::from_millis(50);
+// Give the OS time to put the copied text on the clipboard
+const COPY_SETTLE: Duration = Duration::from_millis(150);
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PopupPlacement {
+    #[default]
+    Mouse,
+    FollowWindow,
+}
+
+// Per-window state for every popup, keyed by window label
+#[derive(Default)]
+struct PopupSlot {
+    // Bumped every time the popup is shown so older follow loops stop
+    follow_generation: u64,
+    // Bumped by every dismiss schedule/cancel so only the latest timer fires
+    dismiss_generation: u64,
+    // Application that was focused when the popup was last shown
+    source_window: Option<WindowHandle>,
+    // Set from the frontend; a pinned popup that is still translating is never reused
+    pinned: bool,
+    busy: bool,
+    // Capture for a freshly created popup whose page was not listening yet
+    pending_data: Option<PopupData>,
+    // Captures made while the popup was translating, shown one by one on request
+    queue: VecDeque<PopupData>,
+    // Docked to the source window rather than placed above the mouse
+    docked: bool,
+    // Logical size last set from here or saved from a drag. Resized events that
+    // match it (including the ones for a scale factor change when the popup moves
+    // to another monitor) aren't taken for the user's.
+    applied_size: Option<PopupSize>,
+    // Bumped by every user resize so only the last one of a drag is saved
+    resize_generation: u64,
+}
+
+/// Width and height of a popup in logical pixels.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PopupSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl PopupSize {
+    fn clamped(self) -> Self {
+        PopupSize {
+            width: self.width.clamp(MIN_SIZE.width, MAX_SIZE.width),
+            height: self.height.clamp(MIN_SIZE.height, MAX_SIZE.height),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CaptureSource {
+    // Copied by the user (Ctrl+C double tap, global shortcut)
+    Clipboard,
+    // Copied by Spark from the current selection (mouse gestures, selection button)
+    Selection,
+    // Files copied in Explorer; offered for file translation instead of translated
+    Files,
+}
+
+// Payload of `popup-data`: the captured text plus what the popup would otherwise
+// have to work out itself.
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PopupData {
+    text: String,
+    detected_lang: Option<&'static str>,
+    source: CaptureSource,
+    // Seconds since UNIX epoch
+    captured_at: i64,
+    // Suggested direction: the active profile's pair (English -> Japanese without
+    // one), swapped when the text is already in the target language
+    source_lang: String,
+    target_lang: String,
+    // Only for CaptureSource::Files; `text` then lists their names
+    files: Vec<ClipboardFile>,
+}
+
+impl PopupData {
+    fn new(state: &AppState, text: String, source: CaptureSource) -> Self {
+        let profile = profiles::active(state);
+        let mut source_lang = profile.as_ref().and_then(|p| p.source_lang.clone()).unwrap_or_else(|| "English".to_string());
+        let mut target_lang = profile.as_ref().and_then(|p| p.target_lang.clone()).unwrap_or_else(|| "Japanese".to_string());
+        let detected_lang = lang_detect::detect(&text);
+        if detected_lang.is_some_and(|lang| lang.eq_ignore_ascii_case(&target_lang)) {
+            std::mem::swap(&mut source_lang, &mut target_lang);
+        }
+        PopupData { text, detected_lang, source, captured_at: db::now(), source_lang, target_lang, files: Vec::new() }
+    }
+
+    fn for_files(state: &AppState, files: Vec<ClipboardFile>) -> Self {
+        let names = files.iter().map(|file| file.name()).collect::<Vec<_>>().join("\n");
+        let mut data = PopupData::new(state, String::new(), CaptureSource::Files);
+        data.text = names;
+        data.files = files;
+        data
+    }
+}
+
+static POPUPS: OnceLock<Mutex<HashMap<String, PopupSlot>>> = OnceLock::new();
+// Suffix for extra popup labels ("popup-1", "popup-2", ...)
+static NEXT_POPUP_ID: AtomicU64 = AtomicU64::new(1);
+
+fn with_slot<T>(label: &str, f: impl FnOnce(&mut PopupSlot) -> T) -> T {
+    let mut popups = POPUPS.get_or_init(Default::default).lock().unwrap();
+    f(popups.entry(label.to_string()).or_default())
+}
+
+/// The main "popup" window and any extra popups spawned next to it.
+pub fn is_popup(label: &str) -> bool {
+    label == "popup" || label.starts_with("popup-")
+}
+
+/// Marks a popup as translating until dropped. No-op for other windows.
+pub struct BusyGuard(Option<String>);
+
+impl BusyGuard {
+    pub fn new(label: &str) -> Self {
+        if !is_popup(label) {
+            return BusyGuard(None);
+        }
+        with_slot(label, |slot| slot.busy = true);
+        BusyGuard(Some(label.to_string()))
+    }
+}
+
+impl Drop for BusyGuard {
+    fn drop(&mut self) {
+        if let Some(label) = &self.0 {
+            with_slot(label, |slot| slot.busy = false);
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+struct SizeHint {
+    // "toast" for single words / short phrases, otherwise "full"
+    mode: &'static str,
+    chars: usize,
+    lines: usize,
+}
+
+fn size_hint(text: &str) -> SizeHint {
+    let trimmed = text.trim();
+    let chars = trimmed.chars().count();
+    let lines = trimmed.lines().count();
+    let short = lines <= 1 && chars <= TOAST_MAX_CHARS && trimmed.split_whitespace().count() <= TOAST_MAX_WORDS;
+    SizeHint { mode: if short { "toast" } else { "full" }, chars, lines }
+}
+
+/// Tells the window how large a finished result is, so the frontend can switch
+/// to a compact toast for short lookups. Sent on `result-size-hint-{label}`.
+/// A popup is also resized to fit the result.
+pub fn emit_result_size_hint(window: &Window, text: &str) {
+    let event_name = format!("result-size-hint-{}", window.label());
+    window.emit(&event_name, size_hint(text)).unwrap_or(());
+    if let Some(popup) = window.get_webview_window(window.label()).filter(|popup| is_popup(popup.label())) {
+        fit_to_result(&popup, text);
+    }
+}
+
+// Display columns of a line: CJK characters are about twice as wide as Latin ones
+fn columns(line: &str) -> usize {
+    line.chars().map(|c| if chunk_size::is_cjk(c) { 2 } else { 1 }).sum()
+}
+
+// Size that shows `text` without scrolling, within the minimum and maximum
+fn content_size(text: &str) -> PopupSize {
+    let longest = text.lines().map(columns).max().unwrap_or(0);
+    let width = (longest as f64 * COLUMN_WIDTH + CHROME_WIDTH).clamp(MIN_SIZE.width, MAX_SIZE.width);
+    let per_row = (((width - CHROME_WIDTH) / COLUMN_WIDTH) as usize).max(1);
+    let rows: usize = text.lines().map(|line| columns(line).div_ceil(per_row).max(1)).sum();
+    PopupSize { width, height: rows as f64 * LINE_HEIGHT + CHROME_HEIGHT }.clamped()
+}
+
+// Remembered sizes are keyed by monitor name, or position for unnamed monitors
+fn monitor_key(monitor: &Monitor) -> String {
+    match monitor.name() {
+        Some(name) => name.clone(),
+        None => format!("{},{}", monitor.position().x, monitor.position().y),
+    }
+}
+
+fn remembered_size(window: &WebviewWindow, monitor: &Monitor) -> Option<PopupSize> {
+    let state = window.state::<AppState>();
+    let settings = state.settings.lock().unwrap();
+    settings.popup_sizes.get(&monitor_key(monitor)).copied()
+}
+
+// Resizes the popup to the size the user picked on `monitor`, or else to
+// `preferred`, scaled for the monitor and no larger than it. Returns the new size.
+fn apply_size(window: &WebviewWindow, monitor: Option<&Monitor>, preferred: PopupSize) -> PhysicalSize<u32> {
+    let size = monitor.and_then(|monitor| remembered_size(window, monitor)).unwrap_or(preferred);
+    let scale = monitor.map(|monitor| monitor.scale_factor()).unwrap_or(1.0);
+    let mut physical = PhysicalSize::new((size.width * scale).round() as u32, (size.height * scale).round() as u32);
+    if let Some(monitor) = monitor {
+        physical.width = physical.width.min(monitor.size().width);
+        physical.height = physical.height.min(monitor.size().height);
+    }
+    let logical = PopupSize { width: physical.width as f64 / scale, height: physical.height as f64 / scale };
+    with_slot(window.label(), |slot| slot.applied_size = Some(logical));
+    let _ = window.set_size(tauri::Size::Physical(physical));
+    physical
+}
+
+fn window_size(window: &WebviewWindow) -> PhysicalSize<u32> {
+    window.outer_size().unwrap_or(PhysicalSize::new(DEFAULT_SIZE.width as u32, DEFAULT_SIZE.height as u32))
+}
+
+// Resizes a shown popup for its result, keeping its bottom edge and its centre
+// (or right edge when docked) in place so it doesn't grow over the mouse
+fn fit_to_result(window: &WebviewWindow, text: &str) {
+    let (Ok(position), Ok(monitor)) = (window.outer_position(), window.current_monitor()) else {
+        return;
+    };
+    let old = window_size(window);
+    let new = apply_size(window, monitor.as_ref(), content_size(text.trim()));
+    let (dw, dh) = (new.width as i32 - old.width as i32, new.height as i32 - old.height as i32);
+    let docked = with_slot(window.label(), |slot| slot.docked);
+    let x = if docked { position.x - dw } else { position.x - dw / 2 };
+    let y = position.y - dh;
+    let (x, y) = clamp_to_monitor(window, new, x, y, position.x + old.width as i32 / 2, position.y + old.height as i32 / 2);
+    move_to(window, x, y);
+}
+
+/// Called for every resize of a popup window. Sizes the user picks by dragging
+/// the popup's edges are saved for the monitor it is on once the drag ends.
+pub fn resized(window: &Window, size: PhysicalSize<u32>) {
+    // Minimized
+    if size.width == 0 || size.height == 0 {
+        return;
+    }
+    let scale = window.scale_factor().unwrap_or(1.0);
+    let logical = PopupSize { width: size.width as f64 / scale, height: size.height as f64 / scale };
+    let generation = with_slot(window.label(), |slot| {
+        // The events for a size set by apply_size, or a scale factor change
+        let unchanged = slot.applied_size.is_some_and(|applied| {
+            (applied.width - logical.width).abs() <= RESIZE_TOLERANCE && (applied.height - logical.height).abs() <= RESIZE_TOLERANCE
+        });
+        if unchanged {
+            return None;
+        }
+        slot.resize_generation += 1;
+        Some(slot.resize_generation)
+    });
+    let Some(generation) = generation else {
+        return;
+    };
+    let window = window.clone();
+    thread::spawn(move || {
+        thread::sleep(RESIZE_SETTLE);
+        if with_slot(window.label(), |slot| slot.resize_generation) != generation {
+            return;
+        }
+        if let Err(e) = remember_size(&window, size) {
+            eprintln!("Failed to save popup size: {}", e);
+        }
+    });
+}
+
+fn remember_size(window: &Window, size: PhysicalSize<u32>) -> Result<(), String> {
+    let Some(monitor) = window.current_monitor().map_err(|e| e.to_string())? else {
+        return Ok(());
+    };
+    let scale = monitor.scale_factor();
+    let logical = PopupSize { width: size.width as f64 / scale, height: size.height as f64 / scale }.clamped();
+    with_slot(window.label(), |slot| slot.applied_size = Some(logical));
+    let state = window.state::<AppState>();
+    let mut settings = state.settings.lock().unwrap();
+    settings.popup_sizes.insert(monitor_key(&monitor), logical);
+    settings::save(window.app_handle(), &settings)?;
+    // Keeps an open settings page from saving the old sizes back
+    window.app_handle().emit("settings-changed", &*settings).unwrap_or(());
+    Ok(())
+}
+
+// Clamps a popup of `size` to the monitor containing (anchor_x, anchor_y) to prevent overflow
+fn clamp_to_monitor(window: &WebviewWindow, size: PhysicalSize<u32>, x: i32, y: i32, anchor_x: i32, anchor_y: i32) -> (i32, i32) {
+    if let Ok(monitors) = window.available_monitors() {
+        for monitor in monitors {
+            let m_pos = monitor.position();
+            let m_size = monitor.size();
+
+            if anchor_x >= m_pos.x && anchor_x < m_pos.x + m_size.width as i32 &&
+               anchor_y >= m_pos.y && anchor_y < m_pos.y + m_size.height as i32 {
+                let max_x = m_pos.x + (m_size.width as i32 - size.width as i32).max(0);
+                let max_y = m_pos.y + (m_size.height as i32 - size.height as i32).max(0);
+                return (x.clamp(m_pos.x, max_x), y.clamp(m_pos.y, max_y));
+            }
+        }
+    }
+    (x, y)
+}
+
+fn move_to(window: &WebviewWindow, x: i32, y: i32) {
+    let _ = window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }));
+}
+
+// Centered above the mouse
+fn place_near_mouse(window: &WebviewWindow, size: PhysicalSize<u32>, mouse_x: f64, mouse_y: f64) {
+    let (mx, my) = (mouse_x as i32, mouse_y as i32);
+    let (w, h) = (size.width as i32, size.height as i32);
+    let (x, y) = clamp_to_monitor(window, size, mx - w / 2, my - h - 20, mx, my);
+    move_to(window, x, y);
+}
+
+// Inside the bottom-right corner of the followed window
+fn dock_to(window: &WebviewWindow, size: PhysicalSize<u32>, rect: WindowRect) {
+    let x = rect.x + rect.width - size.width as i32 - DOCK_MARGIN;
+    let y = rect.y + rect.height - size.height as i32 - DOCK_MARGIN;
+    let (x, y) = clamp_to_monitor(window, size, x, y, rect.x + rect.width / 2, rect.y + rect.height / 2);
+    move_to(window, x, y);
+}
+
+// Re-docks whenever the followed window moves or resizes, until the popup is
+// hidden, the window disappears, or the popup is shown again for a new capture.
+fn follow(window: WebviewWindow, target: WindowHandle, mut last_rect: WindowRect) {
+    let label = window.label().to_string();
+    let generation = with_slot(&label, |slot| slot.follow_generation);
+    thread::spawn(move || loop {
+        thread::sleep(FOLLOW_INTERVAL);
+        let current = with_slot(&label, |slot| slot.follow_generation);
+        if current != generation || !window.is_visible().unwrap_or(false) {
+            break;
+        }
+        match os_window::window_rect(target) {
+            Some(rect) if rect != last_rect => {
+                dock_to(&window, window_size(&window), rect);
+                last_rect = rect;
+            }
+            Some(_) => {}
+            None => break,
+        }
+    });
+}
+
+// Creates another popup window with the same look as the one in tauri.conf.json
+fn spawn_popup(app: &AppHandle) -> Option<WebviewWindow> {
+    let label = format!("popup-{}", NEXT_POPUP_ID.fetch_add(1, Ordering::SeqCst));
+    let builder = WebviewWindowBuilder::new(app, &label, WebviewUrl::App("/popup".into()))
+        .title("Spark Popup")
+        .inner_size(DEFAULT_SIZE.width, DEFAULT_SIZE.height)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false);
+    #[cfg(not(target_os = "macos"))]
+    let builder = builder.transparent(true);
+    match builder.build() {
+        Ok(window) => Some(window),
+        Err(e) => {
+            eprintln!("Failed to create popup window {}: {}", label, e);
+            None
+        }
+    }
+}
+
+// Picks the popup to show a new lookup in: an unpinned one if possible, then a
+// pinned one that has finished. Only when every popup is pinned and still
+// translating is a new window created. Returns the window and whether it is new.
+fn acquire_popup(app: &AppHandle) -> Option<(WebviewWindow, bool)> {
+    let mut candidates: Vec<(String, WebviewWindow)> =
+        app.webview_windows().into_iter().filter(|(label, _)| is_popup(label)).collect();
+    // "popup" sorts before "popup-N", so the main popup is preferred
+    candidates.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let (unpinned, pinned_idle) = {
+        let mut popups = POPUPS.get_or_init(Default::default).lock().unwrap();
+        let mut unpinned = None;
+        let mut pinned_idle = None;
+        for (label, window) in &candidates {
+            let slot = popups.entry(label.clone()).or_default();
+            if !slot.pinned {
+                unpinned.get_or_insert_with(|| window.clone());
+            } else if !slot.busy {
+                pinned_idle.get_or_insert_with(|| window.clone());
+            }
+        }
+        (unpinned, pinned_idle)
+    };
+    match unpinned.or(pinned_idle) {
+        Some(window) => Some((window, false)),
+        None => spawn_popup(app).map(|window| (window, true)),
+    }
+}
+
+// Sends the queued captures to the popup, on `popup-queue`
+fn emit_queue(window: &WebviewWindow, queue: &VecDeque<PopupData>) {
+    let _ = window.emit_to(window.label(), "popup-queue", queue.iter().collect::<Vec<_>>());
+}
+
+/// Positions a popup according to the placement setting, then shows it with `text`.
+/// `source_window` is the application that was focused when the hotkey fired.
+pub fn show_popup(
+    app: &AppHandle,
+    text: String,
+    source: CaptureSource,
+    mouse_x: f64,
+    mouse_y: f64,
+    source_window: Option<WindowHandle>,
+) {
+    let data = PopupData::new(&app.state::<AppState>(), text, source);
+    present(app, data, mouse_x, mouse_y, source_window);
+}
+
+/// Shows a popup offering to translate files copied to the clipboard.
+pub fn show_file_popup(app: &AppHandle, files: Vec<ClipboardFile>, mouse_x: f64, mouse_y: f64, source_window: Option<WindowHandle>) {
+    let data = PopupData::for_files(&app.state::<AppState>(), files);
+    present(app, data, mouse_x, mouse_y, source_window);
+}
+
+fn present(app: &AppHandle, data: PopupData, mouse_x: f64, mouse_y: f64, source_window: Option<WindowHandle>) {
+    let Some((window, created)) = acquire_popup(app) else {
+        return;
+    };
+    let label = window.label().to_string();
+    eprintln!("Showing popup {} with {} characters", label, data.text.chars().count());
+    let queued = !created
+        && with_slot(&label, |slot| {
+            if !slot.busy {
+                return false;
+            }
+            if !slot.queue.iter().any(|queued| queued.text == data.text) {
+                slot.queue.push_back(data.clone());
+                if slot.queue.len() > QUEUE_MAX {
+                    slot.queue.pop_front();
+                }
+            }
+            emit_queue(&window, &slot.queue);
+            true
+        });
+    if queued {
+        let _ = window.show();
+        return;
+    }
+    with_slot(&label, |slot| {
+        slot.follow_generation += 1;
+        slot.dismiss_generation += 1;
+        slot.source_window = source_window;
+        // A new page can't receive events until it has loaded; it asks for the data instead
+        if created {
+            slot.pending_data = Some(data.clone());
+        }
+    });
+
+    let placement = app.state::<AppState>().settings.lock().unwrap().popup_placement;
+    let followed = match (placement, source_window) {
+        (PopupPlacement::FollowWindow, Some(handle)) => os_window::window_rect(handle).map(|rect| (handle, rect)),
+        _ => None,
+    };
+    with_slot(&label, |slot| slot.docked = followed.is_some());
+    // The popup opens at the default (or remembered) size and fits the result once it is done
+    let (anchor_x, anchor_y) = match followed {
+        Some((_, rect)) => (rect.x + rect.width / 2, rect.y + rect.height / 2),
+        None => (mouse_x as i32, mouse_y as i32),
+    };
+    let monitor = window.monitor_from_point(anchor_x as f64, anchor_y as f64).ok().flatten();
+    let size = apply_size(&window, monitor.as_ref(), DEFAULT_SIZE);
+    match followed {
+        Some((_, rect)) => dock_to(&window, size, rect),
+        None => place_near_mouse(&window, size, mouse_x, mouse_y),
+    }
+
+    if !created {
+        let _ = window.emit_to(label.as_str(), "popup-data", data);
+    }
+    let _ = window.show();
+    let _ = window.set_focus();
+
+    if let Some((handle, rect)) = followed {
+        follow(window, handle, rect);
+    }
+}
+
+/// Returns the capture a newly created popup was opened with, once.
+#[tauri::command]
+pub async fn take_pending_popup_data(window: Window) -> Result<Option<PopupData>, String> {
+    Ok(with_slot(window.label(), |slot| slot.pending_data.take()))
+}
+
+/// Removes and returns the oldest queued capture; the popup then translates it.
+#[tauri::command]
+pub async fn take_next_popup_data(app: AppHandle, window: Window) -> Result<Option<PopupData>, String> {
+    let next = with_slot(window.label(), |slot| {
+        let next = slot.queue.pop_front();
+        if let Some(popup) = app.get_webview_window(window.label()) {
+            emit_queue(&popup, &slot.queue);
+        }
+        next
+    });
+    Ok(next)
+}
+
+/// Pinned popups keep their content: while one is translating, new lookups open
+/// in another popup window instead.
+#[tauri::command]
+pub async fn set_popup_pinned(pinned: bool, window: Window) -> Result<(), String> {
+    with_slot(window.label(), |slot| slot.pinned = pinned);
+    Ok(())
+}
+
+/// Hides the popup after `delay_ms` unless it is shown again, another dismiss is
+/// scheduled, or `cancel_popup_dismiss` is called first (e.g. on hover).
+#[tauri::command]
+pub async fn schedule_popup_dismiss(delay_ms: u64, window: Window) -> Result<(), String> {
+    let generation = with_slot(window.label(), |slot| {
+        slot.dismiss_generation += 1;
+        slot.dismiss_generation
+    });
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(delay_ms));
+        if with_slot(window.label(), |slot| slot.dismiss_generation) == generation {
+            let _ = window.hide();
+        }
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_popup_dismiss(window: Window) -> Result<(), String> {
+    with_slot(window.label(), |slot| slot.dismiss_generation += 1);
+    Ok(())
+}
+
+// Set while Spark sends keys itself, so the keyboard hook doesn't take its own
+// Ctrl+C for the user's (see run_key_listener)
+static SYNTHETIC_INPUT: AtomicBool = AtomicBool::new(false);
+
+pub fn is_synthetic_input() -> bool {
+    SYNTHETIC_INPUT.load(Ordering::SeqCst)
+}
+
+struct SyntheticInput;
+
+impl SyntheticInput {
+    fn begin() -> Self {
+        SYNTHETIC_INPUT.store(true, Ordering::SeqCst);
+        SyntheticInput
+    }
+}
+
+impl Drop for SyntheticInput {
+    fn drop(&mut self) {
+        SYNTHETIC_INPUT.store(false, Ordering::SeqCst);
+    }
+}
+
+// Sends Ctrl+`key` to whatever window has focus
+fn simulate_ctrl(key: Key, action: &str) -> Result<(), String> {
+    // Cleared after the last pause, by which time the hook has seen every event
+    let _synthetic = SyntheticInput::begin();
+    let keys = [
+        EventType::KeyPress(Key::ControlLeft),
+        EventType::KeyPress(key),
+        EventType::KeyRelease(key),
+        EventType::KeyRelease(Key::ControlLeft),
+    ];
+    for event in &keys {
+        simulate(event).map_err(|e| format!("Failed to simulate {}: {:?}", action, e))?;
+        // Windows drops events that arrive faster than the target can process them
+        thread::sleep(Duration::from_millis(20));
+    }
+    Ok(())
+}
+
+fn simulate_paste() -> Result<(), String> {
+    simulate_ctrl(Key::KeyV, "paste")
+}
+
+fn simulate_copy() -> Result<(), String> {
+    simulate_ctrl(Key::KeyC, "copy")
+}
+
+// What the clipboard held before Spark copied a selection itself. Text and
+// images can be put back; other formats (files, rich text) are lost.
+enum ClipboardSnapshot {
+    Text(String),
+    Image(Image<'static>),
+    Empty,
+}
+
+impl ClipboardSnapshot {
+    fn take(app: &AppHandle) -> Self {
+        let clipboard = app.clipboard();
+        if let Ok(text) = clipboard.read_text() {
+            return ClipboardSnapshot::Text(text);
+        }
+        match clipboard.read_image() {
+            Ok(image) => ClipboardSnapshot::Image(image.to_owned()),
+            Err(_) => ClipboardSnapshot::Empty,
+        }
+    }
+
+    fn restore(self, app: &AppHandle) {
+        let clipboard = app.clipboard();
+        let result = match &self {
+            ClipboardSnapshot::Text(text) => clipboard.write_text(text.as_str()),
+            ClipboardSnapshot::Image(image) => clipboard.write_image(image),
+            ClipboardSnapshot::Empty => clipboard.clear(),
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to restore clipboard: {}", e);
+        }
+    }
+}
+
+/// Copies the selection in the focused window with a simulated Ctrl+C and shows
+/// it in a popup at (x, y). The clipboard is cleared first, so an empty selection
+/// isn't mistaken for the old contents, and restored afterwards. Blocks for a few
+/// hundred milliseconds; for triggers without a Ctrl+C of their own.
+pub fn translate_selection(app: &AppHandle, x: f64, y: f64, source_window: Option<WindowHandle>) {
+    thread::sleep(SELECTION_SETTLE);
+    let snapshot = ClipboardSnapshot::take(app);
+    let _ = app.clipboard().clear();
+    let copied = simulate_copy();
+    if copied.is_ok() {
+        thread::sleep(COPY_SETTLE);
+    }
+    let selection = copied.and_then(|()| app.clipboard().read_text().map_err(|e| format!("Failed to read clipboard: {}", e)));
+    snapshot.restore(app);
+    match selection {
+        Ok(text) if !text.trim().is_empty() => {
+            let state = app.state::<AppState>();
+            if !history::is_incognito(&state) {
+                state.clipboard_history.record(&text);
+            }
+            show_popup(app, text, CaptureSource::Selection, x, y, source_window);
+        }
+        // Nothing was selected
+        Ok(_) => {}
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+/// Copies `text` to the clipboard and hides the popup.
+#[tauri::command]
+pub async fn popup_copy_and_close(text: String, app: AppHandle, window: Window) -> Result<(), String> {
+    app.clipboard().write_text(text).map_err(|e| e.to_string())?;
+    let _ = window.hide();
+    Ok(())
+}
+
+/// Returns focus to the application that was active before the popup opened.
+/// Called once per popup showing, when the popup is hidden.
+pub fn restore_source_focus(label: &str) {
+    let source_window = with_slot(label, |slot| slot.source_window.take());
+    if let Some(handle) = source_window {
+        os_window::focus_window(handle);
+    }
+}
+
+/// Drops the state of an extra popup once its window is gone.
+pub fn forget(label: &str) {
+    if let Some(popups) = POPUPS.get() {
+        popups.lock().unwrap().remove(label);
+    }
+}
+
+/// Copies `text`, hides the popup, returns focus to the application the text was
+/// captured from and pastes it there.
+#[tauri::command]
+pub async fn popup_paste_and_close(text: String, app: AppHandle, window: Window) -> Result<(), String> {
+    // Read before hiding; the hide itself triggers restore_source_focus
+    let source_window = with_slot(window.label(), |slot| slot.source_window);
+    app.clipboard().write_text(text).map_err(|e| e.to_string())?;
+    let _ = window.hide();
+
+    let Some(handle) = source_window else {
+        return Err("No previously focused window to paste into".to_string());
+    };
+    if !os_window::focus_window(handle) {
+        return Err("Could not return focus to the previous window".to_string());
+    }
+    // Let the focus change settle before sending keystrokes
+    thread::sleep(Duration::from_millis(80));
+    simulate_paste()
+}
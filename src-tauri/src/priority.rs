@@ -0,0 +1,103 @@
+use crate::AppState;
+
+// Thread priority for translation jobs: interactive lookups (popups, main
+// window) run above normal and long document/batch jobs below normal by
+// default. The priority applies to the thread running the job (prompt
+// building, sampling, detokenizing) and is restored when the job ends;
+// llama.cpp's compute threads keep their own priority. That lookups don't wait
+// for a background job is the job queue's doing, see jobs.rs.
+// Only implemented on Windows; elsewhere jobs keep the default priority.
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ThreadPriority {
+    BelowNormal,
+    Normal,
+    AboveNormal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum JobKind {
+    Interactive,
+    Background,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct JobPriorities {
+    pub interactive: ThreadPriority,
+    pub background: ThreadPriority,
+}
+
+impl Default for JobPriorities {
+    fn default() -> Self {
+        JobPriorities { interactive: ThreadPriority::AboveNormal, background: ThreadPriority::BelowNormal }
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::ThreadPriority;
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentThread, GetThreadPriority, SetThreadPriority, THREAD_PRIORITY_ABOVE_NORMAL,
+        THREAD_PRIORITY_BELOW_NORMAL, THREAD_PRIORITY_NORMAL,
+    };
+
+    // THREAD_PRIORITY_ERROR_RETURN, which lives in another windows-sys feature
+    const PRIORITY_ERROR: i32 = i32::MAX;
+
+    /// Sets the current thread's priority and returns the previous one.
+    pub fn set(priority: ThreadPriority) -> Option<i32> {
+        let value = match priority {
+            ThreadPriority::BelowNormal => THREAD_PRIORITY_BELOW_NORMAL,
+            ThreadPriority::Normal => THREAD_PRIORITY_NORMAL,
+            ThreadPriority::AboveNormal => THREAD_PRIORITY_ABOVE_NORMAL,
+        };
+        unsafe {
+            let thread = GetCurrentThread();
+            let previous = GetThreadPriority(thread);
+            if previous == PRIORITY_ERROR || SetThreadPriority(thread, value) == 0 {
+                return None;
+            }
+            Some(previous)
+        }
+    }
+
+    pub fn restore(previous: i32) {
+        unsafe { SetThreadPriority(GetCurrentThread(), previous) };
+    }
+}
+
+#[cfg(not(windows))]
+mod imp {
+    use super::ThreadPriority;
+
+    pub fn set(_priority: ThreadPriority) -> Option<i32> {
+        None
+    }
+
+    pub fn restore(_previous: i32) {}
+}
+
+/// Holds the configured priority for a job on the current thread until dropped.
+/// Must be dropped on the thread that created it.
+pub struct PriorityGuard(Option<i32>);
+
+impl PriorityGuard {
+    pub fn new(state: &AppState, kind: JobKind) -> Self {
+        let priorities = state.settings.lock().unwrap().job_priority;
+        let priority = match kind {
+            JobKind::Interactive => priorities.interactive,
+            JobKind::Background => priorities.background,
+        };
+        PriorityGuard(imp::set(priority))
+    }
+}
+
+impl Drop for PriorityGuard {
+    fn drop(&mut self) {
+        if let Some(saved) = self.0.take() {
+            imp::restore(saved);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+use tauri::{Manager, Window};
+
+use crate::word_diff;
+
+// Profanity post-processing for output shown in professional or classroom settings.
+// Word lists are keyed by language name; each word maps to a softer replacement, or
+// null when it should always be masked. Users can extend or override the bundled
+// list with `profanity.json` (same shape) in the app config directory. The merged
+// list of a language is cached until that file changes.
+
+const BUNDLED_WORDLIST_JSON: &str = include_str!("../data/profanity.json");
+const USER_WORDLIST_FILE: &str = "profanity.json";
+
+type Wordlists = HashMap<String, HashMap<String, Option<String>>>;
+
+struct CachedWordlist {
+    // Modification time of the user's file when the list was built; None without one
+    user_modified: Option<SystemTime>,
+    words: Arc<HashMap<String, Option<String>>>,
+}
+
+static WORDLISTS: OnceLock<Mutex<HashMap<String, CachedWordlist>>> = OnceLock::new();
+
+fn with_cache<T>(f: impl FnOnce(&mut HashMap<String, CachedWordlist>) -> T) -> T {
+    f(&mut WORDLISTS.get_or_init(Default::default).lock().unwrap())
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfanityFilter {
+    #[default]
+    Off,
+    // "f***"
+    Mask,
+    // Replace with the listed softer word, masking when there is none
+    Soften,
+}
+
+/// Bundled list merged with the user's overrides for `language`.
+fn load_wordlist(user_file: Option<&Path>, language: &str) -> HashMap<String, Option<String>> {
+    let mut lists: Wordlists = serde_json::from_str(BUNDLED_WORDLIST_JSON).unwrap_or_default();
+
+    if let Some(raw) = user_file.and_then(|path| std::fs::read_to_string(path).ok()) {
+        match serde_json::from_str::<Wordlists>(&raw) {
+            Ok(user) => {
+                for (lang, words) in user {
+                    lists.entry(lang).or_default().extend(words);
+                }
+            }
+            Err(e) => eprintln!("Ignoring invalid {}: {}", USER_WORDLIST_FILE, e),
+        }
+    }
+
+    lists
+        .into_iter()
+        .find(|(lang, _)| lang.eq_ignore_ascii_case(language))
+        .map(|(_, words)| words.into_iter().map(|(word, soft)| (word.to_lowercase(), soft)).collect())
+        .unwrap_or_default()
+}
+
+/// `load_wordlist`, cached per language while the user's file is unchanged.
+fn wordlist(window: &Window, language: &str) -> Arc<HashMap<String, Option<String>>> {
+    let user_file = window.path().app_config_dir().ok().map(|dir| dir.join(USER_WORDLIST_FILE));
+    let user_modified = user_file.as_ref().and_then(|path| std::fs::metadata(path).and_then(|meta| meta.modified()).ok());
+    let key = language.trim().to_lowercase();
+    let cached = with_cache(|cache| {
+        cache.get(&key).filter(|cached| cached.user_modified == user_modified).map(|cached| cached.words.clone())
+    });
+    if let Some(words) = cached {
+        return words;
+    }
+    let words = Arc::new(load_wordlist(user_file.as_deref(), &key));
+    with_cache(|cache| cache.insert(key, CachedWordlist { user_modified, words: words.clone() }));
+    words
+}
+
+fn mask(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => std::iter::once(first).chain(chars.map(|_| '*')).collect(),
+        None => String::new(),
+    }
+}
+
+// Keeps "Damn" -> "Darn" capitalized like the original
+fn match_case(original: &str, replacement: &str) -> String {
+    let starts_upper = original.chars().next().is_some_and(|c| c.is_uppercase());
+    let mut chars = replacement.chars();
+    match chars.next() {
+        Some(first) if starts_upper => first.to_uppercase().chain(chars).collect(),
+        _ => replacement.to_string(),
+    }
+}
+
+fn replacement(word: &str, soft: Option<&str>, filter: ProfanityFilter) -> String {
+    match (filter, soft) {
+        (ProfanityFilter::Soften, Some(soft)) => match_case(word, soft),
+        _ => mask(word),
+    }
+}
+
+/// Applies the wordlist to `text`. Words in spaced scripts are matched whole and
+/// case-insensitively; CJK entries are matched as substrings.
+pub fn filter_text(text: &str, words: &HashMap<String, Option<String>>, filter: ProfanityFilter) -> String {
+    if filter == ProfanityFilter::Off || words.is_empty() {
+        return text.to_string();
+    }
+
+    let (unspaced, spaced): (Vec<_>, Vec<_>) = words
+        .iter()
+        .partition(|(word, _)| word.chars().any(word_diff::is_unspaced_script));
+
+    // Whole-word pass over alphanumeric runs
+    let mut out = String::with_capacity(text.len());
+    let mut current = String::new();
+    let flush = |current: &mut String, out: &mut String| {
+        let lower = current.to_lowercase();
+        match spaced.iter().find(|(word, _)| **word == lower) {
+            Some((_, soft)) => out.push_str(&replacement(current, soft.as_deref(), filter)),
+            None => out.push_str(current),
+        }
+        current.clear();
+    };
+    for c in text.chars() {
+        if c.is_alphanumeric() && !word_diff::is_unspaced_script(c) {
+            current.push(c);
+        } else {
+            flush(&mut current, &mut out);
+            out.push(c);
+        }
+    }
+    flush(&mut current, &mut out);
+
+    // Substring pass for CJK, longest entries first so "ぶっ殺す" wins over shorter matches
+    let mut unspaced = unspaced;
+    unspaced.sort_by_key(|(word, _)| std::cmp::Reverse(word.chars().count()));
+    for (word, soft) in unspaced {
+        if out.contains(word.as_str()) {
+            out = out.replace(word.as_str(), &replacement(word, soft.as_deref(), filter));
+        }
+    }
+    out
+}
+
+/// Filters translated output for `language` according to `filter`.
+pub fn apply(window: &Window, text: &str, language: &str, filter: ProfanityFilter) -> String {
+    if filter == ProfanityFilter::Off {
+        return text.to_string();
+    }
+    filter_text(text, &wordlist(window, language), filter)
+}
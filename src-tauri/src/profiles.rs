@@ -0,0 +1,124 @@
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{settings, AppState};
+
+// Named settings profiles (e.g. "Work": formal, English target; "Home": casual,
+// Japanese target). A profile bundles the defaults the frontend picks up when it
+// is switched on (model, languages), the project whose glossary and translation
+// memory apply, a tone/style note for the prompt, and the hotkey timing.
+// Profiles live in settings.json; none active means the plain settings.
+
+const DEFAULT_DOUBLE_TAP: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Profile {
+    pub name: String,
+    pub model_id: Option<String>,
+    pub source_lang: Option<String>,
+    pub target_lang: Option<String>,
+    // Glossary/TM scope when no project is open, see projects::scope
+    pub project_id: Option<i64>,
+    // Appended to the translation system prompt, e.g. "Use a formal, polite tone."
+    pub prompt_note: String,
+    // Max gap between the two Ctrl+C presses of the popup hotkey
+    pub double_tap_ms: Option<u64>,
+}
+
+/// The active profile, if any.
+pub fn active(state: &AppState) -> Option<Profile> {
+    let settings = state.settings.lock().unwrap();
+    let name = settings.active_profile.as_deref()?;
+    settings.profiles.iter().find(|p| p.name == name).cloned()
+}
+
+/// Prompt addition of the active profile, empty without one.
+pub fn instruction(state: &AppState) -> String {
+    match active(state) {
+        Some(profile) if !profile.prompt_note.trim().is_empty() => format!(" {}", profile.prompt_note.trim()),
+        _ => String::new(),
+    }
+}
+
+pub fn double_tap_window(app: &AppHandle) -> Duration {
+    active(&app.state::<AppState>())
+        .and_then(|p| p.double_tap_ms)
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_DOUBLE_TAP)
+}
+
+// Persists changed settings and tells every window and the tray about them
+fn store(app: &AppHandle, update: impl FnOnce(&mut settings::Settings) -> Result<(), String>) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let updated = {
+        let mut settings = state.settings.lock().unwrap();
+        let mut updated = settings.clone();
+        update(&mut updated)?;
+        settings::save(app, &updated)?;
+        *settings = updated.clone();
+        updated
+    };
+    app.emit("settings-changed", &updated).unwrap_or(());
+    crate::tray::set_profiles(&updated.profiles, updated.active_profile.as_deref());
+    Ok(())
+}
+
+/// Switches to profile `name`, or back to the plain settings with None.
+/// Emits `profile-changed` with the new profile so windows can apply its defaults.
+pub fn switch(app: &AppHandle, name: Option<String>) -> Result<(), String> {
+    store(app, |settings| {
+        if let Some(name) = &name {
+            if !settings.profiles.iter().any(|p| &p.name == name) {
+                return Err(format!("Profile '{}' not found", name));
+            }
+        }
+        settings.active_profile = name;
+        Ok(())
+    })?;
+    app.emit("profile-changed", active(&app.state::<AppState>())).unwrap_or(());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_profiles(state: State<'_, AppState>) -> Result<Vec<Profile>, String> {
+    Ok(state.settings.lock().unwrap().profiles.clone())
+}
+
+/// Adds a profile or replaces the one with the same name.
+#[tauri::command]
+pub async fn save_profile(profile: Profile, app: AppHandle) -> Result<(), String> {
+    let name = profile.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Profile name is empty".to_string());
+    }
+    store(&app, |settings| {
+        let profile = Profile { name: name.clone(), ..profile };
+        match settings.profiles.iter_mut().find(|p| p.name == name) {
+            Some(existing) => *existing = profile,
+            None => settings.profiles.push(profile),
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn delete_profile(name: String, app: AppHandle) -> Result<(), String> {
+    let was_active = active(&app.state::<AppState>()).is_some_and(|p| p.name == name);
+    store(&app, |settings| {
+        settings.profiles.retain(|p| p.name != name);
+        if settings.active_profile.as_deref() == Some(name.as_str()) {
+            settings.active_profile = None;
+        }
+        Ok(())
+    })?;
+    if was_active {
+        app.emit("profile-changed", None::<Profile>).unwrap_or(());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn switch_profile(name: Option<String>, app: AppHandle) -> Result<(), String> {
+    switch(&app, name)
+}
@@ -0,0 +1,261 @@
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use rusqlite::{params, Connection, OptionalExtension};
+use tauri::{Emitter, State, Window};
+
+use crate::documents::{self, FileJob};
+use crate::export::{self, OutputTemplate};
+use crate::{db, priority, profiles, segments, AppState};
+
+// Translation projects: a named set of files with a fixed language pair whose
+// progress is kept in the database, so multi-file jobs can be resumed across
+// sessions. Opening a project makes it the active one until it is closed;
+// translating a project always uses that project's glossary, TM and examples.
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileStatus {
+    Pending,
+    Done,
+    Failed,
+}
+
+impl FileStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            FileStatus::Pending => "pending",
+            FileStatus::Done => "done",
+            FileStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "done" => FileStatus::Done,
+            "failed" => FileStatus::Failed,
+            _ => FileStatus::Pending,
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSummary {
+    id: i64,
+    name: String,
+    source_lang: String,
+    target_lang: String,
+    file_count: usize,
+    done_count: usize,
+    created_at: i64,
+    opened_at: i64,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectFile {
+    id: i64,
+    path: String,
+    output_path: Option<String>,
+    status: FileStatus,
+    error: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    #[serde(flatten)]
+    summary: ProjectSummary,
+    files: Vec<ProjectFile>,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectProgress {
+    project_id: i64,
+    done: usize,
+    total: usize,
+}
+
+const SUMMARY_QUERY: &str = "SELECT p.id, p.name, p.source_lang, p.target_lang, p.created_at, p.opened_at,
+        COUNT(f.id), COUNT(CASE WHEN f.status = 'done' THEN 1 END)
+    FROM projects p LEFT JOIN project_files f ON f.project_id = p.id";
+
+fn summary_from_row(row: &rusqlite::Row) -> rusqlite::Result<ProjectSummary> {
+    Ok(ProjectSummary {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        source_lang: row.get(2)?,
+        target_lang: row.get(3)?,
+        created_at: row.get(4)?,
+        opened_at: row.get(5)?,
+        file_count: row.get(6)?,
+        done_count: row.get(7)?,
+    })
+}
+
+fn load_project(conn: &Connection, id: i64) -> rusqlite::Result<Option<Project>> {
+    let summary = conn
+        .query_row(&format!("{} WHERE p.id = ?1 GROUP BY p.id", SUMMARY_QUERY), [id], summary_from_row)
+        .optional()?;
+    let Some(summary) = summary else {
+        return Ok(None);
+    };
+    let mut stmt = conn.prepare("SELECT id, path, output_path, status, error FROM project_files WHERE project_id = ?1 ORDER BY path")?;
+    let files = stmt
+        .query_map([id], |row| {
+            Ok(ProjectFile {
+                id: row.get(0)?,
+                path: row.get(1)?,
+                output_path: row.get(2)?,
+                status: FileStatus::parse(&row.get::<_, String>(3)?),
+                error: row.get(4)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(Some(Project { summary, files }))
+}
+
+fn insert_files(conn: &Connection, project_id: i64, files: &[String]) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare("INSERT OR IGNORE INTO project_files (project_id, path) VALUES (?1, ?2)")?;
+    for path in files {
+        stmt.execute(params![project_id, path])?;
+    }
+    Ok(())
+}
+
+/// Project a glossary/TM operation applies to: the given one, else the active one.
+/// None means the global scope.
+pub fn scope(state: &AppState, project_id: Option<i64>) -> Option<i64> {
+    project_id
+        .or(*state.active_project.lock().unwrap())
+        .or_else(|| profiles::active(state).and_then(|p| p.project_id))
+}
+
+fn get_project(state: &AppState, id: i64) -> Result<Project, String> {
+    state.db.with(|conn| load_project(conn, id))?.ok_or_else(|| format!("Project {} not found", id))
+}
+
+#[tauri::command]
+pub async fn create_project(
+    name: String,
+    source_lang: String,
+    target_lang: String,
+    files: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<Project, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Project name is empty".to_string());
+    }
+    let id = state.db.with(|conn| {
+        let tx = conn.transaction()?;
+        let now = db::now();
+        tx.execute(
+            "INSERT INTO projects (name, source_lang, target_lang, created_at, opened_at) VALUES (?1, ?2, ?3, ?4, ?4)",
+            params![name, source_lang, target_lang, now],
+        )?;
+        let id = tx.last_insert_rowid();
+        insert_files(&tx, id, files.as_deref().unwrap_or_default())?;
+        tx.commit()?;
+        Ok(id)
+    })?;
+    *state.active_project.lock().unwrap() = Some(id);
+    get_project(&state, id)
+}
+
+/// Loads a project and makes it the active one.
+#[tauri::command]
+pub async fn open_project(id: i64, state: State<'_, AppState>) -> Result<Project, String> {
+    state.db.with(|conn| conn.execute("UPDATE projects SET opened_at = ?1 WHERE id = ?2", params![db::now(), id]))?;
+    let project = get_project(&state, id)?;
+    *state.active_project.lock().unwrap() = Some(id);
+    Ok(project)
+}
+
+/// Clears the active project, so glossary/TM operations fall back to the
+/// profile's project or the global scope.
+#[tauri::command]
+pub async fn close_project(state: State<'_, AppState>) -> Result<(), String> {
+    *state.active_project.lock().unwrap() = None;
+    Ok(())
+}
+
+/// All projects, most recently opened first.
+#[tauri::command]
+pub async fn list_projects(state: State<'_, AppState>) -> Result<Vec<ProjectSummary>, String> {
+    state.db.with(|conn| {
+        let mut stmt = conn.prepare(&format!("{} GROUP BY p.id ORDER BY p.opened_at DESC", SUMMARY_QUERY))?;
+        let projects = stmt.query_map([], summary_from_row)?.collect();
+        projects
+    })
+}
+
+#[tauri::command]
+pub async fn add_project_files(id: i64, files: Vec<String>, state: State<'_, AppState>) -> Result<Project, String> {
+    get_project(&state, id)?;
+    state.db.with(|conn| insert_files(conn, id, &files))?;
+    get_project(&state, id)
+}
+
+/// Translates the project's files that aren't done yet, recording each result,
+/// and emits `project-progress` after every file. Stops early when cancelled;
+/// calling it again resumes with the remaining files.
+#[tauri::command]
+pub async fn translate_project(
+    id: i64,
+    model_id: String,
+    template: Option<OutputTemplate>,
+    file_name: Option<String>,
+    state: State<'_, AppState>,
+    window: Window,
+) -> Result<Project, String> {
+    let project = get_project(&state, id)?;
+    let summary = &project.summary;
+    let template = template.unwrap_or_default();
+    let pattern = file_name.as_deref().unwrap_or(export::DEFAULT_FILE_NAME);
+
+    // Waits for earlier jobs and resets the cancellation flag
+    let _turn = state.jobs.start(&state, &window, priority::JobKind::Background).await?;
+
+    let mut done = summary.done_count;
+    for file in project.files.iter().filter(|f| f.status != FileStatus::Done) {
+        if state.is_cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        let input = PathBuf::from(&file.path);
+        let output = export::output_path(pattern, &input, &summary.source_lang, &summary.target_lang, template);
+        // Each chunk becomes a reviewable segment; a retranslation starts the review over
+        segments::clear_file(&state, file.id)?;
+        let record = |position: usize, source: &str, target: &str, separator: &str| {
+            segments::record(&state, id, file.id, position, source, target, separator)
+        };
+        let job = FileJob {
+            source_lang: &summary.source_lang,
+            target_lang: &summary.target_lang,
+            model_id: &model_id,
+            include_summary: false,
+            input_cleanup: false,
+            template,
+            on_segment: Some(&record),
+            project_id: Some(id),
+        };
+        let (status, error) = match documents::translate_file(&job, &input, &output, &state, &window) {
+            Ok(()) => (FileStatus::Done, None),
+            Err(_) if state.is_cancelled.load(Ordering::Relaxed) => (FileStatus::Pending, None),
+            Err(e) => (FileStatus::Failed, Some(e)),
+        };
+        state.db.with(|conn| {
+            conn.execute(
+                "UPDATE project_files SET status = ?1, output_path = ?2, error = ?3 WHERE id = ?4",
+                params![status.as_str(), output.to_string_lossy(), error, file.id],
+            )
+        })?;
+        if status == FileStatus::Done {
+            done += 1;
+        }
+        let progress = ProjectProgress { project_id: id, done, total: summary.file_count };
+        window.emit("project-progress", progress).unwrap_or(());
+    }
+    get_project(&state, id)
+}
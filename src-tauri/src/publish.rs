@@ -0,0 +1,202 @@
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+use crate::{history, AppState};
+
+// Publishes job status and finished translations for kiosk and digital-signage
+// setups, as one JSON object per message: to a topic on a local MQTT broker
+// and/or to a named pipe (`\\.\pipe\name` on Windows, a FIFO elsewhere) that
+// the consumer has created. MQTT is spoken directly (3.1.1, QoS 0, retained so
+// a display that connects later still gets the latest message); only CONNECT,
+// PUBLISH and the CONNACK are needed. Each target has its own thread and a
+// short queue: when a broker is down or nobody reads the pipe, messages are
+// dropped instead of holding up translations. Nothing is published in
+// incognito mode.
+
+const QUEUE_LEN: usize = 32;
+const DEFAULT_MQTT_PORT: u16 = 1883;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const MQTT_CLIENT_ID: &str = "spark";
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PublishSettings {
+    pub mqtt: Option<MqttTarget>,
+    pub pipe: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttTarget {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    pub topic: String,
+}
+
+fn default_port() -> u16 {
+    DEFAULT_MQTT_PORT
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum Message<'a> {
+    #[serde(rename_all = "camelCase")]
+    Started { source_lang: &'a str, target_lang: &'a str, model_id: &'a str },
+    #[serde(rename_all = "camelCase")]
+    Completed {
+        source_lang: &'a str,
+        target_lang: &'a str,
+        model_id: &'a str,
+        text: &'a str,
+        // Cut short by cancellation
+        partial: bool,
+    },
+    Failed { error: &'a str },
+}
+
+static MQTT_QUEUE: OnceLock<SyncSender<(MqttTarget, String)>> = OnceLock::new();
+static PIPE_QUEUE: OnceLock<SyncSender<(String, String)>> = OnceLock::new();
+
+fn push_length(packet: &mut Vec<u8>, mut len: usize) {
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+}
+
+// Strings are prefixed with a 16-bit length; anything longer can't be sent
+fn push_string(body: &mut Vec<u8>, value: &str) -> std::io::Result<()> {
+    let len = u16::try_from(value.len()).map_err(|_| {
+        std::io::Error::new(ErrorKind::InvalidInput, format!("MQTT string of {} bytes exceeds 65535", value.len()))
+    })?;
+    body.extend_from_slice(&len.to_be_bytes());
+    body.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    push_length(&mut packet, body.len());
+    packet.extend_from_slice(body);
+    packet
+}
+
+fn mqtt_connect(target: &MqttTarget) -> std::io::Result<TcpStream> {
+    let address = std::net::ToSocketAddrs::to_socket_addrs(&(target.host.as_str(), target.port))?
+        .next()
+        .ok_or_else(|| std::io::Error::other(format!("Unknown host {}", target.host)))?;
+    let mut stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?;
+    let mut body = Vec::new();
+    push_string(&mut body, "MQTT")?;
+    // Protocol level 4 (3.1.1), clean session, no keep-alive
+    body.extend_from_slice(&[4, 0x02, 0, 0]);
+    push_string(&mut body, MQTT_CLIENT_ID)?;
+    stream.write_all(&packet(0x10, &body))?;
+    let mut connack = [0u8; 4];
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    stream.read_exact(&mut connack)?;
+    if connack[0] != 0x20 || connack[3] != 0 {
+        return Err(std::io::Error::other(format!("Broker refused the connection (code {})", connack[3])));
+    }
+    Ok(stream)
+}
+
+// PUBLISH, QoS 0 and retained
+fn mqtt_publish(stream: &mut TcpStream, topic: &str, payload: &str) -> std::io::Result<()> {
+    let mut body = Vec::new();
+    push_string(&mut body, topic)?;
+    body.extend_from_slice(payload.as_bytes());
+    stream.write_all(&packet(0x31, &body))
+}
+
+// Keeps one connection, reconnecting when the target changes or a write fails
+fn mqtt_worker(queue: Receiver<(MqttTarget, String)>) {
+    let mut connection: Option<(MqttTarget, TcpStream)> = None;
+    for (target, payload) in queue {
+        if connection.as_ref().is_some_and(|(connected, _)| *connected != target) {
+            connection = None;
+        }
+        let mut sent = false;
+        // A connection the broker has since closed only fails on the write
+        for _ in 0..2 {
+            if connection.is_none() {
+                match mqtt_connect(&target) {
+                    Ok(stream) => connection = Some((target.clone(), stream)),
+                    Err(e) => {
+                        eprintln!("Failed to connect to MQTT broker {}:{}: {}", target.host, target.port, e);
+                        break;
+                    }
+                }
+            }
+            if let Some((_, stream)) = connection.as_mut() {
+                match mqtt_publish(stream, &target.topic, &payload) {
+                    Ok(()) => {
+                        sent = true;
+                        break;
+                    }
+                    // Nothing was written; the connection is fine but the topic can't be sent
+                    Err(e) if e.kind() == ErrorKind::InvalidInput => {
+                        eprintln!("Invalid MQTT topic: {}", e);
+                        break;
+                    }
+                    Err(_) => connection = None,
+                }
+            }
+        }
+        if !sent {
+            eprintln!("Dropped MQTT message for {}", target.topic);
+        }
+    }
+}
+
+// Opens the pipe per message, so a consumer can restart. Opening a FIFO waits
+// for a reader, which is why this has its own thread.
+fn pipe_worker(queue: Receiver<(String, String)>) {
+    for (path, payload) in queue {
+        let result = OpenOptions::new().append(true).open(&path).and_then(|mut pipe| writeln!(pipe, "{}", payload));
+        if let Err(e) = result {
+            eprintln!("Failed to write to pipe {}: {}", path, e);
+        }
+    }
+}
+
+fn enqueue<T: Send + 'static>(queue: &'static OnceLock<SyncSender<T>>, worker: fn(Receiver<T>), item: T) {
+    let sender = queue.get_or_init(|| {
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_LEN);
+        thread::spawn(move || worker(receiver));
+        sender
+    });
+    if let Err(TrySendError::Full(_)) = sender.try_send(item) {
+        eprintln!("Publish queue full; message dropped");
+    }
+}
+
+/// Sends `message` to the configured targets, unless incognito.
+pub fn publish(state: &AppState, message: Message) {
+    let settings = state.settings.lock().unwrap().publish.clone();
+    if (settings.mqtt.is_none() && settings.pipe.is_none()) || history::is_incognito(state) {
+        return;
+    }
+    let Ok(payload) = serde_json::to_string(&message) else {
+        return;
+    };
+    if let Some(target) = settings.mqtt {
+        enqueue(&MQTT_QUEUE, mqtt_worker, (target, payload.clone()));
+    }
+    if let Some(path) = settings.pipe {
+        enqueue(&PIPE_QUEUE, pipe_worker, (path, payload));
+    }
+}
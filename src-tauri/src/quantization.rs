@@ -0,0 +1,147 @@
+use sysinfo::System;
+use tauri::State;
+
+use crate::AppState;
+
+// Picks the quantization variant (q2_k/q4_k_m/q8_0) of a model to download from
+// the machine's memory and the user's speed/quality preference, with the
+// reasoning spelled out for the download dialog. llama.cpp runs on the CPU here
+// (or on unified memory with Metal), so system RAM is the budget that matters.
+
+const MIB: u64 = 1024 * 1024;
+// Context, compute buffers and the app itself on top of the weights
+const RUNTIME_OVERHEAD: u64 = 512 * MIB;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QuantPreference {
+    // Smallest variant: least memory and fastest generation
+    Speed,
+    #[default]
+    Balanced,
+    // Largest variant that fits
+    Quality,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub enum Quantization {
+    #[serde(rename = "q2_k")]
+    Q2K,
+    #[serde(rename = "q4_k_m")]
+    Q4KM,
+    #[serde(rename = "q8_0")]
+    Q8_0,
+}
+
+impl Quantization {
+    fn suffix(self) -> &'static str {
+        match self {
+            Quantization::Q2K => "q2_k",
+            Quantization::Q4KM => "q4_k_m",
+            Quantization::Q8_0 => "q8_0",
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuantRecommendation {
+    quantization: Quantization,
+    file_name: String,
+    estimated_bytes: u64,
+    // Memory the model may use on this machine
+    budget_bytes: u64,
+    // Why this variant was chosen, for the user
+    reason: String,
+}
+
+// Base model size of a model id and approximate GGUF sizes in MiB for
+// q2_k, q4_k_m and q8_0
+fn model_size(model_id: &str) -> (&'static str, [u64; 3]) {
+    match model_id {
+        "balanced" => ("1.5b", [676, 986, 1650]),
+        "high" => ("3b", [1270, 1930, 3290]),
+        _ => ("0.5b", [339, 398, 531]),
+    }
+}
+
+fn file_size(sizes: [u64; 3], quant: Quantization) -> u64 {
+    let index = match quant {
+        Quantization::Q2K => 0,
+        Quantization::Q4KM => 1,
+        Quantization::Q8_0 => 2,
+    };
+    sizes[index] * MIB
+}
+
+// Variants to try in order of preference
+fn candidates(preference: QuantPreference) -> &'static [Quantization] {
+    match preference {
+        QuantPreference::Speed => &[Quantization::Q2K],
+        QuantPreference::Balanced => &[Quantization::Q4KM, Quantization::Q2K],
+        QuantPreference::Quality => &[Quantization::Q8_0, Quantization::Q4KM, Quantization::Q2K],
+    }
+}
+
+/// Chooses a variant of `model_id` for a machine with `total_memory` bytes of RAM.
+pub fn recommend(model_id: &str, preference: QuantPreference, total_memory: u64) -> QuantRecommendation {
+    let (size, sizes) = model_size(model_id);
+    // Leave half the memory to the OS and the user's other applications
+    let budget = total_memory / 2;
+    let wanted = candidates(preference)[0];
+    let fits = |quant: Quantization| file_size(sizes, quant) + RUNTIME_OVERHEAD <= budget;
+    let chosen = candidates(preference).iter().copied().find(|&quant| fits(quant));
+
+    let (quantization, reason) = match chosen {
+        Some(quant) if quant == wanted => {
+            let why = match preference {
+                QuantPreference::Speed => "the fastest, smallest variant (preference: speed)",
+                QuantPreference::Balanced => "the usual balance of speed and quality",
+                QuantPreference::Quality => "the highest-quality variant (preference: quality)",
+            };
+            (quant, format!("{} fits in the {} MiB available to models: {}", quant.suffix(), budget / MIB, why))
+        }
+        Some(quant) => (
+            quant,
+            format!(
+                "{} would need about {} MiB but only {} MiB is available to models, so the smaller {} was chosen",
+                wanted.suffix(),
+                (file_size(sizes, wanted) + RUNTIME_OVERHEAD) / MIB,
+                budget / MIB,
+                quant.suffix()
+            ),
+        ),
+        None => (
+            Quantization::Q2K,
+            format!(
+                "Even q2_k (about {} MiB) exceeds the {} MiB available to models; expect swapping, or choose a smaller model",
+                (file_size(sizes, Quantization::Q2K) + RUNTIME_OVERHEAD) / MIB,
+                budget / MIB
+            ),
+        ),
+    };
+    QuantRecommendation {
+        quantization,
+        file_name: format!("qwen2.5-{}-instruct-{}.gguf", size, quantization.suffix()),
+        estimated_bytes: file_size(sizes, quantization),
+        budget_bytes: budget,
+        reason,
+    }
+}
+
+/// The variant of `model_id` to download on this machine, using the preference
+/// from the settings unless one is given.
+#[tauri::command]
+pub async fn recommend_quantization(
+    model_id: String,
+    preference: Option<QuantPreference>,
+    state: State<'_, AppState>,
+) -> Result<QuantRecommendation, String> {
+    let preference = preference.unwrap_or_else(|| state.settings.lock().unwrap().quant_preference);
+    let mut system = System::new();
+    system.refresh_memory();
+    if system.total_memory() == 0 {
+        return Err("Could not determine the amount of system memory".to_string());
+    }
+    Ok(recommend(&model_id, preference, system.total_memory()))
+}
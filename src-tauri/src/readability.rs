@@ -0,0 +1,108 @@
+use tauri::{Emitter, Window};
+
+use crate::word_diff;
+use crate::{AppState, TranslateOptions};
+
+// "Simplify" mode: plain-language output at a target reading level. The prompt asks
+// for short sentences and the result is checked afterwards; if sentences are still
+// too long the chunk is regenerated once at the next stricter level.
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadingLevel {
+    Elementary,
+    #[default]
+    Intermediate,
+    Advanced,
+}
+
+impl ReadingLevel {
+    // Average sentence length allowed, in words (two CJK characters count as one word)
+    fn max_average_words(self) -> f32 {
+        match self {
+            ReadingLevel::Elementary => 10.0,
+            ReadingLevel::Intermediate => 15.0,
+            ReadingLevel::Advanced => 20.0,
+        }
+    }
+
+    fn stricter(self) -> Option<ReadingLevel> {
+        match self {
+            ReadingLevel::Advanced => Some(ReadingLevel::Intermediate),
+            ReadingLevel::Intermediate => Some(ReadingLevel::Elementary),
+            ReadingLevel::Elementary => None,
+        }
+    }
+}
+
+/// Extra system prompt text for simplified output.
+pub fn instruction(level: ReadingLevel) -> String {
+    let audience = match level {
+        ReadingLevel::Elementary => "a young child or a beginner learner of the language",
+        ReadingLevel::Intermediate => "a teenager or an intermediate learner of the language",
+        ReadingLevel::Advanced => "a general adult reader",
+    };
+    format!(
+        " Write the translation in plain language that {} can easily understand. Use short sentences of at most {} words, common everyday words, and split long sentences into several short ones. Keep all of the original meaning.",
+        audience,
+        level.max_average_words() as u32
+    )
+}
+
+fn is_sentence_end(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '。' | '！' | '？' | '\n')
+}
+
+/// Sentence lengths in words, counting two CJK characters as one word.
+pub fn sentence_lengths(text: &str) -> Vec<f32> {
+    text.split(is_sentence_end)
+        .map(|sentence| {
+            word_diff::tokenize(sentence)
+                .iter()
+                .map(|token| match token.chars().next() {
+                    Some(c) if word_diff::is_unspaced_script(c) => 0.5,
+                    _ => 1.0,
+                })
+                .sum::<f32>()
+        })
+        .filter(|len| *len > 0.0)
+        .collect()
+}
+
+/// True when the text is harder than `level` allows: average sentence length over
+/// the limit, or any single sentence more than twice as long.
+pub fn exceeds_level(text: &str, level: ReadingLevel) -> bool {
+    let lengths = sentence_lengths(text);
+    if lengths.is_empty() {
+        return false;
+    }
+    let limit = level.max_average_words();
+    let average = lengths.iter().sum::<f32>() / lengths.len() as f32;
+    average > limit || lengths.iter().any(|len| *len > limit * 2.0)
+}
+
+/// Runs a simplified translation without streaming, validates it and retries once
+/// at a stricter level if needed. The caller emits the final text.
+pub fn translate_simplified(
+    text: &str,
+    source_lang: &str,
+    target_lang: &str,
+    model_id: &str,
+    state: &AppState,
+    window: &Window,
+    options: &TranslateOptions,
+) -> Result<String, String> {
+    let mut options = TranslateOptions { stream: false, ..options.clone() };
+    let mut output = crate::run_translation(text, source_lang, target_lang, model_id, state, window, &options)?;
+
+    if exceeds_level(&output, options.reading_level) {
+        if let Some(stricter) = options.reading_level.stricter() {
+            window
+                .emit("debug-log", format!("Simplified output above {:?} level, retrying as {:?}", options.reading_level, stricter))
+                .unwrap_or(());
+            options.reading_level = stricter;
+            output = crate::run_translation(text, source_lang, target_lang, model_id, state, window, &options)?;
+        }
+    }
+    Ok(output)
+}
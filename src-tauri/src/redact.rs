@@ -0,0 +1,20 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::{history, AppState};
+
+// Debug logs (stderr and `debug-log` events) don't contain the user's text
+// unless verbose logging is switched on for troubleshooting. Redacted text is
+// shown as its length plus a short hash, enough to tell whether two log lines
+// saw the same input. Incognito mode always redacts.
+
+/// `text` as it may appear in a log line.
+pub fn text(state: &AppState, text: &str) -> String {
+    let verbose = state.settings.lock().unwrap().verbose_logs;
+    if verbose && !history::is_incognito(state) {
+        return text.to_string();
+    }
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    format!("<{} chars, #{:08x}>", text.chars().count(), hasher.finish() as u32)
+}
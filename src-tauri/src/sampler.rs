@@ -0,0 +1,126 @@
+use llama_cpp_2::sampling::LlamaSampler;
+use llama_cpp_2::token::data_array::LlamaTokenDataArray;
+use llama_cpp_2::token::LlamaToken;
+
+use crate::AppState;
+
+// Token sampling parameters. The defaults are the translation sampler: greedy
+// decoding with a mild repetition penalty over the last 64 tokens. A positive
+// temperature switches to seeded random sampling (for experiments). Models can
+// have their own settings (`modelSamplers`), since the small models repeat
+// themselves on CJK output much sooner, and a request can override them.
+
+// Temperature of a retry after greedy decoding failed
+const RETRY_TEMPERATURE: f32 = 0.3;
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SamplerSettings {
+    pub penalty_last_n: i32,
+    pub repeat_penalty: f32,
+    // Lower a token's logit per occurrence (frequency) or once (presence) in the last penalty_last_n tokens
+    pub frequency_penalty: f32,
+    pub presence_penalty: f32,
+    // 0 = greedy
+    pub temperature: f32,
+    pub seed: u32,
+}
+
+impl Default for SamplerSettings {
+    fn default() -> Self {
+        SamplerSettings {
+            penalty_last_n: 64,
+            repeat_penalty: 1.15,
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            temperature: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+/// Named output styles for users who don't want to tune the sampler: each
+/// bundles sampler settings with an instruction for the system prompt.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StylePreset {
+    // Close to the source wording; greedy
+    Faithful,
+    // Idiomatic phrasing with the same meaning
+    Natural,
+    // Freer rewording, e.g. for marketing copy or chat
+    Creative,
+}
+
+impl StylePreset {
+    pub fn settings(self) -> SamplerSettings {
+        let (temperature, repeat_penalty, presence_penalty) = match self {
+            StylePreset::Faithful => (0.0, 1.15, 0.0),
+            StylePreset::Natural => (0.3, 1.1, 0.0),
+            StylePreset::Creative => (0.8, 1.05, 0.2),
+        };
+        SamplerSettings { temperature, repeat_penalty, presence_penalty, ..SamplerSettings::default() }
+    }
+
+    /// Extra system prompt text for the style.
+    pub fn instruction(self) -> &'static str {
+        match self {
+            StylePreset::Faithful => " Translate as literally as the target language allows, keeping the wording and sentence structure of the source.",
+            StylePreset::Natural => " Prefer natural, idiomatic phrasing in the target language over literal wording, but keep the meaning exact.",
+            StylePreset::Creative => " Feel free to reword and restructure sentences so the text reads as if originally written in the target language, keeping its intent and tone.",
+        }
+    }
+}
+
+impl SamplerSettings {
+    /// Settings for a second attempt that must not repeat the first: another
+    /// seed, and mild randomness if decoding was greedy.
+    pub fn reseeded(&self) -> SamplerSettings {
+        SamplerSettings { temperature: self.temperature.max(RETRY_TEMPERATURE), seed: self.seed.wrapping_add(1), ..self.clone() }
+    }
+}
+
+/// The settings configured for `model_id`, or the defaults.
+pub fn for_model(state: &AppState, model_id: &str) -> SamplerSettings {
+    state.settings.lock().unwrap().model_samplers.get(model_id).cloned().unwrap_or_default()
+}
+
+/// Per-generation sampler state built from `SamplerSettings`.
+pub struct Sampler {
+    penalties: LlamaSampler,
+    // Temperature and seeded distribution, or None for greedy decoding
+    random: Option<(LlamaSampler, LlamaSampler)>,
+}
+
+impl Sampler {
+    pub fn new(settings: &SamplerSettings) -> Self {
+        let random = (settings.temperature > 0.0)
+            .then(|| (LlamaSampler::temp(settings.temperature), LlamaSampler::dist(settings.seed)));
+        Sampler {
+            penalties: LlamaSampler::penalties(
+                settings.penalty_last_n,
+                settings.repeat_penalty,
+                settings.frequency_penalty,
+                settings.presence_penalty,
+            ),
+            random,
+        }
+    }
+
+    /// Records a prompt or generated token so it counts towards the penalty.
+    pub fn accept(&mut self, token: LlamaToken) {
+        self.penalties.accept(token);
+    }
+
+    pub fn sample(&self, candidates: &mut LlamaTokenDataArray) -> LlamaToken {
+        candidates.apply_sampler(&self.penalties);
+        match &self.random {
+            None => candidates.sample_token_greedy(),
+            Some((temp, dist)) => {
+                candidates.apply_sampler(temp);
+                candidates.apply_sampler(dist);
+                candidates.selected_token().unwrap_or_else(|| candidates.sample_token_greedy())
+            }
+        }
+    }
+}
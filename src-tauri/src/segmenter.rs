@@ -0,0 +1,112 @@
+use crate::lang_detect;
+
+// Sentence boundaries for the chunker. A line that doesn't fit in one chunk is
+// cut between sentences rather than wherever the size runs out, so every
+// prompt holds whole sentences. What ends a sentence depends on the source
+// language: CJK text ends sentences with full-width punctuation and no space,
+// Thai has no sentence punctuation and separates sentences with spaces, and
+// Western text uses ". " which also follows abbreviations like "Dr." or "z.B.".
+
+// Words that end with a period without ending the sentence, lowercase without the period
+const ABBREVIATIONS: &[&str] = &[
+    // English
+    "mr", "mrs", "ms", "dr", "prof", "st", "jr", "sr", "vs", "etc", "inc", "ltd", "co", "corp", "no", "fig",
+    "vol", "approx", "dept", "est", "jan", "feb", "mar", "apr", "jun", "jul", "aug", "sep", "sept", "oct", "nov",
+    "dec",
+    // German, French, Spanish, Italian, Portuguese
+    "bzw", "ca", "ggf", "nr", "str", "usw", "vgl", "mme", "mlle", "env", "sra", "srta", "pág", "núm", "sig",
+];
+
+// Closing quotes and brackets that belong to the sentence they follow
+const CLOSERS: &[char] = &['"', '\'', '”', '’', ')', ']', '»', '」', '』', '）', '】'];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Segmenter {
+    // Japanese and Chinese: 。！？ end a sentence, with or without a following space
+    Cjk,
+    // Thai: sentences are separated by spaces
+    Thai,
+    // Space-separated languages: . ! ? followed by a space, except after abbreviations
+    Western,
+}
+
+impl Segmenter {
+    /// The segmenter for `source_lang`, or for the language detected in `text`
+    /// when the source language isn't a known name.
+    pub fn for_language(source_lang: &str, text: &str) -> Self {
+        match Self::by_name(source_lang) {
+            Some(segmenter) => segmenter,
+            None => lang_detect::detect(text).and_then(Self::by_name).unwrap_or(Segmenter::Western),
+        }
+    }
+
+    fn by_name(lang: &str) -> Option<Self> {
+        match lang.to_ascii_lowercase().as_str() {
+            "japanese" | "chinese" => Some(Segmenter::Cjk),
+            "thai" => Some(Segmenter::Thai),
+            "auto" | "" => None,
+            _ => Some(Segmenter::Western),
+        }
+    }
+
+    /// Splits one line into sentences. Each keeps the whitespace that follows
+    /// it, so the pieces concatenate back to `line`.
+    pub fn sentences(self, line: &str) -> Vec<&str> {
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let ends = match self {
+                Segmenter::Cjk => matches!(c, '。' | '！' | '？' | '!' | '?' | '．' | '…'),
+                Segmenter::Thai => c.is_whitespace() || matches!(c, '.' | '!' | '?'),
+                Segmenter::Western => matches!(c, '.' | '!' | '?' | '…'),
+            };
+            if !ends {
+                continue;
+            }
+            let mut end = i + c.len_utf8();
+            while let Some(&(j, next)) = chars.peek() {
+                if !CLOSERS.contains(&next) && !matches!(next, '.' | '!' | '?' | '。' | '！' | '？') {
+                    break;
+                }
+                end = j + next.len_utf8();
+                chars.next();
+            }
+            let space_end = end + (line[end..].len() - line[end..].trim_start().len());
+            if space_end == line.len() {
+                break;
+            }
+            let boundary = match self {
+                Segmenter::Cjk => true,
+                Segmenter::Thai => line[i..space_end].contains(char::is_whitespace),
+                Segmenter::Western => space_end > end && !continues(&line[start..i], c, &line[space_end..]),
+            };
+            if boundary {
+                pieces.push(&line[start..space_end]);
+                start = space_end;
+                while chars.peek().is_some_and(|&(j, _)| j < space_end) {
+                    chars.next();
+                }
+            }
+        }
+        pieces.push(&line[start..]);
+        pieces
+    }
+}
+
+// Whether a Western sentence goes on after `mark`: abbreviations, initials
+// ("J. R. R. Tolkien") and a lowercase next word
+fn continues(before: &str, mark: char, after: &str) -> bool {
+    if after.chars().next().is_some_and(char::is_lowercase) {
+        return true;
+    }
+    if mark != '.' {
+        return false;
+    }
+    let word = before.rsplit(char::is_whitespace).next().unwrap_or("");
+    let word = word.trim_start_matches(|c: char| !c.is_alphanumeric());
+    let is_initial = word.chars().count() == 1 && word.chars().all(char::is_uppercase);
+    // "e.g", "U.S", "z.B"
+    let dotted = word.contains('.');
+    is_initial || dotted || ABBREVIATIONS.contains(&word.to_lowercase().as_str())
+}
@@ -0,0 +1,200 @@
+use std::path::Path;
+use rusqlite::params;
+use tauri::State;
+
+use crate::export::{self, OutputTemplate};
+use crate::{db, examples, tm, AppState};
+
+// Review workflow for project translations. Every chunk a project job
+// translates is kept as a segment that starts out machine-translated and can be
+// edited and approved by a reviewer; only approved segments are exported.
+// Edits are fed back into the translation memory.
+
+// "notes.txt" -> "notes.japanese.approved.txt"
+const APPROVED_FILE_NAME: &str = "{name}.{target}.approved.{ext}";
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SegmentState {
+    Machine,
+    Edited,
+    Approved,
+}
+
+impl SegmentState {
+    fn as_str(self) -> &'static str {
+        match self {
+            SegmentState::Machine => "machine",
+            SegmentState::Edited => "edited",
+            SegmentState::Approved => "approved",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "edited" => SegmentState::Edited,
+            "approved" => SegmentState::Approved,
+            _ => SegmentState::Machine,
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Segment {
+    id: i64,
+    file_id: i64,
+    position: i64,
+    source_text: String,
+    target_text: String,
+    // What follows this chunk in the translation: "\n" between lines, a space or
+    // nothing between sentences of one long line (see split_into_chunks)
+    separator: String,
+    state: SegmentState,
+    updated_at: i64,
+}
+
+/// Drops a file's segments before it is translated again.
+pub fn clear_file(state: &AppState, file_id: i64) -> Result<(), String> {
+    state.db.with(|conn| conn.execute("DELETE FROM segments WHERE file_id = ?1", [file_id]))?;
+    Ok(())
+}
+
+/// Stores a freshly machine-translated chunk of a project file, with the
+/// separator that followed it.
+pub fn record(
+    state: &AppState,
+    project_id: i64,
+    file_id: i64,
+    position: usize,
+    source: &str,
+    target: &str,
+    separator: &str,
+) -> Result<(), String> {
+    state.db.with(|conn| {
+        conn.execute(
+            "INSERT INTO segments (project_id, file_id, position, source_text, target_text, separator, state, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![project_id, file_id, position as i64, source, target, separator, SegmentState::Machine.as_str(), db::now()],
+        )
+    })?;
+    Ok(())
+}
+
+fn load_segments(state: &AppState, project_id: i64, file_id: Option<i64>) -> Result<Vec<Segment>, String> {
+    state.db.with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, file_id, position, source_text, target_text, separator, state, updated_at FROM segments
+             WHERE project_id = ?1 AND (?2 IS NULL OR file_id = ?2)
+             ORDER BY file_id, position",
+        )?;
+        let rows = stmt.query_map(params![project_id, file_id], |row| {
+            Ok(Segment {
+                id: row.get(0)?,
+                file_id: row.get(1)?,
+                position: row.get(2)?,
+                source_text: row.get(3)?,
+                target_text: row.get(4)?,
+                separator: row.get(5)?,
+                state: SegmentState::parse(&row.get::<_, String>(6)?),
+                updated_at: row.get(7)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+/// Segments of a project, or of one of its files, in document order.
+#[tauri::command]
+pub async fn list_segments(project_id: i64, file_id: Option<i64>, state: State<'_, AppState>) -> Result<Vec<Segment>, String> {
+    load_segments(&state, project_id, file_id)
+}
+
+#[tauri::command]
+pub async fn set_segment_state(segment_id: i64, segment_state: SegmentState, state: State<'_, AppState>) -> Result<(), String> {
+    let updated = state.db.with(|conn| {
+        conn.execute(
+            "UPDATE segments SET state = ?1, updated_at = ?2 WHERE id = ?3",
+            params![segment_state.as_str(), db::now(), segment_id],
+        )
+    })?;
+    if updated == 0 {
+        return Err(format!("Segment {} not found", segment_id));
+    }
+    Ok(())
+}
+
+/// Saves a reviewer's correction of a segment of project `job_id`, marks it as
+/// edited and stores the corrected pair in the project's translation memory.
+/// With `add_example`, the pair also joins the few-shot example pool.
+#[tauri::command]
+pub async fn update_segment(
+    job_id: i64,
+    segment_id: i64,
+    edited_text: String,
+    add_example: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    if edited_text.trim().is_empty() {
+        return Err("Edited text is empty".to_string());
+    }
+    let (source_text, source_lang, target_lang) = state.db.with(|conn| {
+        let tx = conn.transaction()?;
+        let row = tx.query_row(
+            "SELECT s.source_text, p.source_lang, p.target_lang FROM segments s JOIN projects p ON p.id = s.project_id
+             WHERE s.id = ?1 AND s.project_id = ?2",
+            params![segment_id, job_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+        )?;
+        tx.execute(
+            "UPDATE segments SET target_text = ?1, state = ?2, updated_at = ?3 WHERE id = ?4",
+            params![edited_text, SegmentState::Edited.as_str(), db::now(), segment_id],
+        )?;
+        tx.commit()?;
+        Ok(row)
+    })?;
+    tm::store(&state, &source_text, &edited_text, &source_lang, &target_lang, Some(job_id))?;
+    if add_example.unwrap_or(false) {
+        examples::add(&state, &source_text, &edited_text, &source_lang, &target_lang, Some(job_id))?;
+    }
+    Ok(())
+}
+
+/// Writes the approved segments of every project file to its own output file
+/// (`file_name` pattern, see export.rs) and returns the paths written. Segments
+/// that aren't approved yet are left out; files without any are skipped.
+#[tauri::command]
+pub async fn export_approved(project_id: i64, file_name: Option<String>, state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let (source_lang, target_lang, files) = state.db.with(|conn| {
+        let (source_lang, target_lang) = conn.query_row(
+            "SELECT source_lang, target_lang FROM projects WHERE id = ?1",
+            [project_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )?;
+        let mut stmt = conn.prepare("SELECT id, path FROM project_files WHERE project_id = ?1 ORDER BY path")?;
+        let files = stmt
+            .query_map([project_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok((source_lang, target_lang, files))
+    })?;
+    let pattern = file_name.as_deref().unwrap_or(APPROVED_FILE_NAME);
+
+    let mut written = Vec::new();
+    for (file_id, path) in files {
+        let approved: Vec<Segment> = load_segments(&state, project_id, Some(file_id))?
+            .into_iter()
+            .filter(|segment| segment.state == SegmentState::Approved)
+            .collect();
+        let Some((last, rest)) = approved.split_last() else {
+            continue;
+        };
+        // Each segment is followed by its own separator, so a line that was split
+        // into several chunks is written back as one line
+        let mut text: String = rest.iter().flat_map(|segment| [segment.target_text.as_str(), segment.separator.as_str()]).collect();
+        text.push_str(&last.target_text);
+        let output = export::output_path(pattern, Path::new(&path), &source_lang, &target_lang, OutputTemplate::Translation);
+        std::fs::write(&output, text).map_err(|e| format!("Failed to write {:?}: {}", output, e))?;
+        written.push(output.to_string_lossy().into_owned());
+    }
+    Ok(written)
+}
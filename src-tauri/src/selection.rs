@@ -0,0 +1,194 @@
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use rdev::{Button, EventType, Key};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+use crate::os_window::{self, WindowHandle};
+use crate::{dnd, popup, AppState};
+
+// Selection button mode: when the user finishes selecting text with the mouse
+// (a drag or a double click), a small floating button appears next to the
+// cursor. Clicking it, or tapping Shift twice while it is visible, copies the
+// selection and translates it. Nothing is copied until then, so plain text
+// selection leaves the clipboard alone. The button never takes focus.
+
+const BUTTON_LABEL: &str = "selection-button";
+const BUTTON_SIZE: i32 = 32;
+// Offset from the cursor, so the button doesn't cover the end of the selection
+const BUTTON_OFFSET: i32 = 12;
+// Hidden again when not used within this time
+const BUTTON_TIMEOUT: Duration = Duration::from_secs(4);
+// A left press and release further apart than this selected text by dragging
+const DRAG_MIN_DISTANCE: f64 = 10.0;
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+const SHIFT_DOUBLE_TAP: Duration = Duration::from_millis(400);
+
+#[derive(Default)]
+struct ButtonState {
+    // Bumped on every show/hide so only the latest timeout fires
+    generation: u64,
+    visible: bool,
+    // Physical screen rect of the button and where the selection ended
+    rect: (i32, i32, i32, i32),
+    anchor: (f64, f64),
+    // Application the selection was made in
+    source_window: Option<WindowHandle>,
+}
+
+static BUTTON: OnceLock<Mutex<ButtonState>> = OnceLock::new();
+
+fn with_button<T>(f: impl FnOnce(&mut ButtonState) -> T) -> T {
+    f(&mut BUTTON.get_or_init(Default::default).lock().unwrap())
+}
+
+fn enabled(app: &AppHandle) -> bool {
+    app.state::<AppState>().settings.lock().unwrap().selection_button
+}
+
+fn over_button(x: f64, y: f64) -> bool {
+    with_button(|button| {
+        let (left, top, width, height) = button.rect;
+        let (x, y) = (x as i32, y as i32);
+        button.visible && x >= left && x < left + width && y >= top && y < top + height
+    })
+}
+
+/// Per-listener selection state, fed every mouse button and key event.
+#[derive(Default)]
+pub struct SelectionDetector {
+    press_at: Option<(f64, f64)>,
+    last_release: Option<Instant>,
+    last_shift: Option<Instant>,
+}
+
+impl SelectionDetector {
+    pub fn on_event(&mut self, app: &AppHandle, event: &EventType, x: f64, y: f64) {
+        match event {
+            EventType::ButtonPress(Button::Left) => {
+                // Clicks on the button itself are handled by its window
+                if over_button(x, y) {
+                    self.press_at = None;
+                    return;
+                }
+                hide(app);
+                self.press_at = Some((x, y));
+            }
+            EventType::ButtonRelease(Button::Left) => {
+                let Some((start_x, start_y)) = self.press_at.take() else {
+                    return;
+                };
+                let now = Instant::now();
+                let dragged = (x - start_x).hypot(y - start_y) >= DRAG_MIN_DISTANCE;
+                let double_click = self.last_release.is_some_and(|at| now.duration_since(at) < DOUBLE_CLICK_INTERVAL);
+                self.last_release = Some(now);
+                if (dragged || double_click) && enabled(app) && !dnd::is_active(app) {
+                    show(app, x, y, os_window::foreground_window());
+                }
+            }
+            EventType::KeyPress(Key::ShiftLeft | Key::ShiftRight) => {
+                let now = Instant::now();
+                let double_tap = self.last_shift.is_some_and(|at| now.duration_since(at) < SHIFT_DOUBLE_TAP);
+                self.last_shift = if double_tap { None } else { Some(now) };
+                if double_tap && with_button(|button| button.visible) {
+                    confirm(app);
+                }
+            }
+            // Typing replaces or moves the selection
+            EventType::KeyPress(_) => {
+                self.last_shift = None;
+                hide(app);
+            }
+            _ => {}
+        }
+    }
+}
+
+// Creates the button window on first use
+fn button_window(app: &AppHandle) -> Option<WebviewWindow> {
+    if let Some(window) = app.get_webview_window(BUTTON_LABEL) {
+        return Some(window);
+    }
+    let builder = WebviewWindowBuilder::new(app, BUTTON_LABEL, WebviewUrl::App("/selection-button".into()))
+        .title("Spark")
+        .inner_size(BUTTON_SIZE as f64, BUTTON_SIZE as f64)
+        .decorations(false)
+        .resizable(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .focused(false)
+        .visible(false);
+    #[cfg(not(target_os = "macos"))]
+    let builder = builder.transparent(true);
+    match builder.build() {
+        Ok(window) => Some(window),
+        Err(e) => {
+            eprintln!("Failed to create selection button: {}", e);
+            None
+        }
+    }
+}
+
+fn show(app: &AppHandle, x: f64, y: f64, source_window: Option<WindowHandle>) {
+    let app = app.clone();
+    // Off the listener thread; creating the window waits for the event loop
+    thread::spawn(move || {
+        let Some(window) = button_window(&app) else {
+            return;
+        };
+        let (left, top) = (x as i32 + BUTTON_OFFSET, y as i32 + BUTTON_OFFSET);
+        let generation = with_button(|button| {
+            button.generation += 1;
+            button.visible = true;
+            button.rect = (left, top, BUTTON_SIZE, BUTTON_SIZE);
+            button.anchor = (x, y);
+            button.source_window = source_window;
+            button.generation
+        });
+        let _ = window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x: left, y: top }));
+        let _ = window.show();
+
+        thread::sleep(BUTTON_TIMEOUT);
+        if with_button(|button| button.generation) == generation {
+            hide(&app);
+        }
+    });
+}
+
+fn hide(app: &AppHandle) {
+    let was_visible = with_button(|button| {
+        button.generation += 1;
+        std::mem::replace(&mut button.visible, false)
+    });
+    if was_visible {
+        if let Some(window) = app.get_webview_window(BUTTON_LABEL) {
+            let _ = window.hide();
+        }
+    }
+}
+
+// Hides the button, returns focus to the source application and translates its selection
+fn confirm(app: &AppHandle) {
+    let ((x, y), source_window) = with_button(|button| (button.anchor, button.source_window));
+    hide(app);
+    let app = app.clone();
+    thread::spawn(move || {
+        if let Some(handle) = source_window {
+            os_window::focus_window(handle);
+        }
+        popup::translate_selection(&app, x, y, source_window);
+    });
+}
+
+/// Called when the floating button is clicked.
+#[tauri::command]
+pub async fn confirm_selection_button(app: AppHandle) -> Result<(), String> {
+    confirm(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn dismiss_selection_button(app: AppHandle) -> Result<(), String> {
+    hide(&app);
+    Ok(())
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::emoji::EmojiPolicy;
+use crate::glossary::{self, PortableTerm};
+use crate::history::{self, HistoryRetention};
+use crate::hooks::CompletionHooks;
+use crate::model_registry::{self, ModelEntry};
+use crate::models::CustomModel;
+use crate::mouse_trigger::MouseTrigger;
+use crate::priority::JobPriorities;
+use crate::popup::{PopupPlacement, PopupSize};
+use crate::profiles::Profile;
+use crate::prompts::{self, PromptTemplates};
+use crate::publish::PublishSettings;
+use crate::quantization::QuantPreference;
+use crate::sampler::{SamplerSettings, StylePreset};
+use crate::shortcut::{self, TriggerBackend};
+use crate::sounds::SoundCues;
+use crate::AppState;
+
+// Persistent user settings, stored as JSON in the app config directory.
+// Missing fields fall back to defaults so older files keep loading.
+
+const SETTINGS_FILE: &str = "settings.json";
+
+// Version of the export_settings file format. Bump it when the layout changes
+// and add a step to `migrate_export` so older exports keep importing.
+const EXPORT_VERSION: u64 = 2;
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    // What to do with emoji/kaomoji in the source text
+    pub emoji_policy: EmojiPolicy,
+    // Open the popup at the mouse or docked to the source application's window
+    pub popup_placement: PopupPlacement,
+    // Suspend hotkeys automatically while a fullscreen application is focused
+    pub auto_do_not_disturb: bool,
+    // Named profiles and the one in use, see profiles.rs
+    pub profiles: Vec<Profile>,
+    pub active_profile: Option<String>,
+    // How long translation history is kept, see history.rs
+    pub history_retention: HistoryRetention,
+    // Include source/translated text in debug logs (troubleshooting only), see redact.rs
+    pub verbose_logs: bool,
+    // Keyboard hook (Ctrl+C double tap) or an OS global shortcut, see shortcut.rs
+    pub trigger_backend: TriggerBackend,
+    // Accelerator for the global shortcut backend; None uses the default
+    pub global_shortcut: Option<String>,
+    // Optional mouse gesture that also opens the popup, see mouse_trigger.rs
+    pub mouse_trigger: MouseTrigger,
+    // Show a floating translate button after selecting text with the mouse, see selection.rs
+    pub selection_button: bool,
+    // Prompt decode batch size in tokens; smaller needs less memory. None keeps llama.cpp's default
+    pub n_batch: Option<u32>,
+    // Model layers offloaded to the GPU, from the next model load; None keeps llama.cpp's default, see gpu.rs
+    pub gpu_layers: Option<u32>,
+    // Thread priority of interactive and background translation jobs, see priority.rs
+    pub job_priority: JobPriorities,
+    // Speed or quality when choosing a model's quantization variant, see quantization.rs
+    pub quant_preference: QuantPreference,
+    // Manifest of curated prompt/model catalog updates; None disables update checks, see updater.rs
+    pub update_url: Option<String>,
+    // Chunk size in estimated tokens per source language (e.g. "Japanese"), see chunk_size.rs
+    pub chunk_tokens: HashMap<String, usize>,
+    // Keep the output of a cancelled translation in the history and clipboard, marked as partial
+    pub keep_partial_results: bool,
+    // Sampler settings per model id; other models use the defaults, see sampler.rs
+    pub model_samplers: HashMap<String, SamplerSettings>,
+    // Default output style for translations that don't pick one, see sampler.rs
+    pub style_preset: Option<StylePreset>,
+    // Popup size picked by resizing it, per monitor; other monitors size it to the result, see popup.rs
+    pub popup_sizes: HashMap<String, PopupSize>,
+    // Move focus to the result when a translation finishes, for screen readers, see accessibility.rs
+    pub focus_result: bool,
+    // Sounds for finished and failed translations and a recognized hotkey, see sounds.rs
+    pub sound_cues: SoundCues,
+    // Target languages the popup's Ctrl+L cycles through, in order, see languages.rs
+    pub target_cycle: Vec<String>,
+    // GGUF files registered under model ids of their own, see models.rs
+    pub custom_models: Vec<CustomModel>,
+    // Webhook and command run when a document or batch job ends, see hooks.rs
+    pub completion_hooks: CompletionHooks,
+    // MQTT topic and/or named pipe receiving job status and translations, see publish.rs
+    pub publish: PublishSettings,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(SETTINGS_FILE))
+}
+
+/// Loads settings from disk, using defaults when the file is missing or invalid.
+pub fn load(app: &AppHandle) -> Settings {
+    let Ok(path) = settings_path(app) else {
+        return Settings::default();
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(raw) => serde_json::from_str(&raw).unwrap_or_else(|e| {
+            eprintln!("Invalid settings file {:?}, using defaults: {}", path, e);
+            Settings::default()
+        }),
+        Err(_) => Settings::default(),
+    }
+}
+
+pub fn save(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    let path = settings_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to save settings: {}", e))
+}
+
+// Full configuration as moved between machines: settings (including profiles
+// and their hotkey timing), the global glossary, the prompt templates and the
+// model registry (version 2)
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SettingsExport {
+    version: u64,
+    settings: Settings,
+    #[serde(default)]
+    glossary: Vec<PortableTerm>,
+    #[serde(default)]
+    prompts: Option<PromptTemplates>,
+    #[serde(default)]
+    models: Option<Vec<ModelEntry>>,
+}
+
+// Upgrades an export of any older version to the current layout
+fn migrate_export(value: serde_json::Value) -> Result<SettingsExport, String> {
+    let invalid = |e: serde_json::Error| format!("Invalid settings file: {}", e);
+    match value.get("version").and_then(|v| v.as_u64()) {
+        // A bare settings.json copied from another install
+        None => Ok(SettingsExport {
+            version: EXPORT_VERSION,
+            settings: serde_json::from_value(value).map_err(invalid)?,
+            glossary: Vec::new(),
+            prompts: None,
+            models: None,
+        }),
+        Some(version) if version > EXPORT_VERSION => {
+            Err(format!("Settings file is from a newer version of Spark (format {})", version))
+        }
+        // Version 1 had no model registry; the installed one is kept
+        Some(1) => {
+            let export: SettingsExport = serde_json::from_value(value).map_err(invalid)?;
+            Ok(SettingsExport { version: EXPORT_VERSION, models: None, ..export })
+        }
+        Some(_) => serde_json::from_value(value).map_err(invalid),
+    }
+}
+
+// Saves, applies and announces new settings
+fn apply(app: &AppHandle, state: &AppState, settings: &Settings) -> Result<(), String> {
+    // First, so an unusable shortcut is reported before anything is saved
+    shortcut::apply(app, settings)?;
+    save(app, settings)?;
+    *state.settings.lock().unwrap() = settings.clone();
+    app.emit("settings-changed", settings).unwrap_or(());
+    crate::tray::set_profiles(&settings.profiles, settings.active_profile.as_deref());
+    // A shorter retention applies right away; the settings are saved either way
+    if let Err(e) = history::prune(state) {
+        eprintln!("Failed to prune history: {}", e);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_settings(state: State<'_, AppState>) -> Result<Settings, String> {
+    Ok(state.settings.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn update_settings(settings: Settings, app: AppHandle, state: State<'_, AppState>) -> Result<Settings, String> {
+    apply(&app, &state, &settings)?;
+    Ok(settings)
+}
+
+/// Writes settings, the global glossary and the prompt templates to `path`.
+#[tauri::command]
+pub async fn export_settings(path: String, state: State<'_, AppState>) -> Result<(), String> {
+    let export = SettingsExport {
+        version: EXPORT_VERSION,
+        settings: state.settings.lock().unwrap().clone(),
+        glossary: glossary::export_global(&state)?,
+        prompts: Some(state.prompts.lock().unwrap().clone()),
+        models: Some(state.model_registry.lock().unwrap().clone()),
+    };
+    let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
+
+/// Restores a file written by `export_settings` (any format version, or a plain
+/// settings.json). Settings, prompts and the model registry are replaced;
+/// glossary terms are merged. The completion hook command is never imported:
+/// a shared file could otherwise run any program after the next job, so this
+/// machine's command (if any) is kept and has to be set here.
+#[tauri::command]
+pub async fn import_settings(path: String, app: AppHandle, state: State<'_, AppState>) -> Result<Settings, String> {
+    let raw = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    let value = serde_json::from_str(&raw).map_err(|e| format!("Invalid settings file: {}", e))?;
+    let mut export = migrate_export(value)?;
+    if export.settings.completion_hooks.command.is_some() {
+        eprintln!("Ignoring the completion hook command in imported settings {:?}", path);
+    }
+    export.settings.completion_hooks.command = state.settings.lock().unwrap().completion_hooks.command.clone();
+    glossary::import_global(&state, &export.glossary)?;
+    if let Some(templates) = &export.prompts {
+        prompts::save(&app, templates)?;
+    }
+    if let Some(models) = export.models {
+        model_registry::replace(&app, models)?;
+    }
+    apply(&app, &state, &export.settings)?;
+    Ok(export.settings)
+}
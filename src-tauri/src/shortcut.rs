@@ -0,0 +1,77 @@
+use std::thread;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+use crate::mouse_trigger::MouseTrigger;
+use crate::settings::Settings;
+use crate::{clipboard_files, dnd, history, os_window, popup, sounds, AppState};
+
+// Popup trigger backends. The default keyboard hook (rdev, see
+// start_key_listener) watches every key for the Ctrl+C double tap, which some
+// corporate antivirus products flag as a keylogger. The alternative registers a
+// single OS global shortcut instead; pressing it translates the clipboard, so
+// the flow becomes "copy, then press the shortcut".
+
+pub const DEFAULT_SHORTCUT: &str = "CommandOrControl+Shift+Space";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TriggerBackend {
+    #[default]
+    KeyboardHook,
+    GlobalShortcut,
+}
+
+pub fn backend(app: &AppHandle) -> TriggerBackend {
+    app.state::<AppState>().settings.lock().unwrap().trigger_backend
+}
+
+/// Registers the shortcut or starts the keyboard hook, whichever the settings
+/// select. A hook that is already running stays installed until restart, but
+/// ignores Ctrl+C while the shortcut backend is active.
+pub fn apply(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    shortcuts.unregister_all().map_err(|e| e.to_string())?;
+    // Mouse gestures and selections are detected by the hook whichever backend handles keys
+    if settings.mouse_trigger != MouseTrigger::Off || settings.selection_button {
+        crate::start_key_listener(app.clone());
+    }
+    match settings.trigger_backend {
+        TriggerBackend::KeyboardHook => crate::start_key_listener(app.clone()),
+        TriggerBackend::GlobalShortcut => {
+            let shortcut = settings.global_shortcut.as_deref().unwrap_or(DEFAULT_SHORTCUT);
+            shortcuts
+                .register(shortcut)
+                .map_err(|e| format!("Failed to register shortcut '{}': {}", shortcut, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Handler for the global-shortcut plugin.
+pub fn on_shortcut(app: &AppHandle, _shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed || backend(app) != TriggerBackend::GlobalShortcut || dnd::is_active(app) {
+        return;
+    }
+    sounds::play(app, sounds::Cue::Hotkey);
+    let app = app.clone();
+    thread::spawn(move || {
+        let source_window = os_window::foreground_window();
+        // No hook means no tracked mouse position; ask the OS instead
+        let (x, y) = app.cursor_position().map(|p| (p.x, p.y)).unwrap_or((0.0, 0.0));
+        let text = match app.clipboard().read_text() {
+            Ok(text) => text,
+            Err(_) if clipboard_files::offer(&app, x, y, source_window) => return,
+            Err(e) => {
+                eprintln!("Failed to read clipboard: {}", e);
+                return;
+            }
+        };
+        let state = app.state::<AppState>();
+        if !history::is_incognito(&state) {
+            state.clipboard_history.record(&text);
+        }
+        popup::show_popup(&app, text, popup::CaptureSource::Clipboard, x, y, source_window);
+    });
+}
@@ -0,0 +1,79 @@
+use tauri::{AppHandle, Manager};
+
+use crate::AppState;
+
+// Short sound cues for when the popup is on another monitor or the user is
+// looking away: a translation finished, failed, or the hotkey was recognized.
+// Each is off unless enabled in the settings (`soundCues`). The sounds are
+// bundled in data/sounds. Only played on Windows; elsewhere cues are silent.
+
+const COMPLETE_WAV: &[u8] = include_bytes!("../data/sounds/complete.wav");
+const ERROR_WAV: &[u8] = include_bytes!("../data/sounds/error.wav");
+const HOTKEY_WAV: &[u8] = include_bytes!("../data/sounds/hotkey.wav");
+
+/// Which cues are played.
+#[derive(Clone, Copy, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SoundCues {
+    pub complete: bool,
+    pub error: bool,
+    pub hotkey: bool,
+}
+
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Cue {
+    Complete,
+    Error,
+    Hotkey,
+}
+
+impl Cue {
+    fn wav(self) -> &'static [u8] {
+        match self {
+            Cue::Complete => COMPLETE_WAV,
+            Cue::Error => ERROR_WAV,
+            Cue::Hotkey => HOTKEY_WAV,
+        }
+    }
+
+    fn enabled(self, cues: SoundCues) -> bool {
+        match self {
+            Cue::Complete => cues.complete,
+            Cue::Error => cues.error,
+            Cue::Hotkey => cues.hotkey,
+        }
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use windows_sys::Win32::Media::Audio::{PlaySoundW, SND_ASYNC, SND_MEMORY, SND_NODEFAULT};
+
+    // Returns at once; a new cue cuts off one that is still playing
+    pub fn play(wav: &'static [u8]) {
+        unsafe {
+            PlaySoundW(wav.as_ptr() as *const u16, std::ptr::null_mut(), SND_MEMORY | SND_ASYNC | SND_NODEFAULT);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod imp {
+    pub fn play(_wav: &'static [u8]) {}
+}
+
+/// Plays `cue` if it is enabled in the settings.
+pub fn play(app: &AppHandle, cue: Cue) {
+    let cues = app.state::<AppState>().settings.lock().unwrap().sound_cues;
+    if cue.enabled(cues) {
+        imp::play(cue.wav());
+    }
+}
+
+/// Plays `cue` whether or not it is enabled, so the settings page can preview it.
+#[tauri::command]
+pub async fn preview_sound_cue(cue: Cue) -> Result<(), String> {
+    imp::play(cue.wav());
+    Ok(())
+}
@@ -0,0 +1,594 @@
+use tauri::{State, Window};
+
+use crate::{priority, AppState, SegmentTranslator};
+
+// Source-code-aware translation: a small lexer that knows the comment and string
+// syntax of common languages, so only comments (and optionally string literals)
+// are sent to the model and the code itself stays byte-identical. It is
+// hand-written rather than built on tree-sitter grammars: it only has to find
+// comments and strings, which doesn't justify a C grammar per language in the
+// bundle. The language quirks that matter for that are handled below (nested
+// block comments, raw strings and char literals in Rust, digit separators in
+// C++).
+
+// Closing quotes of raw strings with 0 to 7 hashes (r"..", r#".."#, ...)
+const RAW_STRING_ENDS: [&str; 8] = ["\"", "\"#", "\"##", "\"###", "\"####", "\"#####", "\"######", "\"#######"];
+
+pub struct Syntax {
+    // Longest markers first ("///" before "//")
+    line_comments: &'static [&'static str],
+    block_comments: &'static [(&'static str, &'static str)],
+    // Triple quotes first so they win over single quotes
+    quotes: &'static [&'static str],
+    // Python-style docstrings: triple-quoted strings are treated as comments
+    docstrings: bool,
+    // Opener counted inside block comments when they nest (Rust)
+    nested_comments: Option<&'static str>,
+    // Raw strings (r#".."#) and char literals ('"'), kept as code (Rust)
+    raw_strings: bool,
+    // A ' inside a number separates digits (C++14 1'000) instead of opening a char
+    digit_separators: bool,
+    // Quotes whose strings use backslash escapes and may be translated; strings
+    // in other quotes have different escape rules and are left alone
+    translatable_quotes: &'static [&'static str],
+    // Shell "...": only \\ \$ \` \" are escapes, and $ and ` are special
+    shell_escapes: bool,
+}
+
+const C_LIKE: Syntax = Syntax {
+    line_comments: &["///", "//!", "//"],
+    block_comments: &[("/**", "*/"), ("/*!", "*/"), ("/*", "*/")],
+    quotes: &["\"", "'", "`"],
+    docstrings: false,
+    nested_comments: None,
+    raw_strings: false,
+    digit_separators: true,
+    translatable_quotes: &["\"", "'", "`"],
+    shell_escapes: false,
+};
+
+// Rust uses ' for lifetimes as well as chars, so only double quotes delimit strings
+const RUST: Syntax = Syntax {
+    line_comments: &["///", "//!", "//"],
+    block_comments: &[("/**", "*/"), ("/*!", "*/"), ("/*", "*/")],
+    quotes: &["\""],
+    docstrings: false,
+    nested_comments: Some("/*"),
+    raw_strings: true,
+    digit_separators: false,
+    translatable_quotes: &["\""],
+    shell_escapes: false,
+};
+
+const HASH: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comments: &[],
+    quotes: &["\"\"\"", "'''", "\"", "'"],
+    docstrings: true,
+    nested_comments: None,
+    raw_strings: false,
+    digit_separators: false,
+    translatable_quotes: &["\"", "'"],
+    shell_escapes: false,
+};
+
+// Single-quoted shell strings have no escapes at all, so only "..." is translated
+const SHELL: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comments: &[],
+    quotes: &["\"", "'"],
+    docstrings: false,
+    nested_comments: None,
+    raw_strings: false,
+    digit_separators: false,
+    translatable_quotes: &["\""],
+    shell_escapes: true,
+};
+
+// PowerShell escapes with backticks and doubled quotes, not backslashes
+const POWERSHELL: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comments: &[("<#", "#>")],
+    quotes: &["\"", "'"],
+    docstrings: false,
+    nested_comments: None,
+    raw_strings: false,
+    digit_separators: false,
+    translatable_quotes: &[],
+    shell_escapes: false,
+};
+
+// YAML, TOML and INI: single-quoted values are literal
+const CONFIG: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comments: &[],
+    quotes: &["\"\"\"", "'''", "\"", "'"],
+    docstrings: false,
+    nested_comments: None,
+    raw_strings: false,
+    digit_separators: false,
+    translatable_quotes: &["\""],
+    shell_escapes: false,
+};
+
+// No line comments: `//` in url(http://..) is not a comment. CSS strings
+// escape with hex codes, so they are never translated.
+const CSS: Syntax = Syntax {
+    line_comments: &[],
+    block_comments: &[("/*", "*/")],
+    quotes: &["\"", "'"],
+    docstrings: false,
+    nested_comments: None,
+    raw_strings: false,
+    digit_separators: false,
+    translatable_quotes: &[],
+    shell_escapes: false,
+};
+
+const DASH: Syntax = Syntax {
+    line_comments: &["--"],
+    block_comments: &[("--[[", "]]"), ("{-", "-}"), ("/*", "*/")],
+    quotes: &["'", "\""],
+    docstrings: false,
+    nested_comments: None,
+    raw_strings: false,
+    digit_separators: false,
+    translatable_quotes: &[],
+    shell_escapes: false,
+};
+
+const MARKUP: Syntax = Syntax {
+    line_comments: &[],
+    block_comments: &[("<!--", "-->")],
+    quotes: &[],
+    docstrings: false,
+    nested_comments: None,
+    raw_strings: false,
+    digit_separators: false,
+    translatable_quotes: &[],
+    shell_escapes: false,
+};
+
+/// Maps a language name or file extension to its syntax.
+pub fn syntax_for(language: &str) -> Option<&'static Syntax> {
+    let lang = language.trim().trim_start_matches('.').to_lowercase();
+    match lang.as_str() {
+        "c" | "h" | "cpp" | "cc" | "cxx" | "hpp" | "c++" | "cs" | "csharp" | "java" | "js" | "jsx"
+        | "javascript" | "ts" | "tsx" | "typescript" | "go" | "golang" | "swift"
+        | "kt" | "kotlin" | "scala" | "dart" | "php" => Some(&C_LIKE),
+        "css" | "scss" => Some(&CSS),
+        "rs" | "rust" => Some(&RUST),
+        "py" | "python" | "rb" | "ruby" | "pl" | "perl" | "r" => Some(&HASH),
+        "sh" | "bash" | "zsh" | "dockerfile" | "makefile" => Some(&SHELL),
+        "ps1" | "powershell" => Some(&POWERSHELL),
+        "yaml" | "yml" | "toml" | "ini" | "conf" => Some(&CONFIG),
+        "sql" | "lua" | "hs" | "haskell" => Some(&DASH),
+        "html" | "htm" | "xml" | "vue" | "svelte" | "md" | "markdown" => Some(&MARKUP),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SegmentKind {
+    // Untouched code, including comment markers and quotes
+    Code,
+    Comment,
+    StringLiteral,
+}
+
+#[derive(Clone, Debug)]
+pub struct Segment {
+    pub kind: SegmentKind,
+    pub text: String,
+    // Set on the body of a complete, unprefixed string literal that may be translated
+    pub literal: Option<Literal>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Literal {
+    delimiter: &'static str,
+    shell_escapes: bool,
+}
+
+// Carries an unterminated block comment or string over to the next call, so
+// callers that lex line by line (e.g. diffs) keep state across lines.
+#[derive(Clone, Default)]
+pub struct LexState {
+    open_block: Option<&'static str>,
+    // Comments still open, for nested block comments
+    block_depth: usize,
+    // Closing quote, kind, and whether it is a raw string (no escapes)
+    open_quote: Option<(&'static str, SegmentKind, bool)>,
+}
+
+fn push(segments: &mut Vec<Segment>, kind: SegmentKind, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match segments.last_mut() {
+        Some(last) if last.kind == kind => last.text.push_str(text),
+        _ => segments.push(Segment { kind, text: text.to_string(), literal: None }),
+    }
+}
+
+// A whole string literal body is its own segment, so it can be re-escaped
+fn push_literal(segments: &mut Vec<Segment>, text: &str, literal: Literal) {
+    segments.push(Segment { kind: SegmentKind::StringLiteral, text: text.to_string(), literal: Some(literal) });
+}
+
+// Finds the end marker that closes a block comment `depth` levels deep, starting
+// at `from`. When there is none, `depth` is left at the levels still open.
+fn find_block_end(source: &str, from: usize, end_marker: &str, nested: Option<&str>, depth: &mut usize) -> Option<usize> {
+    let mut i = from;
+    while i < source.len() {
+        let rest = &source[i..];
+        if rest.starts_with(end_marker) {
+            *depth -= 1;
+            if *depth == 0 {
+                return Some(i);
+            }
+            i += end_marker.len();
+        } else if let Some(opener) = nested.filter(|opener| rest.starts_with(*opener)) {
+            *depth += 1;
+            i += opener.len();
+        } else {
+            i += rest.chars().next().map(|c| c.len_utf8()).unwrap_or(1);
+        }
+    }
+    None
+}
+
+// Finds the end of a string body starting at `from`, honouring backslash escapes
+// unless the string is raw.
+fn find_string_end(source: &str, from: usize, quote: &str, raw: bool) -> Option<usize> {
+    if raw {
+        return source[from..].find(quote).map(|offset| from + offset);
+    }
+    let mut i = from;
+    let bytes = source.as_bytes();
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && quote.len() == 1 {
+            i += 2;
+            continue;
+        }
+        if bytes[i..].starts_with(quote.as_bytes()) {
+            return Some(i);
+        }
+        // Single-quoted strings don't span lines in any supported language
+        if bytes[i] == b'\n' && quote.len() == 1 && quote != "`" {
+            return None;
+        }
+        i += 1;
+    }
+    None
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// r"..", r#".."#, br"..": the length of the opening and the closing quote
+fn raw_string_open(before: &str, rest: &str) -> Option<(usize, &'static str)> {
+    if before.chars().next_back().is_some_and(is_identifier_char) {
+        return None;
+    }
+    let after_prefix = rest.strip_prefix("br").or_else(|| rest.strip_prefix('r'))?;
+    let hashes = after_prefix.len() - after_prefix.trim_start_matches('#').len();
+    if hashes >= RAW_STRING_ENDS.len() || !after_prefix[hashes..].starts_with('"') {
+        return None;
+    }
+    Some((rest.len() - after_prefix.len() + hashes + 1, RAW_STRING_ENDS[hashes]))
+}
+
+// 'x', '\n', '\u{1F600}': the literal's length. Lifetimes ('a) aren't literals.
+fn char_literal_len(rest: &str) -> Option<usize> {
+    if !rest.starts_with('\'') {
+        return None;
+    }
+    let mut chars = rest.char_indices().skip(1);
+    match chars.next()? {
+        (_, '\\') => rest.get(3..)?.find('\'').filter(|&offset| offset <= 10).map(|offset| 3 + offset + 1),
+        _ => chars.next().filter(|&(_, c)| c == '\'').map(|(end, _)| end + 1),
+    }
+}
+
+// 1'000 or 0xFF'FF: the ' follows a number and a digit follows it
+fn is_digit_separator(before: &str, after: &str) -> bool {
+    let word = before.rsplit(|c: char| !c.is_ascii_alphanumeric()).next().unwrap_or("");
+    word.starts_with(|c: char| c.is_ascii_digit()) && after.starts_with(|c: char| c.is_ascii_alphanumeric())
+}
+
+/// Splits source into code / comment / string segments. Concatenating the
+/// segment texts always reproduces the input exactly.
+pub fn lex(source: &str, syntax: &Syntax, state: &mut LexState) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut i = 0;
+
+    // Resume an unterminated construct from a previous call
+    if let Some(end_marker) = state.open_block {
+        match find_block_end(source, 0, end_marker, syntax.nested_comments, &mut state.block_depth) {
+            Some(end) => {
+                push(&mut segments, SegmentKind::Comment, &source[..end]);
+                push(&mut segments, SegmentKind::Code, end_marker);
+                i = end + end_marker.len();
+                state.open_block = None;
+            }
+            None => {
+                push(&mut segments, SegmentKind::Comment, source);
+                return segments;
+            }
+        }
+    } else if let Some((quote, kind, raw)) = state.open_quote {
+        match find_string_end(source, 0, quote, raw) {
+            Some(end) => {
+                push(&mut segments, kind, &source[..end]);
+                push(&mut segments, SegmentKind::Code, quote);
+                i = end + quote.len();
+                state.open_quote = None;
+            }
+            None => {
+                push(&mut segments, kind, source);
+                return segments;
+            }
+        }
+    }
+
+    let mut code_start = i;
+    while i < source.len() {
+        let rest = &source[i..];
+
+        if let Some((start, end_marker)) = syntax.block_comments.iter().find(|(start, _)| rest.starts_with(start)) {
+            push(&mut segments, SegmentKind::Code, &source[code_start..i]);
+            push(&mut segments, SegmentKind::Code, start);
+            let body_start = i + start.len();
+            let mut depth = 1;
+            match find_block_end(source, body_start, end_marker, syntax.nested_comments, &mut depth) {
+                Some(end) => {
+                    push(&mut segments, SegmentKind::Comment, &source[body_start..end]);
+                    push(&mut segments, SegmentKind::Code, end_marker);
+                    i = end + end_marker.len();
+                }
+                None => {
+                    push(&mut segments, SegmentKind::Comment, &source[body_start..]);
+                    state.open_block = Some(end_marker);
+                    state.block_depth = depth;
+                    i = source.len();
+                }
+            }
+            code_start = i;
+            continue;
+        }
+
+        if let Some(marker) = syntax.line_comments.iter().find(|m| rest.starts_with(**m)) {
+            // Leave shebangs alone, and $# / ${#arr[@]} (shell) or $#array (Perl)
+            let shebang = i == 0 && rest.starts_with("#!");
+            let dollar_hash = *marker == "#" && (source[..i].ends_with('$') || source[..i].ends_with("${"));
+            if !shebang && !dollar_hash {
+                push(&mut segments, SegmentKind::Code, &source[code_start..i]);
+                push(&mut segments, SegmentKind::Code, marker);
+                let body_start = i + marker.len();
+                let line_end = source[body_start..].find('\n').map(|o| body_start + o).unwrap_or(source.len());
+                push(&mut segments, SegmentKind::Comment, &source[body_start..line_end]);
+                i = line_end;
+                code_start = i;
+                continue;
+            }
+        }
+
+        // Translations can't be escaped inside a raw string, so it stays code
+        if let Some((open_len, end)) = syntax.raw_strings.then(|| raw_string_open(&source[..i], rest)).flatten() {
+            let body_start = i + open_len;
+            match source[body_start..].find(end) {
+                Some(offset) => i = body_start + offset + end.len(),
+                None => {
+                    state.open_quote = Some((end, SegmentKind::Code, true));
+                    i = source.len();
+                }
+            }
+            continue;
+        }
+        if let Some(len) = syntax.raw_strings.then(|| char_literal_len(rest)).flatten() {
+            i += len;
+            continue;
+        }
+        if syntax.digit_separators && rest.starts_with('\'') && is_digit_separator(&source[..i], &rest[1..]) {
+            i += 1;
+            continue;
+        }
+
+        if let Some(quote) = syntax.quotes.iter().find(|q| rest.starts_with(**q)) {
+            let kind = if syntax.docstrings && quote.len() == 3 {
+                SegmentKind::Comment
+            } else {
+                SegmentKind::StringLiteral
+            };
+            push(&mut segments, SegmentKind::Code, &source[code_start..i]);
+            push(&mut segments, SegmentKind::Code, quote);
+            let body_start = i + quote.len();
+            match find_string_end(source, body_start, quote, false) {
+                Some(end) => {
+                    // f"..", b"..", L"..", u8"..": prefixes change the escape rules
+                    let prefixed = source[..i].chars().next_back().is_some_and(is_identifier_char);
+                    let body = &source[body_start..end];
+                    let translatable = kind == SegmentKind::StringLiteral && syntax.translatable_quotes.contains(quote);
+                    if translatable && !prefixed && !body.is_empty() {
+                        let literal = Literal { delimiter: quote, shell_escapes: syntax.shell_escapes };
+                        push_literal(&mut segments, body, literal);
+                    } else {
+                        push(&mut segments, kind, body);
+                    }
+                    push(&mut segments, SegmentKind::Code, quote);
+                    i = end + quote.len();
+                }
+                None if quote.len() == 3 || *quote == "`" => {
+                    push(&mut segments, kind, &source[body_start..]);
+                    state.open_quote = Some((quote, kind, false));
+                    i = source.len();
+                }
+                None => {
+                    // Unterminated single-line string: treat the rest of the line as code
+                    let line_end = source[body_start..].find('\n').map(|o| body_start + o).unwrap_or(source.len());
+                    push(&mut segments, SegmentKind::Code, &source[body_start..line_end]);
+                    i = line_end;
+                }
+            }
+            code_start = i;
+            continue;
+        }
+
+        i += rest.chars().next().map(|c| c.len_utf8()).unwrap_or(1);
+    }
+    push(&mut segments, SegmentKind::Code, &source[code_start..]);
+    segments
+}
+
+fn has_words(text: &str) -> bool {
+    text.chars().any(|c| c.is_alphabetic())
+}
+
+// ${x}, #{x}, {{x}}, {0}, {name}, %s, %.2f, %(name)s, $var: the model would
+// translate or drop them, and the code filling them in would break
+fn has_placeholder(text: &str) -> bool {
+    if text.contains("${") || text.contains("#{") || text.contains("{{") {
+        return true;
+    }
+    text.char_indices().any(|(i, c)| {
+        let rest = &text[i + c.len_utf8()..];
+        match c {
+            '{' => rest.find('}').is_some_and(|end| rest[..end].chars().all(|c| is_identifier_char(c) || c == ':' || c == '.')),
+            '%' => {
+                let spec = rest.trim_start_matches(|c: char| c.is_ascii_digit() || "-+#.".contains(c));
+                spec.starts_with('(') || spec.starts_with(|c: char| "sdifuxXeEgGcp@".contains(c))
+            }
+            '$' => rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_'),
+            _ => false,
+        }
+    })
+}
+
+// Strings like "utf-8", "user_id" or "/api/v1" are identifiers, not prose.
+fn looks_like_prose(text: &str) -> bool {
+    has_words(text) && (text.trim().contains(' ') || !text.is_ascii()) && !has_placeholder(text)
+}
+
+/// Translates a comment body line by line, keeping per-line decoration such as
+/// the leading ` * ` of block comments.
+pub fn translate_comment(text: &str, translator: &SegmentTranslator) -> Result<String, String> {
+    let mut lines = Vec::new();
+    for line in text.split('\n') {
+        let content_start = line
+            .char_indices()
+            .find(|(_, c)| !c.is_whitespace() && *c != '*' && *c != '/' && *c != '#' && *c != '!')
+            .map(|(i, _)| i)
+            .unwrap_or(line.len());
+        let (decoration, content) = line.split_at(content_start);
+        if has_words(content) {
+            lines.push(format!("{}{}", decoration, translator.translate(content)?));
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+// The text a string literal body stands for. None when it uses an escape other
+// than the simple ones (\x41, \u{..}, \d in a regex, ...), which couldn't be
+// written back the same way.
+fn unescape(body: &str, literal: Literal) -> Option<String> {
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match (chars.next()?, literal.shell_escapes) {
+            (c @ ('\\' | '"' | '`' | '$'), true) => out.push(c),
+            ('n', false) => out.push('\n'),
+            ('t', false) => out.push('\t'),
+            (c @ ('\\' | '"' | '\'' | '`'), false) => out.push(c),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+// Escapes translated text for the literal it goes back into: backslashes, the
+// delimiter, and line breaks and tabs (or $ and ` in shell strings)
+fn escape(text: &str, literal: Literal) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\n' if !literal.shell_escapes => out.push_str("\\n"),
+            '\t' if !literal.shell_escapes => out.push_str("\\t"),
+            '$' | '`' if literal.shell_escapes => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c == '\\' || literal.delimiter.starts_with(c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn translate_string_literal(segment: &Segment, translator: &SegmentTranslator) -> Result<String, String> {
+    let Some(literal) = segment.literal else {
+        return Ok(segment.text.clone());
+    };
+    match unescape(&segment.text, literal) {
+        Some(text) if looks_like_prose(&text) => Ok(escape(&translator.translate(&text)?, literal)),
+        _ => Ok(segment.text.clone()),
+    }
+}
+
+pub fn translate_segments(
+    segments: &[Segment],
+    translate_strings: bool,
+    translator: &SegmentTranslator,
+) -> Result<String, String> {
+    let mut out = String::new();
+    for segment in segments {
+        match segment.kind {
+            SegmentKind::Comment => out.push_str(&translate_comment(&segment.text, translator)?),
+            SegmentKind::StringLiteral if translate_strings => {
+                out.push_str(&translate_string_literal(segment, translator)?)
+            }
+            _ => out.push_str(&segment.text),
+        }
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn translate_code(
+    source: String,
+    language: String,
+    source_lang: String,
+    target_lang: String,
+    model_id: String,
+    translate_strings: Option<bool>,
+    state: State<'_, AppState>,
+    window: Window,
+) -> Result<String, String> {
+    let syntax = syntax_for(&language).ok_or_else(|| format!("Unsupported source language '{}'", language))?;
+    // Waits for earlier jobs and resets the cancellation flag
+    let _turn = state.jobs.start(&state, &window, priority::JobKind::Interactive).await?;
+
+    let translator = SegmentTranslator {
+        source_lang: &source_lang,
+        target_lang: &target_lang,
+        model_id: &model_id,
+        state: &state,
+        window: &window,
+    };
+    let segments = lex(&source, syntax, &mut LexState::default());
+    let output = translate_segments(&segments, translate_strings.unwrap_or(false), &translator)?;
+
+    crate::emit_result(&window, &state, &output, &target_lang)?;
+    Ok(output)
+}
@@ -0,0 +1,193 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+use std::sync::Mutex;
+use tauri::State;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::{db, model_registry, models, AppState};
+
+// Support bundle: a zip with what a bug report needs (recent debug log lines,
+// settings, model files, system info, the last error), so users don't have to
+// copy console output. Log lines are kept already redacted (see redact.rs), so
+// the bundle holds no translated text unless verbose logging was on.
+
+const LOG_CAPACITY: usize = 500;
+
+// Settings that can hold credentials or internal hosts (webhook tokens, hook
+// command arguments, broker addresses); the bundle only says whether they are set
+const PRIVATE_SETTINGS: &[&str] = &["/completionHooks/webhookUrl", "/completionHooks/command", "/publish/mqtt", "/publish/pipe"];
+
+#[derive(Default)]
+pub struct RecentLogs {
+    lines: Mutex<VecDeque<String>>,
+    last_error: Mutex<Option<String>>,
+}
+
+impl RecentLogs {
+    pub fn record(&self, line: &str) {
+        let mut lines = self.lines.lock().unwrap();
+        lines.push_back(format!("[{}] {}", db::now(), line));
+        if lines.len() > LOG_CAPACITY {
+            lines.pop_front();
+        }
+    }
+
+    pub fn record_error(&self, error: &str) {
+        self.record(&format!("ERROR {}", error));
+        *self.last_error.lock().unwrap() = Some(format!("[{}] {}", db::now(), error));
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelFile {
+    id: String,
+    file_name: String,
+    // None when the file wasn't found in any search location
+    path: Option<String>,
+    size_bytes: Option<u64>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemInfo {
+    app_version: &'static str,
+    os: &'static str,
+    arch: &'static str,
+    cpu_brand: String,
+    cpu_threads: usize,
+    total_memory_bytes: u64,
+    // SIMD extensions the CPU supports (NEON/dotprod/i8mm on ARM64, AVX2 etc. on x86-64)
+    cpu_features: Vec<&'static str>,
+    // Where llama.cpp runs: "metal" on Apple Silicon builds, "cpu" otherwise
+    accelerator: &'static str,
+    gpu_offload: bool,
+    // Set when this CPU will be noticeably slow, e.g. ARM64 without dot-product instructions
+    performance_note: Option<&'static str>,
+    models_path_env: Option<String>,
+    loaded_model: Option<String>,
+}
+
+fn model_files(state: &AppState) -> Vec<ModelFile> {
+    model_registry::ids(state)
+        .into_iter()
+        .map(|id| {
+            let path = models::resolve(state, &id).ok();
+            ModelFile {
+                file_name: model_registry::file_name(state, &id),
+                id,
+                size_bytes: path.as_ref().and_then(|p| std::fs::metadata(p).ok()).map(|m| m.len()),
+                path: path.map(|p| p.to_string_lossy().into_owned()),
+            }
+        })
+        .collect()
+}
+
+// Detected at runtime: llama.cpp is built for the build machine's CPU, so the
+// features present here are what the quantized kernels can use
+fn cpu_features() -> Vec<&'static str> {
+    #[allow(unused_mut)]
+    let mut features = Vec::new();
+    #[cfg(target_arch = "aarch64")]
+    {
+        use std::arch::is_aarch64_feature_detected;
+        let detected = [
+            ("neon", is_aarch64_feature_detected!("neon")),
+            ("dotprod", is_aarch64_feature_detected!("dotprod")),
+            ("fp16", is_aarch64_feature_detected!("fp16")),
+            ("i8mm", is_aarch64_feature_detected!("i8mm")),
+            ("sve", is_aarch64_feature_detected!("sve")),
+        ];
+        features.extend(detected.into_iter().filter(|(_, present)| *present).map(|(name, _)| name));
+    }
+    #[cfg(target_arch = "x86_64")]
+    {
+        let detected = [
+            ("sse4.2", is_x86_feature_detected!("sse4.2")),
+            ("avx", is_x86_feature_detected!("avx")),
+            ("avx2", is_x86_feature_detected!("avx2")),
+            ("fma", is_x86_feature_detected!("fma")),
+            ("f16c", is_x86_feature_detected!("f16c")),
+            ("avx512f", is_x86_feature_detected!("avx512f")),
+        ];
+        features.extend(detected.into_iter().filter(|(_, present)| *present).map(|(name, _)| name));
+    }
+    features
+}
+
+fn performance_note(features: &[&str]) -> Option<&'static str> {
+    match std::env::consts::ARCH {
+        "aarch64" if !features.contains(&"dotprod") => {
+            Some("This ARM64 CPU lacks dot-product instructions; quantized models will run slowly")
+        }
+        "x86_64" if !features.contains(&"avx2") => Some("This CPU lacks AVX2; quantized models will run slowly"),
+        "aarch64" | "x86_64" => None,
+        _ => Some("No SIMD-optimized llama.cpp kernels for this architecture; translation will be slow"),
+    }
+}
+
+pub fn system_info(state: &AppState) -> SystemInfo {
+    let mut system = sysinfo::System::new();
+    system.refresh_cpu_list(sysinfo::CpuRefreshKind::nothing());
+    system.refresh_memory();
+    let cpu_features = cpu_features();
+    SystemInfo {
+        app_version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        cpu_brand: system.cpus().first().map(|cpu| cpu.brand().trim().to_string()).unwrap_or_default(),
+        cpu_threads: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        total_memory_bytes: system.total_memory(),
+        performance_note: performance_note(&cpu_features),
+        cpu_features,
+        accelerator: if cfg!(all(target_os = "macos", target_arch = "aarch64")) { "metal" } else { "cpu" },
+        gpu_offload: state._backend.supports_gpu_offload(),
+        models_path_env: std::env::var("SPARK_MODELS_PATH").ok(),
+        loaded_model: state.current_model_id.lock().unwrap().clone(),
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| e.to_string())
+}
+
+fn redacted_settings(state: &AppState) -> Result<serde_json::Value, String> {
+    let mut settings = serde_json::to_value(&*state.settings.lock().unwrap()).map_err(|e| e.to_string())?;
+    for pointer in PRIVATE_SETTINGS {
+        if let Some(value) = settings.pointer_mut(pointer) {
+            *value = serde_json::Value::from(if value.is_null() { "unset" } else { "set" });
+        }
+    }
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_system_info(state: State<'_, AppState>) -> Result<SystemInfo, String> {
+    Ok(system_info(&state))
+}
+
+/// Writes the support bundle zip to `path`.
+#[tauri::command]
+pub async fn export_support_bundle(path: String, state: State<'_, AppState>) -> Result<(), String> {
+    let logs: Vec<String> = state.recent_logs.lines.lock().unwrap().iter().cloned().collect();
+    let last_error = state.recent_logs.last_error.lock().unwrap().clone();
+    let entries = [
+        ("logs.txt", logs.join("\n")),
+        ("settings.json", to_json(&redacted_settings(&state)?)?),
+        ("models.json", to_json(&model_files(&state))?),
+        ("system.json", to_json(&system_info(&state))?),
+        ("last_error.txt", last_error.unwrap_or_else(|| "No error recorded this session".to_string())),
+    ];
+
+    let file = File::create(&path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, contents) in entries {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(contents.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    zip.finish().map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    Ok(())
+}
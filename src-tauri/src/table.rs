@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use crate::SegmentTranslator;
+
+// Spreadsheet cells copied as tab-separated text. Translated as a whole, the
+// model merges cells and drops tabs, so pasting back lands in the wrong cells.
+// Instead each cell is translated on its own and the tabs, row endings and
+// quoting are written back exactly as copied. Excel quotes cells that contain
+// line breaks, tabs or quotes ("a ""b"" c"), and ends the last row with CRLF.
+
+#[derive(Debug)]
+struct Cell<'a> {
+    // Unquoted content
+    text: String,
+    quoted: bool,
+    // What follows the cell: "\t", the row ending, or "" at the end of the text
+    separator: &'a str,
+}
+
+// Splits `text` into cells, or None if an opening quote is never closed
+fn parse(text: &str) -> Option<Vec<Vec<Cell<'_>>>> {
+    let mut rows = vec![Vec::new()];
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (content, quoted, after) = match rest.strip_prefix('"') {
+            Some(inner) => {
+                let (content, after) = quoted_cell(inner)?;
+                (content, true, after)
+            }
+            None => {
+                let end = rest.find(['\t', '\r', '\n']).unwrap_or(rest.len());
+                (rest[..end].to_string(), false, &rest[end..])
+            }
+        };
+        let separator_len = if after.starts_with("\r\n") {
+            2
+        } else if after.starts_with(['\t', '\r', '\n']) {
+            1
+        } else if after.is_empty() {
+            0
+        } else {
+            // Text after a closing quote: not a quoted cell after all
+            return None;
+        };
+        let separator = &after[..separator_len];
+        rows.last_mut().unwrap().push(Cell { text: content, quoted, separator });
+        rest = &after[separator_len..];
+        if separator != "\t" && !rest.is_empty() {
+            rows.push(Vec::new());
+        }
+    }
+    Some(rows)
+}
+
+// The content of a quoted cell and the text after its closing quote
+fn quoted_cell(inner: &str) -> Option<(String, &str)> {
+    let mut content = String::new();
+    let mut chars = inner.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c != '"' {
+            content.push(c);
+        } else if inner[i + 1..].starts_with('"') {
+            content.push('"');
+            chars.next();
+        } else {
+            return Some((content, &inner[i + 1..]));
+        }
+    }
+    None
+}
+
+/// Whether `text` looks like copied spreadsheet cells: at least two columns,
+/// the same number in every row, and not merely tab-indented lines.
+pub fn is_table(text: &str) -> bool {
+    if !text.contains('\t') {
+        return false;
+    }
+    let Some(rows) = parse(text) else {
+        return false;
+    };
+    let columns = rows[0].len();
+    columns >= 2
+        && rows.iter().all(|row| row.len() == columns)
+        && !rows.iter().all(|row| row[0].text.trim().is_empty())
+}
+
+// Written back in quotes when it was quoted or now has to be
+fn write_cell(output: &mut String, text: &str, quoted: bool) {
+    if quoted || text.contains(['\t', '\n', '"']) {
+        output.push('"');
+        output.push_str(&text.replace('"', "\"\""));
+        output.push('"');
+    } else {
+        output.push_str(text);
+    }
+}
+
+/// Translates every cell containing words; numbers, codes and empty cells are
+/// kept. Line breaks the model adds inside a cell that had none are joined.
+pub fn translate(text: &str, translator: &SegmentTranslator) -> Result<String, String> {
+    let rows = parse(text).ok_or("Not a table")?;
+    // Column headers and category values repeat
+    let mut cache: HashMap<&str, String> = HashMap::new();
+    let mut output = String::with_capacity(text.len());
+    for cell in rows.iter().flatten() {
+        let translated = if !cell.text.chars().any(char::is_alphabetic) {
+            cell.text.clone()
+        } else if let Some(cached) = cache.get(cell.text.as_str()) {
+            cached.clone()
+        } else {
+            let mut translated = translator.translate(&cell.text)?.replace('\t', " ");
+            if !cell.text.contains('\n') {
+                translated = translated.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+            }
+            cache.insert(&cell.text, translated.clone());
+            translated
+        };
+        write_cell(&mut output, &translated, cell.quoted);
+        output.push_str(cell.separator);
+    }
+    Ok(output)
+}
@@ -0,0 +1,96 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Where the time of a translation went: loading the model, decoding each
+// chunk's prompt, and generating its output. run_translation fills a recorder
+// passed in TranslateOptions; the result is stored with the history entry and
+// summarized by `get_job_report` (see history.rs).
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkTiming {
+    // Position of the chunk in the job (split chunks keep counting up)
+    pub chunk: usize,
+    pub prompt_tokens: usize,
+    pub output_tokens: usize,
+    // Context creation plus prompt decoding
+    pub prompt_ms: u64,
+    pub generation_ms: u64,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobTimings {
+    // Zero when the model was already loaded
+    pub load_ms: u64,
+    pub chunks: Vec<ChunkTiming>,
+}
+
+/// Shared between the calls of one job, e.g. the retries of a simplified translation.
+pub type Recorder = Arc<Mutex<JobTimings>>;
+
+pub fn millis(duration: Duration) -> u64 {
+    duration.as_millis().min(u64::MAX as u128) as u64
+}
+
+pub fn record_load(recorder: Option<&Recorder>, duration: Duration) {
+    if let Some(recorder) = recorder {
+        recorder.lock().unwrap().load_ms += millis(duration);
+    }
+}
+
+pub fn record_chunk(recorder: Option<&Recorder>, timing: ChunkTiming) {
+    if let Some(recorder) = recorder {
+        recorder.lock().unwrap().chunks.push(timing);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Phase {
+    Loading,
+    Prompting,
+    Generation,
+}
+
+/// Totals of a job's timings, with the per-chunk breakdown.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobReport {
+    id: i64,
+    model_id: String,
+    created_at: i64,
+    load_ms: u64,
+    prompt_ms: u64,
+    generation_ms: u64,
+    prompt_tokens: usize,
+    output_tokens: usize,
+    // Output tokens per second of generation time
+    tokens_per_second: Option<f64>,
+    // The phase that took the longest
+    dominant: Phase,
+    chunks: Vec<ChunkTiming>,
+}
+
+impl JobReport {
+    pub fn new(id: i64, model_id: String, created_at: i64, timings: JobTimings) -> Self {
+        let prompt_ms = timings.chunks.iter().map(|c| c.prompt_ms).sum();
+        let generation_ms: u64 = timings.chunks.iter().map(|c| c.generation_ms).sum();
+        let output_tokens = timings.chunks.iter().map(|c| c.output_tokens).sum();
+        let phases = [(Phase::Loading, timings.load_ms), (Phase::Prompting, prompt_ms), (Phase::Generation, generation_ms)];
+        let dominant = phases.iter().max_by_key(|(_, ms)| *ms).map(|(phase, _)| *phase).unwrap_or(Phase::Generation);
+        JobReport {
+            id,
+            model_id,
+            created_at,
+            load_ms: timings.load_ms,
+            prompt_ms,
+            generation_ms,
+            prompt_tokens: timings.chunks.iter().map(|c| c.prompt_tokens).sum(),
+            output_tokens,
+            tokens_per_second: (generation_ms > 0).then(|| output_tokens as f64 * 1000.0 / generation_ms as f64),
+            dominant,
+            chunks: timings.chunks,
+        }
+    }
+}
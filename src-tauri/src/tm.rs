@@ -0,0 +1,136 @@
+use rusqlite::{params, OptionalExtension};
+use tauri::State;
+
+use crate::{db, projects, AppState};
+
+// Translation memory: stored source/target segment pairs that are reused
+// verbatim when the same source comes up again. Like the glossary, entries are
+// scoped to a project or global, and project entries win over global ones.
+
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TmEntry {
+    id: i64,
+    // None for global entries
+    project_id: Option<i64>,
+    source_text: String,
+    target_text: String,
+    updated_at: i64,
+}
+
+// Lookup key: whitespace differences don't make a new segment
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Stored translation of `text` for the project (default: the active one) or,
+/// failing that, the global memory.
+pub fn lookup(state: &AppState, text: &str, source_lang: &str, target_lang: &str, project_id: Option<i64>) -> Option<String> {
+    let key = normalize(text);
+    if key.is_empty() {
+        return None;
+    }
+    let project_id = projects::scope(state, project_id);
+    state
+        .db
+        .with(|conn| {
+            conn.query_row(
+                "SELECT target_text FROM tm_entries
+                 WHERE source_key = ?1 AND source_lang = ?2 AND target_lang = ?3 AND (project_id IS NULL OR project_id = ?4)
+                 ORDER BY project_id IS NULL, updated_at DESC LIMIT 1",
+                params![key, source_lang, target_lang, project_id],
+                |row| row.get(0),
+            )
+            .optional()
+        })
+        .ok()
+        .flatten()
+}
+
+/// Adds a pair to the memory of `project_id` (None = global), replacing any
+/// earlier translation of the same source.
+pub fn store(
+    state: &AppState,
+    source_text: &str,
+    target_text: &str,
+    source_lang: &str,
+    target_lang: &str,
+    project_id: Option<i64>,
+) -> Result<(), String> {
+    let key = normalize(source_text);
+    if key.is_empty() || target_text.trim().is_empty() {
+        return Err("Translation memory entries must not be empty".to_string());
+    }
+    state.db.with(|conn| {
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM tm_entries WHERE project_id IS ?1 AND source_lang = ?2 AND target_lang = ?3 AND source_key = ?4",
+            params![project_id, source_lang, target_lang, key],
+        )?;
+        tx.execute(
+            "INSERT INTO tm_entries (project_id, source_lang, target_lang, source_key, source_text, target_text, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![project_id, source_lang, target_lang, key, source_text, target_text, db::now()],
+        )?;
+        tx.commit()
+    })
+}
+
+/// Adds a pair to a project's memory (default: the active project), or to the
+/// global memory when `global` is set or no project is open.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn add_tm_entry(
+    source_text: String,
+    target_text: String,
+    source_lang: String,
+    target_lang: String,
+    project_id: Option<i64>,
+    global: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let scope = if global.unwrap_or(false) { None } else { projects::scope(&state, project_id) };
+    store(&state, &source_text, &target_text, &source_lang, &target_lang, scope)
+}
+
+/// Entries whose source contains `query`, visible from a project (default: the
+/// active one) plus the global memory, project entries first.
+#[tauri::command]
+pub async fn search_tm(
+    query: String,
+    source_lang: String,
+    target_lang: String,
+    project_id: Option<i64>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TmEntry>, String> {
+    let scope = projects::scope(&state, project_id);
+    let pattern = format!("%{}%", normalize(&query).replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT) as i64;
+    state.db.with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, source_text, target_text, updated_at FROM tm_entries
+             WHERE source_lang = ?1 AND target_lang = ?2 AND (project_id IS NULL OR project_id = ?3)
+               AND source_key LIKE ?4 ESCAPE '\\'
+             ORDER BY project_id IS NULL, updated_at DESC LIMIT ?5",
+        )?;
+        let rows = stmt.query_map(params![source_lang, target_lang, scope, pattern, limit], |row| {
+            Ok(TmEntry {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                source_text: row.get(2)?,
+                target_text: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+#[tauri::command]
+pub async fn remove_tm_entry(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.with(|conn| conn.execute("DELETE FROM tm_entries WHERE id = ?1", [id]))?;
+    Ok(())
+}
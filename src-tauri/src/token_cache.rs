@@ -0,0 +1,63 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+use llama_cpp_2::model::{AddBos, LlamaModel};
+use llama_cpp_2::token::LlamaToken;
+
+// Tokenization results of recent prompts and chunks, keyed by model and text.
+// Batch jobs and retries tokenize the same instructions and boilerplate again
+// and again; a repeat is served from here instead of `str_to_token`. The
+// oldest entries are dropped once MAX_CACHED_TOKENS is reached, and the whole
+// cache when a model is (re)loaded, since the file behind an id can change.
+
+const MAX_CACHED_TOKENS: usize = 256 * 1024;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Key {
+    model_id: String,
+    add_bos: bool,
+    text: String,
+}
+
+#[derive(Default)]
+struct TokenCache {
+    entries: HashMap<Key, Vec<LlamaToken>>,
+    // Insertion order, oldest first
+    order: VecDeque<Key>,
+    tokens: usize,
+}
+
+static CACHE: OnceLock<Mutex<TokenCache>> = OnceLock::new();
+
+fn with_cache<T>(f: impl FnOnce(&mut TokenCache) -> T) -> T {
+    f(&mut CACHE.get_or_init(Default::default).lock().unwrap())
+}
+
+/// `model.str_to_token`, cached. `model_id` names the model's vocabulary.
+pub fn tokenize(model: &LlamaModel, model_id: &str, text: &str, add_bos: AddBos) -> Result<Vec<LlamaToken>, String> {
+    let key = Key { model_id: model_id.to_string(), add_bos: matches!(add_bos, AddBos::Always), text: text.to_string() };
+    if let Some(tokens) = with_cache(|cache| cache.entries.get(&key).cloned()) {
+        return Ok(tokens);
+    }
+    let tokens = model.str_to_token(text, add_bos).map_err(|e| e.to_string())?;
+    with_cache(|cache| {
+        if cache.entries.contains_key(&key) || tokens.len() > MAX_CACHED_TOKENS {
+            return;
+        }
+        while cache.tokens + tokens.len() > MAX_CACHED_TOKENS {
+            let Some(oldest) = cache.order.pop_front() else { break };
+            if let Some(evicted) = cache.entries.remove(&oldest) {
+                cache.tokens -= evicted.len();
+            }
+        }
+        cache.tokens += tokens.len();
+        cache.order.push_back(key.clone());
+        cache.entries.insert(key, tokens.clone());
+    });
+    Ok(tokens)
+}
+
+/// Forgets everything, e.g. after loading a model.
+pub fn clear() {
+    with_cache(|cache| *cache = TokenCache::default());
+}
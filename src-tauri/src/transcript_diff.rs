@@ -0,0 +1,104 @@
+use crate::word_diff::{self, DiffOp};
+
+// Read-aloud check: a word diff between the sentence the user was asked to read
+// and a speech-to-text transcript of what they said. The app has no speech
+// capture or recognizer of its own, so the transcript comes from the caller
+// (e.g. the frontend's speech input). Only the words are compared; nothing
+// here judges pronunciation itself.
+
+// Per side; the word alignment needs memory for every pair of words
+const MAX_WORDS: usize = 2000;
+
+#[derive(Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WordStatus {
+    Correct,
+    // Expected word was replaced by something else
+    Substituted,
+    // Expected word was not heard at all
+    Missed,
+    // Heard a word that isn't in the expected text
+    Extra,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct WordResult {
+    expected: Option<String>,
+    heard: Option<String>,
+    status: WordStatus,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct TranscriptDiff {
+    // Correct words / expected words, 0.0 - 1.0
+    accuracy: f32,
+    correct: usize,
+    total: usize,
+    words: Vec<WordResult>,
+}
+
+pub fn compare(expected: &str, transcript: &str) -> Result<TranscriptDiff, String> {
+    let expected_tokens = word_diff::tokenize(expected);
+    let heard_tokens = word_diff::tokenize(transcript);
+
+    // Punctuation-only tokens ("—", "。") are never spoken, skip them
+    let expected_tokens: Vec<String> = expected_tokens.into_iter()
+        .filter(|t| !word_diff::normalize(t).is_empty())
+        .collect();
+    let heard_tokens: Vec<String> = heard_tokens.into_iter()
+        .filter(|t| !word_diff::normalize(t).is_empty())
+        .collect();
+    if expected_tokens.len() > MAX_WORDS || heard_tokens.len() > MAX_WORDS {
+        return Err(format!("Texts of more than {} words can't be compared; use a shorter passage", MAX_WORDS));
+    }
+
+    let ops = word_diff::diff(&expected_tokens, &heard_tokens, |a, b| {
+        word_diff::normalize(a) == word_diff::normalize(b)
+    });
+
+    let mut words = Vec::with_capacity(ops.len());
+    let mut correct = 0;
+    let mut idx = 0;
+    while idx < ops.len() {
+        if let DiffOp::Equal(a, b) = ops[idx] {
+            correct += 1;
+            words.push(WordResult {
+                expected: Some(expected_tokens[a].clone()),
+                heard: Some(heard_tokens[b].clone()),
+                status: WordStatus::Correct,
+            });
+            idx += 1;
+            continue;
+        }
+        // A run of changes between two correct words: the expected and heard
+        // words in it pair up as substitutions, in order
+        let run_end = ops[idx..].iter().position(|op| matches!(op, DiffOp::Equal(..))).map_or(ops.len(), |end| idx + end);
+        let run = &ops[idx..run_end];
+        let missed: Vec<usize> = run.iter().filter_map(|op| if let DiffOp::Delete(a) = op { Some(*a) } else { None }).collect();
+        let extra: Vec<usize> = run.iter().filter_map(|op| if let DiffOp::Insert(b) = op { Some(*b) } else { None }).collect();
+        for pair in 0..missed.len().max(extra.len()) {
+            let expected = missed.get(pair).map(|&a| expected_tokens[a].clone());
+            let heard = extra.get(pair).map(|&b| heard_tokens[b].clone());
+            let status = match (&expected, &heard) {
+                (Some(_), Some(_)) => WordStatus::Substituted,
+                (Some(_), None) => WordStatus::Missed,
+                _ => WordStatus::Extra,
+            };
+            words.push(WordResult { expected, heard, status });
+        }
+        idx = run_end;
+    }
+
+    let total = expected_tokens.len();
+    let accuracy = if total == 0 { 0.0 } else { correct as f32 / total as f32 };
+
+    Ok(TranscriptDiff { accuracy, correct, total, words })
+}
+
+#[tauri::command]
+pub async fn diff_transcript(expected: String, transcript: String) -> Result<TranscriptDiff, String> {
+    if expected.trim().is_empty() {
+        return Err("Expected text is empty".to_string());
+    }
+    compare(&expected, &transcript)
+}
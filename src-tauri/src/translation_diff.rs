@@ -0,0 +1,152 @@
+use rusqlite::OptionalExtension;
+use tauri::State;
+
+use crate::word_diff::{self, DiffOp};
+use crate::AppState;
+
+// Word-level comparison of two translations from the history, e.g. the same
+// text through nano and high, or before and after a glossary change. Diffing
+// here keeps large texts off the frontend, which only renders the spans.
+// Line breaks are kept as tokens of their own; other whitespace is normalized
+// to single spaces, and unspaced scripts (CJK) are compared per character.
+
+// Above this many LCS cells (tokens of a times tokens of b, after trimming the
+// common start and end) the middle is reported as replaced wholesale, since
+// the table would take hundreds of megabytes
+const MAX_DIFF_CELLS: usize = 16_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SpanKind {
+    Equal,
+    // Only in the first translation
+    Removed,
+    // Only in the second translation
+    Added,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct DiffSpan {
+    kind: SpanKind,
+    text: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffSide {
+    id: i64,
+    model_id: String,
+    created_at: i64,
+    words: usize,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranslationDiff {
+    a: DiffSide,
+    b: DiffSide,
+    // Shared words / average word count, 0.0 - 1.0
+    similarity: f32,
+    spans: Vec<DiffSpan>,
+}
+
+// Words per line, with "\n" tokens between lines
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            tokens.push("\n".to_string());
+        }
+        tokens.extend(word_diff::tokenize(line));
+    }
+    tokens
+}
+
+fn needs_space(previous: &str, next: &str) -> bool {
+    let unspaced = |c: Option<char>| c.is_some_and(word_diff::is_unspaced_script);
+    previous != "\n"
+        && next != "\n"
+        && !(unspaced(previous.chars().next_back()) && unspaced(next.chars().next()))
+}
+
+// Appends a token to the spans, merging it into the last span of the same kind
+fn push(spans: &mut Vec<DiffSpan>, previous: &mut Option<String>, kind: SpanKind, token: &str) {
+    let space = previous.as_deref().is_some_and(|previous| needs_space(previous, token));
+    match spans.last_mut() {
+        Some(span) if span.kind == kind => {
+            if space {
+                span.text.push(' ');
+            }
+            span.text.push_str(token);
+        }
+        _ => {
+            // A space between spans goes with the one it starts
+            let text = if space { format!(" {}", token) } else { token.to_string() };
+            spans.push(DiffSpan { kind, text });
+        }
+    }
+    *previous = Some(token.to_string());
+}
+
+// The spans turning a into b, and how many words they share
+fn diff(a: &[String], b: &[String]) -> (Vec<DiffSpan>, usize) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let (middle_a, middle_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let ops = if middle_a.len().saturating_mul(middle_b.len()) <= MAX_DIFF_CELLS {
+        word_diff::diff(middle_a, middle_b, |x, y| x == y)
+    } else {
+        (0..middle_a.len()).map(DiffOp::Delete).chain((0..middle_b.len()).map(DiffOp::Insert)).collect()
+    };
+
+    let mut spans = Vec::new();
+    // Last token written, for spacing; removed and added tokens both follow it
+    let mut previous = None;
+    for token in &a[..prefix] {
+        push(&mut spans, &mut previous, SpanKind::Equal, token);
+    }
+    let mut lcs_words = 0;
+    for op in ops {
+        let (kind, token) = match op {
+            DiffOp::Equal(i, _) => {
+                lcs_words += usize::from(middle_a[i] != "\n");
+                (SpanKind::Equal, &middle_a[i])
+            }
+            DiffOp::Delete(i) => (SpanKind::Removed, &middle_a[i]),
+            DiffOp::Insert(j) => (SpanKind::Added, &middle_b[j]),
+        };
+        push(&mut spans, &mut previous, kind, token);
+    }
+    for token in &a[a.len() - suffix..] {
+        push(&mut spans, &mut previous, SpanKind::Equal, token);
+    }
+    let shared = words(&a[..prefix]) + words(&a[a.len() - suffix..]) + lcs_words;
+    (spans, shared)
+}
+
+fn words(tokens: &[String]) -> usize {
+    tokens.iter().filter(|token| *token != "\n").count()
+}
+
+fn load(state: &AppState, id: i64) -> Result<(DiffSide, Vec<String>), String> {
+    let row: Option<(String, String, i64)> = state.db.with(|conn| {
+        conn.query_row("SELECT translated_text, model_id, created_at FROM history WHERE id = ?1", [id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .optional()
+    })?;
+    let (text, model_id, created_at) = row.ok_or_else(|| format!("History entry {} not found", id))?;
+    let tokens = tokenize(&text);
+    Ok((DiffSide { id, model_id, created_at, words: words(&tokens) }, tokens))
+}
+
+/// Word-level differences from history entry `id_a`'s translation to `id_b`'s.
+#[tauri::command]
+pub async fn diff_translations(id_a: i64, id_b: i64, state: State<'_, AppState>) -> Result<TranslationDiff, String> {
+    let (a, tokens_a) = load(&state, id_a)?;
+    let (b, tokens_b) = load(&state, id_b)?;
+    let (spans, shared) = diff(&tokens_a, &tokens_b);
+    let similarity = if a.words + b.words == 0 { 1.0 } else { (2 * shared) as f32 / (a.words + b.words) as f32 };
+    Ok(TranslationDiff { a, b, similarity, spans })
+}
@@ -0,0 +1,92 @@
+use std::sync::OnceLock;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{App, Manager};
+
+use crate::profiles::Profile;
+use crate::AppState;
+
+// System tray icon and menu.
+
+// Updates the "Do Not Disturb" checkbox when the toggle changes outside the menu
+type CheckedSetter = Box<dyn Fn(bool) + Send + Sync>;
+static DND_CHECKED: OnceLock<CheckedSetter> = OnceLock::new();
+
+// Rebuilds the "Profile" submenu when profiles are added, removed or switched
+type ProfilesSetter = Box<dyn Fn(&[Profile], Option<&str>) + Send + Sync>;
+static PROFILES: OnceLock<ProfilesSetter> = OnceLock::new();
+
+// Menu ids of the profile entries; "profile:" + name for named profiles
+const NO_PROFILE_ID: &str = "profile-none";
+const PROFILE_ID_PREFIX: &str = "profile:";
+
+pub fn create(app: &App) -> tauri::Result<()> {
+    let dnd = CheckMenuItem::with_id(app, "dnd", "Do Not Disturb", true, false, None::<&str>)?;
+    let open = MenuItem::with_id(app, "open", "Open Spark", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let profiles = Submenu::with_items(app, "Profile", true, &[])?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let menu = Menu::with_items(app, &[&dnd, &profiles, &open, &separator, &quit])?;
+
+    let dnd_item = dnd.clone();
+    let _ = DND_CHECKED.set(Box::new(move |checked| {
+        let _ = dnd_item.set_checked(checked);
+    }));
+
+    let handle = app.handle().clone();
+    let _ = PROFILES.set(Box::new(move |list, active| {
+        while let Ok(Some(_)) = profiles.remove_at(0) {}
+        let entries = std::iter::once((NO_PROFILE_ID.to_string(), "Default", active.is_none()))
+            .chain(list.iter().map(|p| (format!("{}{}", PROFILE_ID_PREFIX, p.name), p.name.as_str(), active == Some(p.name.as_str()))));
+        for (id, name, checked) in entries {
+            if let Ok(item) = CheckMenuItem::with_id(&handle, id, name, true, checked, None::<&str>) {
+                let _ = profiles.append(&item);
+            }
+        }
+    }));
+    {
+        let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+        set_profiles(&settings.profiles, settings.active_profile.as_deref());
+    }
+
+    let mut builder = TrayIconBuilder::with_id("main")
+        .tooltip("Spark")
+        .menu(&menu)
+        .on_menu_event(move |app, event| match event.id.as_ref() {
+            "dnd" => crate::dnd::set(app, dnd.is_checked().unwrap_or(false)),
+            "open" => {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+            }
+            "quit" => app.exit(0),
+            NO_PROFILE_ID => {
+                let _ = crate::profiles::switch(app, None);
+            }
+            id => {
+                if let Some(name) = id.strip_prefix(PROFILE_ID_PREFIX) {
+                    if let Err(e) = crate::profiles::switch(app, Some(name.to_string())) {
+                        eprintln!("{}", e);
+                    }
+                }
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    Ok(())
+}
+
+pub fn set_do_not_disturb_checked(checked: bool) {
+    if let Some(set_checked) = DND_CHECKED.get() {
+        set_checked(checked);
+    }
+}
+
+pub fn set_profiles(profiles: &[Profile], active: Option<&str>) {
+    if let Some(set) = PROFILES.get() {
+        set(profiles, active);
+    }
+}
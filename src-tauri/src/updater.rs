@@ -0,0 +1,206 @@
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::prompts::{self, PromptTemplates};
+use crate::AppState;
+
+// Curated updates between app releases: prompt-template improvements and model
+// catalog entries (data only, never code), published as a JSON manifest at the
+// URL in the settings. Checking only reports what would change, on
+// `update-available`; nothing is written until the user applies that exact
+// version. Applied templates then reload like a manual edit (see prompts.rs).
+
+const UPDATES_FILE: &str = "updates.json";
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Manifest at the update URL. Every field but `version` is optional.
+#[derive(Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateManifest {
+    // Increases with every published update
+    version: u64,
+    #[serde(default)]
+    notes: String,
+    // Replacement templates; missing ones are left alone
+    #[serde(default)]
+    prompts: PromptUpdate,
+    // The full catalog; replaces the stored one
+    models: Option<Vec<CatalogEntry>>,
+}
+
+#[derive(Clone, Default, serde::Deserialize)]
+struct PromptUpdate {
+    system: Option<String>,
+    chat: Option<String>,
+    example: Option<String>,
+}
+
+/// A downloadable model offered in the catalog.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogEntry {
+    pub id: String,
+    name: String,
+    pub file_name: String,
+    pub url: String,
+    pub size_bytes: Option<u64>,
+    description: Option<String>,
+}
+
+// What has been applied so far, in `{app config dir}/updates.json`
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct AppliedUpdates {
+    version: u64,
+    models: Vec<CatalogEntry>,
+}
+
+/// Sent on `update-available` and returned by `check_for_updates`.
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSummary {
+    version: u64,
+    notes: String,
+    // Template files the update would overwrite
+    prompt_files: Vec<&'static str>,
+    // Names of catalog entries that are new or changed
+    new_models: Vec<String>,
+}
+
+// The manifest from the last check, kept until it is applied or superseded
+static PENDING: OnceLock<Mutex<Option<UpdateManifest>>> = OnceLock::new();
+
+fn with_pending<T>(f: impl FnOnce(&mut Option<UpdateManifest>) -> T) -> T {
+    f(&mut PENDING.get_or_init(Default::default).lock().unwrap())
+}
+
+fn updates_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(UPDATES_FILE))
+}
+
+fn load_applied(app: &AppHandle) -> AppliedUpdates {
+    updates_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_applied(app: &AppHandle, applied: &AppliedUpdates) -> Result<(), String> {
+    let path = updates_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(applied).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
+
+fn fetch(url: &str) -> Result<UpdateManifest, String> {
+    ureq::get(url)
+        .timeout(REQUEST_TIMEOUT)
+        .call()
+        .map_err(|e| format!("Failed to fetch updates from {}: {}", url, e))?
+        .into_json()
+        .map_err(|e| format!("Invalid update manifest at {}: {}", url, e))
+}
+
+fn summarize(manifest: &UpdateManifest, applied: &AppliedUpdates) -> UpdateSummary {
+    let prompts = &manifest.prompts;
+    let prompt_files = [("system.txt", &prompts.system), ("chat.txt", &prompts.chat), ("example.txt", &prompts.example)]
+        .into_iter()
+        .filter(|(_, text)| text.is_some())
+        .map(|(file, _)| file)
+        .collect();
+    let new_models = manifest
+        .models
+        .iter()
+        .flatten()
+        .filter(|entry| !applied.models.contains(entry))
+        .map(|entry| entry.name.clone())
+        .collect();
+    UpdateSummary { version: manifest.version, notes: manifest.notes.clone(), prompt_files, new_models }
+}
+
+// Fetches the manifest and keeps it as pending when it is newer than what was applied
+fn check(app: &AppHandle) -> Result<Option<UpdateSummary>, String> {
+    let url = app.state::<AppState>().settings.lock().unwrap().update_url.clone();
+    let url = url.ok_or("No update URL is configured")?;
+    let manifest = fetch(&url)?;
+    let applied = load_applied(app);
+    if manifest.version <= applied.version {
+        with_pending(|pending| pending.take());
+        return Ok(None);
+    }
+    let summary = summarize(&manifest, &applied);
+    with_pending(|pending| *pending = Some(manifest));
+    Ok(Some(summary))
+}
+
+/// Checks at startup and then daily while an update URL is configured.
+pub fn start_checker(app: AppHandle) {
+    thread::spawn(move || loop {
+        let configured = app.state::<AppState>().settings.lock().unwrap().update_url.is_some();
+        if configured {
+            match check(&app) {
+                Ok(Some(summary)) => app.emit("update-available", summary).unwrap_or(()),
+                Ok(None) => {}
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+/// Looks for a newer update now. Returns None when there is nothing to apply.
+#[tauri::command]
+pub async fn check_for_updates(app: AppHandle) -> Result<Option<UpdateSummary>, String> {
+    check(&app)
+}
+
+/// Applies the update found by the last check, after the user agreed to
+/// `version`: replaces the listed prompt templates and the model catalog.
+#[tauri::command]
+pub async fn apply_update(version: u64, app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let manifest = with_pending(|pending| match pending.take() {
+        Some(manifest) if manifest.version == version => Ok(manifest),
+        other => {
+            *pending = other;
+            Err(format!("Update {} is not pending; check for updates again", version))
+        }
+    })?;
+
+    let current = state.prompts.lock().unwrap().clone();
+    let update = manifest.prompts;
+    if update.system.is_some() || update.chat.is_some() || update.example.is_some() {
+        let templates = PromptTemplates {
+            system: update.system.unwrap_or(current.system),
+            chat: update.chat.unwrap_or(current.chat),
+            example: update.example.unwrap_or(current.example),
+        };
+        prompts::save(&app, &templates)?;
+    }
+
+    let mut applied = load_applied(&app);
+    applied.version = manifest.version;
+    if let Some(models) = manifest.models {
+        applied.models = models;
+    }
+    save_applied(&app, &applied)?;
+    app.emit("update-applied", manifest.version).unwrap_or(());
+    Ok(())
+}
+
+/// Model catalog from the last applied update (empty until one is applied).
+pub fn catalog(app: &AppHandle) -> Vec<CatalogEntry> {
+    load_applied(app).models
+}
+
+#[tauri::command]
+pub async fn get_model_catalog(app: AppHandle) -> Result<Vec<CatalogEntry>, String> {
+    Ok(catalog(&app))
+}
@@ -6,9 +6,19 @@ import "./App.css";
 
 const appWindow = getCurrentWindow();
 
+// `accessibility-{label}` payload, see accessibility.rs
+type Announcement =
+    | { kind: "started"; sourceLang: string; targetLang: string }
+    | { kind: "progress"; percent: number }
+    | { kind: "completed"; status: "completed" | "cancelled"; characters: number; preview: string; focusResult: boolean };
+
 function App() {
     const [inputText, setInputText] = useState("");
     const [translatedText, setTranslatedText] = useState("");
+    const [direction, setDirection] = useState<"ltr" | "rtl">("ltr");
+    // Read out by screen readers instead of the streamed text
+    const [announcement, setAnnouncement] = useState("");
+    const resultRef = useRef<HTMLParagraphElement>(null);
 
     const [sourceLang, setSourceLang] = useState("English");
     const [targetLang, setTargetLang] = useState("Japanese");
@@ -103,9 +113,12 @@ function App() {
 
     useEffect(() => {
         // Set up event listener for streaming translations
-        const unlistenPromise = listen<{ chunk: string; is_last: boolean }>("translation-event-main", (event) => {
+        const unlistenPromise = listen<{ chunk: string; is_last: boolean; status?: "completed" | "cancelled"; direction?: "ltr" | "rtl" }>("translation-event-main", (event) => {
             console.log("📥 Event received:", event.payload.chunk.substring(0, 50), "is_last:", event.payload.is_last);
 
+            if (event.payload.direction) {
+                setDirection(event.payload.direction);
+            }
             // Only update if chunk is not empty
             if (event.payload.chunk) {
                 setTranslatedText((prev) => {
@@ -116,10 +129,14 @@ function App() {
 
             if (event.payload.is_last) {
                 setIsTranslating(false);
+                const cancelled = event.payload.status === "cancelled";
                 // Fallback if translation resulted in no output
-                setTranslatedText(prev =>
-                    prev === "翻訳中..." ? "翻訳に失敗しました。バックエンドのログを確認してください。" : prev
-                );
+                setTranslatedText(prev => {
+                    if (prev === "翻訳中...") {
+                        return cancelled ? "翻訳をキャンセルしました。" : "翻訳に失敗しました。バックエンドのログを確認してください。";
+                    }
+                    return cancelled ? prev + "\n\n（キャンセルされました）" : prev;
+                });
                 console.log("✅ Translation complete");
 
                 // Start auto-unload timer after translation finishes
@@ -127,6 +144,20 @@ function App() {
             }
         });
 
+        const unlistenAccessibilityPromise = listen<Announcement>("accessibility-main", (event) => {
+            const a = event.payload;
+            if (a.kind === "started") {
+                setAnnouncement(`${a.sourceLang}から${a.targetLang}へ翻訳中`);
+            } else if (a.kind === "progress") {
+                setAnnouncement(`${a.percent}%翻訳済み`);
+            } else {
+                setAnnouncement(a.status === "cancelled"
+                    ? `翻訳をキャンセルしました（${a.characters}文字）`
+                    : `翻訳完了（${a.characters}文字）: ${a.preview}`);
+                if (a.focusResult) resultRef.current?.focus();
+            }
+        });
+
         // Debug Log Listener
         const unlistenLogPromise = listen<string>("debug-log", (event) => {
             console.log("🛠️ BACKEND LOG:", event.payload);
@@ -138,9 +169,50 @@ function App() {
             }
         });
 
+        // Switching profiles (tray or command) applies the profile's defaults
+        const unlistenProfilePromise = listen<{ modelId?: string; sourceLang?: string; targetLang?: string } | null>("profile-changed", (event) => {
+            const profile = event.payload;
+            if (!profile) return;
+            if (profile.modelId) setModelId(profile.modelId);
+            if (profile.sourceLang) setSourceLang(profile.sourceLang);
+            if (profile.targetLang) setTargetLang(profile.targetLang);
+        });
+
+        // Low-memory mitigations taken by the backend (see memory.rs)
+        const unlistenMemoryPromise = listen<{ action: string; message: string }>("memory-pressure", (event) => {
+            console.warn("🧠 Memory:", event.payload.message);
+            if (event.payload.action === "unloadedModel") {
+                setModelLoaded(false);
+            }
+        });
+
+        // Output the prompt-injection guard threw away again (see prompt_guard.rs)
+        const unlistenSecurityPromise = listen<{ chunk: number; violation: string; action: string; discard: number }>("translation-security-warning-main", (event) => {
+            console.warn("🛡️ Prompt injection guard:", event.payload.violation, event.payload.action);
+            const discard = event.payload.discard;
+            if (discard > 0) setTranslatedText((prev) => prev.slice(0, Math.max(0, prev.length - discard)));
+        });
+
+        // Curated prompt/catalog updates are only applied once the user agrees (see updater.rs)
+        const unlistenUpdatePromise = listen<{ version: number; notes: string; promptFiles: string[]; newModels: string[] }>("update-available", (event) => {
+            const { version, notes, promptFiles, newModels } = event.payload;
+            const changes = [
+                promptFiles.length > 0 ? `プロンプト: ${promptFiles.join(", ")}` : null,
+                newModels.length > 0 ? `モデル: ${newModels.join(", ")}` : null,
+            ].filter(Boolean).join("\n");
+            if (window.confirm(`翻訳設定の更新があります (v${version})\n${notes}\n${changes}\n\n適用しますか？`)) {
+                invoke("apply_update", { version }).catch(console.error);
+            }
+        });
+
         return () => {
             unlistenPromise.then((unlisten) => unlisten());
             unlistenLogPromise.then((unlisten) => unlisten());
+            unlistenProfilePromise.then((unlisten) => unlisten());
+            unlistenMemoryPromise.then((unlisten) => unlisten());
+            unlistenUpdatePromise.then((unlisten) => unlisten());
+            unlistenSecurityPromise.then((unlisten) => unlisten());
+            unlistenAccessibilityPromise.then((unlisten) => unlisten());
             // Clear timer on unmount
             if (unloadTimerRef.current) clearTimeout(unloadTimerRef.current);
         };
@@ -285,6 +357,23 @@ function App() {
 
     const [showSettings, setShowSettings] = useState(false);
 
+    // Self-test report from run_diagnostics
+    type DiagnosticCheck = { name: string; status: "pass" | "warn" | "fail" | "skipped"; detail: string };
+    const [diagnostics, setDiagnostics] = useState<DiagnosticCheck[] | null>(null);
+    const [runningDiagnostics, setRunningDiagnostics] = useState(false);
+
+    const runDiagnostics = async () => {
+        setRunningDiagnostics(true);
+        try {
+            const report = await invoke<{ passed: boolean; checks: DiagnosticCheck[] }>("run_diagnostics");
+            setDiagnostics(report.checks);
+        } catch (error) {
+            setDiagnostics([{ name: "Diagnostics", status: "fail", detail: String(error) }]);
+        } finally {
+            setRunningDiagnostics(false);
+        }
+    };
+
     return (
         <div className="w-full h-full bg-[#f5f7f8] dark:bg-[#101922] font-display flex flex-col overflow-hidden relative group transition-colors duration-300">
             {/* Top Bar */}
@@ -492,7 +581,10 @@ function App() {
                     {/* Result Display */}
                     <div className="w-full flex-1 p-8 overflow-y-auto">
                         <p
-                            className="text-2xl md:text-3xl text-gray-900 dark:text-white leading-relaxed font-light whitespace-pre-wrap transition-all duration-200"
+                            ref={resultRef}
+                            tabIndex={-1}
+                            dir={translatedText ? direction : undefined}
+                            className="outline-none text-2xl md:text-3xl text-gray-900 dark:text-white leading-relaxed font-light whitespace-pre-wrap transition-all duration-200"
                             style={{ fontSize: `${fontSize}px`, lineHeight: 1.5 }}
                         >
                             {translatedText || (
@@ -501,6 +593,7 @@ function App() {
                                 </span>
                             )}
                         </p>
+                        <div className="sr-only" role="status" aria-live="polite">{announcement}</div>
                     </div>
 
                     {/* Action Floating Buttons */}
@@ -592,6 +685,38 @@ function App() {
                                     ))}
                                 </div>
                             </div>
+
+                            {/* Diagnostics */}
+                            <div className="flex flex-col gap-2">
+                                <div className="flex items-center justify-between">
+                                    <div className="flex flex-col">
+                                        <span className="text-sm font-medium text-gray-900 dark:text-gray-200">Diagnostics</span>
+                                        <span className="text-xs text-gray-500">Check models, clipboard and hotkeys</span>
+                                    </div>
+                                    <button
+                                        onClick={runDiagnostics}
+                                        disabled={runningDiagnostics}
+                                        className="px-3 py-1.5 rounded-lg text-sm border border-gray-200 dark:border-white/10 text-gray-600 dark:text-gray-400 hover:border-gray-400 dark:hover:border-white/30 disabled:opacity-50"
+                                    >
+                                        {runningDiagnostics ? "Running..." : "Run"}
+                                    </button>
+                                </div>
+                                {diagnostics && (
+                                    <ul className="flex flex-col gap-1.5 mt-1">
+                                        {diagnostics.map((check) => (
+                                            <li key={check.name} className="flex items-start gap-2 text-xs">
+                                                <span className={`material-icons text-[16px] ${check.status === "pass" ? "text-green-500" : check.status === "warn" ? "text-yellow-500" : check.status === "fail" ? "text-red-500" : "text-gray-400"}`}>
+                                                    {check.status === "pass" ? "check_circle" : check.status === "warn" ? "warning" : check.status === "fail" ? "error" : "remove_circle_outline"}
+                                                </span>
+                                                <div className="flex flex-col">
+                                                    <span className="font-medium text-gray-800 dark:text-gray-200">{check.name}</span>
+                                                    <span className="text-gray-500 break-all">{check.detail}</span>
+                                                </div>
+                                            </li>
+                                        ))}
+                                    </ul>
+                                )}
+                            </div>
                         </div>
 
                         <div className="pt-4 border-t border-gray-200 dark:border-white/10 flex justify-end">
@@ -1,4 +1,4 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { getCurrentWindow } from "@tauri-apps/api/window";
@@ -6,11 +6,62 @@ import "./App.css"; // Reuse globally defined styles if needed, or inline
 
 const appWindow = getCurrentWindow();
 
+// Files copied in Explorer, see clipboard_files.rs
+interface ClipboardFile {
+    path: string;
+    name: string;
+    kind: "document" | "folder" | "archive" | "unsupported";
+}
+
+// `popup-data` payload, see PopupData in popup.rs
+interface PopupData {
+    text: string;
+    detectedLang: string | null;
+    source: "clipboard" | "selection" | "files";
+    capturedAt: number;
+    sourceLang: string;
+    targetLang: string;
+    files: ClipboardFile[];
+}
+
+// `accessibility-{label}` payload, see accessibility.rs
+type Announcement =
+    | { kind: "started"; sourceLang: string; targetLang: string }
+    | { kind: "progress"; percent: number }
+    | { kind: "completed"; status: "completed" | "cancelled"; characters: number; preview: string; focusResult: boolean };
+
+// OS accessibility preferences, see os_preferences.rs
+interface OsPreferences {
+    reducedMotion: boolean;
+    highContrast: boolean;
+}
+
+function applyOsPreferences(prefs: OsPreferences) {
+    document.documentElement.classList.toggle("reduce-motion", prefs.reducedMotion);
+    document.documentElement.classList.toggle("high-contrast", prefs.highContrast);
+}
+
 export default function Popup() {
     const [text, setText] = useState("");
     const [translation, setTranslation] = useState("");
     const [loading, setLoading] = useState(false);
     const [error, setError] = useState<string | null>(null);
+    // Captures waiting while this popup translates, and jobs ahead of ours in the backend queue
+    const [queue, setQueue] = useState<PopupData[]>([]);
+    const [jobsAhead, setJobsAhead] = useState(0);
+    // Chunks the backend kept untranslated or cut short
+    const [chunkIssues, setChunkIssues] = useState(0);
+    // The copied text tried to steer the model instead of being translated (see prompt_guard.rs)
+    const [securityWarning, setSecurityWarning] = useState(false);
+    // The last translation was cancelled; what was shown is partial
+    const [cancelled, setCancelled] = useState(false);
+    const [direction, setDirection] = useState<"ltr" | "rtl">("ltr");
+    // Read out by screen readers instead of the streamed text
+    const [announcement, setAnnouncement] = useState("");
+    const resultRef = useRef<HTMLDivElement>(null);
+    // Copied files offered for translation, with where each result was written
+    const [files, setFiles] = useState<ClipboardFile[]>([]);
+    const [fileResults, setFileResults] = useState<Record<string, string>>({});
 
 
     const [fontSize, setFontSize] = useState(16); // Default font size
@@ -29,6 +80,7 @@ export default function Popup() {
     const [showModelMenu, setShowModelMenu] = useState(false);
     const [showAppMenu, setShowAppMenu] = useState(false);
     const [showSettings, setShowSettings] = useState(false);
+    const [pinned, setPinned] = useState(false);
     const [startInTray, setStartInTray] = useState(() => {
         if (typeof window !== "undefined" && window.localStorage) {
             return window.localStorage.getItem("startInTray") === "true";
@@ -61,30 +113,62 @@ export default function Popup() {
             document.documentElement.classList.add("dark");
         }
 
-        // Listen for data from backend
-        const unlistenPromise = listen<string>("popup-data", (event) => {
-            setText(event.payload);
-            setTranslation("");
-            setLoading(true);
-            setError(null);
+        // Listen for data from backend (sent to this popup window only)
+        const unlistenPromise = appWindow.listen<PopupData>("popup-data", (event) => startLookup(event.payload));
 
+        // A newly spawned popup was opened before it could listen; fetch its capture
+        invoke<PopupData | null>("take_pending_popup_data").then((data) => {
+            if (data) startLookup(data);
+        });
 
-            // Trigger translation immediately
-            translateText(event.payload, sourceLang, targetLang, modelId);
+        const unlistenQueuePromise = appWindow.listen<PopupData[]>("popup-queue", (event) => setQueue(event.payload));
+        const unlistenQueuedPromise = listen<{ ahead: number }>(`translation-queued-${appWindow.label}`, (event) => setJobsAhead(event.payload.ahead));
+        const unlistenChunkErrorPromise = listen<{ chunk: number; error: string; action: string }>(`translation-chunk-error-${appWindow.label}`, (event) => {
+            console.warn(`Chunk ${event.payload.chunk} ${event.payload.action}:`, event.payload.error);
+            if (event.payload.action !== "retrying") setChunkIssues((n) => n + 1);
+        });
+
+        const unlistenSecurityPromise = listen<{ chunk: number; violation: string; action: string; discard: number }>(`translation-security-warning-${appWindow.label}`, (event) => {
+            console.warn(`Chunk ${event.payload.chunk} ${event.payload.violation}, ${event.payload.action}`);
+            const discard = event.payload.discard;
+            if (discard > 0) setTranslation((prev) => prev.slice(0, Math.max(0, prev.length - discard)));
+            setSecurityWarning(true);
         });
 
         // Listen for translation chunks (streaming)
-        const unlistenTranslationPromise = listen<{ chunk: string; is_last: boolean }>("translation-event-popup", (event) => {
+        const unlistenTranslationPromise = listen<{ chunk: string; is_last: boolean; status?: "completed" | "cancelled"; direction?: "ltr" | "rtl" }>(`translation-event-${appWindow.label}`, (event) => {
+            setJobsAhead(0);
+            if (event.payload.direction) {
+                setDirection(event.payload.direction);
+            }
             if (event.payload.chunk) {
                 setTranslation((prev) => prev + event.payload.chunk);
             }
             if (event.payload.is_last) {
                 setLoading(false);
+                setCancelled(event.payload.status === "cancelled");
             }
         });
 
 
 
+        const unlistenAccessibilityPromise = listen<Announcement>(`accessibility-${appWindow.label}`, (event) => {
+            const a = event.payload;
+            if (a.kind === "started") {
+                setAnnouncement(`Translating from ${a.sourceLang} to ${a.targetLang}`);
+            } else if (a.kind === "progress") {
+                setAnnouncement(`${a.percent}% translated`);
+            } else {
+                setAnnouncement(a.status === "cancelled"
+                    ? `Translation cancelled after ${a.characters} characters`
+                    : `Translation complete, ${a.characters} characters: ${a.preview}`);
+                if (a.focusResult) resultRef.current?.focus();
+            }
+        });
+
+        invoke<OsPreferences>("get_os_preferences").then(applyOsPreferences).catch(console.error);
+        const unlistenOsPreferencesPromise = listen<OsPreferences>("os-preferences-changed", (event) => applyOsPreferences(event.payload));
+
         // Listen for theme changes from main window
         const unlistenThemePromise = listen<string>("theme-changed", (event) => {
             const newTheme = event.payload;
@@ -122,6 +206,12 @@ export default function Popup() {
         return () => {
             unlistenPromise.then((unlisten) => unlisten());
             unlistenTranslationPromise.then((unlisten) => unlisten());
+            unlistenQueuePromise.then((unlisten) => unlisten());
+            unlistenQueuedPromise.then((unlisten) => unlisten());
+            unlistenChunkErrorPromise.then((unlisten) => unlisten());
+            unlistenSecurityPromise.then((unlisten) => unlisten());
+            unlistenAccessibilityPromise.then((unlisten) => unlisten());
+            unlistenOsPreferencesPromise.then((unlisten) => unlisten());
 
             unlistenThemePromise.then((unlisten) => unlisten());
             window.removeEventListener("keydown", handleKeyDown);
@@ -143,10 +233,23 @@ export default function Popup() {
                 e.preventDefault();
                 cycleMode();
             }
+            if (e.ctrlKey && (e.key === "l" || e.key === "L")) {
+                e.preventDefault();
+                cycleTarget();
+            }
             if (e.ctrlKey && (e.key === "q" || e.key === "Q")) {
                 e.preventDefault();
                 appWindow.hide();
             }
+            // Ctrl+Shift+C: copy & close, Ctrl+Shift+V: paste into the previous window
+            if (e.ctrlKey && e.shiftKey && (e.key === "c" || e.key === "C")) {
+                e.preventDefault();
+                if (translation) handleCopy();
+            }
+            if (e.ctrlKey && e.shiftKey && (e.key === "v" || e.key === "V")) {
+                e.preventDefault();
+                if (translation) handlePaste();
+            }
             if (e.ctrlKey && e.key === "Enter") {
                 e.preventDefault();
                 if (text) {
@@ -158,7 +261,7 @@ export default function Popup() {
         };
         window.addEventListener("keydown", handleShortcut);
         return () => window.removeEventListener("keydown", handleShortcut);
-    }, [sourceLang, targetLang, text, modelId]); // Need text to re-translate
+    }, [sourceLang, targetLang, text, modelId, translation]); // Need text to re-translate, translation to copy
 
 
     useEffect(() => {
@@ -179,6 +282,36 @@ export default function Popup() {
         return () => window.removeEventListener("wheel", handleWheel);
     }, [startInTray, defaultModel]);
 
+    const startLookup = (data: PopupData) => {
+        setSourceLang(data.sourceLang);
+        setTargetLang(data.targetLang);
+        setTranslation("");
+        setError(null);
+        setJobsAhead(0);
+        setChunkIssues(0);
+        setSecurityWarning(false);
+        setCancelled(false);
+        setFiles(data.files);
+        setFileResults({});
+
+        // Files are only translated when the user asks for it
+        if (data.source === "files") {
+            setText("");
+            setLoading(false);
+            return;
+        }
+        setText(data.text);
+        setLoading(true);
+
+        // Trigger translation immediately, in the suggested direction
+        translateText(data.text, data.sourceLang, data.targetLang, modelId);
+    };
+
+    const showNextQueued = async () => {
+        const next = await invoke<PopupData | null>("take_next_popup_data");
+        if (next) startLookup(next);
+    };
+
     async function translateText(sourceText: string, src: string, tgt: string, model: string = modelId) {
         if (!sourceText.trim()) return;
 
@@ -196,6 +329,39 @@ export default function Popup() {
         }
     }
 
+    // Text files go through translate_document, folders and ZIP archives through translate_batch
+    const translateFiles = async () => {
+        setLoading(true);
+        setError(null);
+        for (const file of files) {
+            if (file.kind === "unsupported") continue;
+            setTranslation("");
+            setFileResults((prev) => ({ ...prev, [file.path]: "Translating..." }));
+            try {
+                const args = { path: file.path, sourceLang, targetLang, modelId };
+                const output = file.kind === "document"
+                    ? await invoke<string>("translate_document", args)
+                    : (await invoke<{ outputDir: string }>("translate_batch", args)).outputDir;
+                setFileResults((prev) => ({ ...prev, [file.path]: `→ ${output}` }));
+            } catch (err) {
+                setFileResults((prev) => ({ ...prev, [file.path]: `Error: ${err}` }));
+            }
+        }
+        setLoading(false);
+    };
+
+    // Next target from the user's list in settings (Ctrl+L), see languages.rs
+    const cycleTarget = async () => {
+        const next = await invoke<string>("next_target_language", { current: targetLang, sourceLang });
+        if (next === targetLang) return;
+        setTargetLang(next);
+        if (text) {
+            setTranslation("");
+            setLoading(true);
+            translateText(text, sourceLang, next, modelId);
+        }
+    };
+
     const cycleMode = () => {
         const modes = ["nano", "light", "balanced", "high"];
         const currentIndex = modes.indexOf(modelId);
@@ -224,9 +390,20 @@ export default function Popup() {
         }
     };
 
+    const togglePinned = () => {
+        const next = !pinned;
+        setPinned(next);
+        invoke("set_popup_pinned", { pinned: next }).catch(console.error);
+    };
+
+    // Clipboard, hiding and pasting go through the backend (see popup.rs), which
+    // can also give focus back to the window the text came from
     const handleCopy = () => {
-        navigator.clipboard.writeText(translation);
-        appWindow.hide();
+        invoke("popup_copy_and_close", { text: translation }).catch(console.error);
+    };
+
+    const handlePaste = () => {
+        invoke("popup_paste_and_close", { text: translation }).catch(console.error);
     };
 
     return (
@@ -312,9 +489,20 @@ export default function Popup() {
                     )}
                 </div>
 
-                <button onClick={() => appWindow.hide()} className="opacity-50 hover:opacity-100 transition-opacity p-1 rounded-md hover:bg-white/10">
-                    <span className="material-icons text-lg">close</span>
-                </button>
+                <div className="flex items-center gap-1">
+                    {queue.length > 0 && (
+                        <button onClick={showNextQueued} title={`Next: ${queue[0].text.slice(0, 60)}`} className="opacity-70 hover:opacity-100 transition-opacity px-1 rounded-md hover:bg-white/10 flex items-center text-xs">
+                            <span className="material-icons text-lg">skip_next</span>
+                            {queue.length}
+                        </button>
+                    )}
+                    <button onClick={togglePinned} title={pinned ? "Unpin" : "Pin"} className={`hover:opacity-100 transition-opacity p-1 rounded-md hover:bg-white/10 ${pinned ? 'opacity-100' : 'opacity-50'}`}>
+                        <span className="material-icons text-lg">push_pin</span>
+                    </button>
+                    <button onClick={() => appWindow.hide()} className="opacity-50 hover:opacity-100 transition-opacity p-1 rounded-md hover:bg-white/10">
+                        <span className="material-icons text-lg">close</span>
+                    </button>
+                </div>
             </div>
 
             {/* Source Text Removed as per request */}
@@ -323,9 +511,36 @@ export default function Popup() {
 
             {/* Translation */}
             <div className="w-full flex-1 overflow-y-auto mb-4">
+                {files.length > 0 && (
+                    <div className="flex flex-col gap-1 mb-3 text-sm">
+                        {files.map((file) => (
+                            <div key={file.path} className={`flex items-center gap-2 ${file.kind === "unsupported" ? "opacity-40" : ""}`} title={file.path}>
+                                <span className="material-icons text-base">
+                                    {file.kind === "folder" ? "folder" : (file.kind === "archive" ? "folder_zip" : "description")}
+                                </span>
+                                <span className="truncate">{file.name}</span>
+                                <span className="text-xs text-gray-500 truncate ml-auto">
+                                    {fileResults[file.path] ?? (file.kind === "unsupported" ? "Not supported" : "")}
+                                </span>
+                            </div>
+                        ))}
+                        {!loading && Object.keys(fileResults).length === 0 && (
+                            <button
+                                onClick={translateFiles}
+                                disabled={files.every((file) => file.kind === "unsupported")}
+                                className="self-start mt-2 px-3 py-1 bg-[#258cf4] text-white rounded-lg text-xs font-medium disabled:opacity-40"
+                            >
+                                Translate {files.length > 1 ? `${files.length} files` : "file"}
+                            </button>
+                        )}
+                    </div>
+                )}
                 {translation ? (
                     <div
-                        className="font-light leading-relaxed whitespace-pre-wrap transition-all duration-200"
+                        ref={resultRef}
+                        tabIndex={-1}
+                        dir={direction}
+                        className="outline-none font-light leading-relaxed whitespace-pre-wrap transition-all duration-200"
                         style={{ fontSize: `${fontSize}px` }}
                     >
                         {translation}
@@ -336,11 +551,15 @@ export default function Popup() {
                             <div className="w-2 h-2 bg-blue-500 rounded-full animate-bounce" style={{ animationDelay: '0ms' }}></div>
                             <div className="w-2 h-2 bg-blue-500 rounded-full animate-bounce" style={{ animationDelay: '150ms' }}></div>
                             <div className="w-2 h-2 bg-blue-500 rounded-full animate-bounce" style={{ animationDelay: '300ms' }}></div>
-                            Translating...
+                            {jobsAhead > 0 ? `Waiting for ${jobsAhead} earlier translation${jobsAhead > 1 ? "s" : ""}...` : "Translating..."}
                         </div>
                     )
                 )}
+                <div className="sr-only" role="status" aria-live="polite">{announcement}</div>
                 {error && <div className="text-red-400 text-sm">Error: {error}</div>}
+                {securityWarning && <div className="text-yellow-500 text-xs mt-2">The copied text tried to instruct the model; affected parts were retranslated or left as is</div>}
+                {cancelled && <div className="text-gray-500 text-xs mt-2">Cancelled; the translation above is partial</div>}
+                {chunkIssues > 0 && <div className="text-yellow-500 text-xs mt-2">{chunkIssues} part{chunkIssues > 1 ? "s" : ""} could not be fully translated</div>}
             </div>
 
             {/* Footer Actions */}
@@ -352,6 +571,7 @@ export default function Popup() {
 
                 <button
                     onClick={handleCopy}
+                    title="Ctrl+Shift+C (Ctrl+Shift+V pastes into the previous window)"
                     className="px-4 py-2 bg-white text-black rounded-lg text-sm font-medium hover:bg-gray-200 transition-colors flex items-center gap-2 shrink-0"
                 >
                     <span className="material-icons text-base">content_copy</span>
@@ -0,0 +1,27 @@
+import { invoke } from "@tauri-apps/api/core";
+import "./App.css";
+
+// Floating button shown next to a fresh mouse selection (see selection.rs)
+export default function SelectionButton() {
+    return (
+        <button
+            onMouseDown={(e) => e.preventDefault()}
+            onClick={() => invoke("confirm_selection_button").catch(console.error)}
+            onContextMenu={(e) => {
+                e.preventDefault();
+                invoke("dismiss_selection_button").catch(console.error);
+            }}
+            title="Translate selection (or press Shift twice)"
+            className="w-8 h-8 flex items-center justify-center rounded-full bg-blue-600 hover:bg-blue-500 text-white shadow-lg cursor-pointer select-none"
+        >
+            <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round">
+                <path d="M5 8l6 6" />
+                <path d="M4 14l6-6 2-3" />
+                <path d="M2 5h12" />
+                <path d="M7 2h1" />
+                <path d="M22 22l-5-10-5 10" />
+                <path d="M14 18h6" />
+            </svg>
+        </button>
+    );
+}
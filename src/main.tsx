@@ -4,6 +4,7 @@ import App from "./App.tsx";
 import "./index.css";
 
 import Popup from "./Popup";
+import SelectionButton from "./SelectionButton";
 
 if (window.location.pathname === "/popup") {
     ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
@@ -11,6 +12,12 @@ if (window.location.pathname === "/popup") {
             <Popup />
         </React.StrictMode>,
     );
+} else if (window.location.pathname === "/selection-button") {
+    ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
+        <React.StrictMode>
+            <SelectionButton />
+        </React.StrictMode>,
+    );
 } else {
     ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
         <React.StrictMode>